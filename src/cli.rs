@@ -0,0 +1,213 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+};
+
+use libc::{SIGHUP, SIGQUIT, SIGTERM, c_int};
+
+pub const USAGE: &str = "\
+Usage: rustify [-?hVt] [-s signal] [-p prefix] [-c filename] [--workers N]
+
+Options:
+  -?,-h         : this help
+  -V            : show version and build information, then exit
+  -t            : test configuration and exit
+  -s signal     : send signal to a master process: stop, quit, reload
+  -p prefix     : set prefix path for relative paths (default: current directory)
+  -c filename   : set configuration file (default: conf/rustify.conf)
+  --workers N   : override worker_processes from the configuration file";
+
+//-s로 실행 중인 마스터에 보낼 신호 (nginx와 같은 의미)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    Stop,   //SIGTERM: 즉시 종료
+    Quit,   //SIGQUIT: 처리 중인 요청을 마치고 종료
+    Reload, //SIGHUP: 설정 다시 읽기
+}
+
+impl Signal {
+    fn from_name(name: &str) -> Option<Signal> {
+        match name {
+            "stop" => Some(Signal::Stop),
+            "quit" => Some(Signal::Quit),
+            "reload" => Some(Signal::Reload),
+            _ => None,
+        }
+    }
+
+    pub fn as_raw(&self) -> c_int {
+        match self {
+            Signal::Stop => SIGTERM,
+            Signal::Quit => SIGQUIT,
+            Signal::Reload => SIGHUP,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Options {
+    pub help: bool,
+    pub version: bool,
+    pub test_config: bool,
+    pub signal: Option<Signal>,
+    pub prefix: Option<PathBuf>,
+    pub config_path: Option<PathBuf>,
+    pub workers: Option<usize>,
+}
+
+impl Options {
+    //설정 파일 경로 (상대 경로면 prefix 기준)
+    pub fn config_path(&self, default: &str) -> PathBuf {
+        let path = self
+            .config_path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(default));
+        self.prefix().join(path)
+    }
+
+    pub fn prefix(&self) -> PathBuf {
+        self.prefix.clone().unwrap_or_default()
+    }
+}
+
+/*
+ 명령행 인자 파싱 (프로그램 이름 제외)
+ - 값을 받는 옵션은 "-c file"과 "-cfile" 둘 다 허용 (nginx와 같음)
+ - --workers는 "--workers N"과 "--workers=N" 허용
+*/
+pub fn parse_args<I: Iterator<Item = String>>(mut args: I) -> std::result::Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        if let Some(long) = arg.strip_prefix("--") {
+            let (name, inline) = match long.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (long, None),
+            };
+
+            match name {
+                "workers" => {
+                    let value = inline
+                        .or_else(|| args.next())
+                        .ok_or("option \"--workers\" requires a number")?;
+                    let workers = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|n| *n > 0)
+                        .ok_or(format!("invalid worker count \"{}\"", value))?;
+                    options.workers = Some(workers);
+                }
+                "help" => options.help = true,
+                "version" => options.version = true,
+                _ => return Err(format!("invalid option: \"{}\"", arg)),
+            }
+            continue;
+        }
+
+        let Some(flags) = arg.strip_prefix('-').filter(|flags| !flags.is_empty()) else {
+            return Err(format!("invalid option: \"{}\"", arg));
+        };
+
+        for (i, flag) in flags.char_indices() {
+            match flag {
+                '?' | 'h' => options.help = true,
+                'V' | 'v' => options.version = true,
+                't' => options.test_config = true,
+                's' | 'p' | 'c' => {
+                    //값이 붙어 있으면 나머지 전체, 아니면 다음 인자
+                    let rest = &flags[i + flag.len_utf8()..];
+                    let value = if rest.is_empty() {
+                        args.next()
+                            .ok_or(format!("option \"-{}\" requires parameter", flag))?
+                    } else {
+                        rest.to_string()
+                    };
+
+                    match flag {
+                        's' => {
+                            options.signal = Some(Signal::from_name(&value).ok_or(format!(
+                                "invalid option: \"-s {}\" (expected stop, quit or reload)",
+                                value
+                            ))?);
+                        }
+                        'p' => options.prefix = Some(PathBuf::from(value)),
+                        _ => options.config_path = Some(PathBuf::from(value)),
+                    }
+                    break;
+                }
+                _ => return Err(format!("invalid option: \"{}\"", arg)),
+            }
+        }
+    }
+
+    Ok(options)
+}
+
+//-V: 버전과 빌드 정보
+pub fn print_version() {
+    println!(
+        "rustify version: {}/{}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    println!(
+        "built for {}-{} ({} build)",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        }
+    );
+
+    let mut features: Vec<&str> = Vec::new();
+    if cfg!(feature = "io-uring") {
+        features.push("io-uring");
+    }
+    println!(
+        "features: {}",
+        if features.is_empty() {
+            "(none)".to_string()
+        } else {
+            features.join(", ")
+        }
+    );
+}
+
+//pid 파일에 적힌 마스터 프로세스에 신호 전송
+pub fn send_signal(pid_path: &Path, signal: Signal) -> Result<()> {
+    let content = fs::read_to_string(pid_path).map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("open() \"{}\" failed ({})", pid_path.display(), e),
+        )
+    })?;
+
+    let pid: i32 = content
+        .trim()
+        .parse()
+        .ok()
+        .filter(|pid| *pid > 0)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "invalid PID number \"{}\" in \"{}\"",
+                    content.trim(),
+                    pid_path.display()
+                ),
+            )
+        })?;
+
+    if unsafe { libc::kill(pid, signal.as_raw()) } < 0 {
+        let e = Error::last_os_error();
+        return Err(Error::new(
+            e.kind(),
+            format!("kill({}, {}) failed ({})", pid, signal.as_raw(), e),
+        ));
+    }
+
+    Ok(())
+}
@@ -1,4 +0,0 @@
-pub const HOST_IP: &str = "0.0.0.0";
-pub const HOST_PORT: &str = "7878";
-// pub const HOST_ADDR: &str = "0.0.0.0:7878";
-pub const HOST_ADDR: &str = "127.0.0.1:7879";
@@ -0,0 +1,1967 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    net::{SocketAddr, ToSocketAddrs},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use Rustify::http::StatusCode;
+
+use crate::{
+    config::{
+        parser::{Arg, ConfigError, Directive, Position, parse},
+        settings::{
+            AcceptStrategy, Address, Balance, CircuitBreakerConfig, Config, EventMethod,
+            EventsConfig, Expires, FilesConfig, HealthCheckConfig, HttpConfig, KeepaliveConfig,
+            ListenConfig, LocationConfig, NextUpstream, OpenFileCacheConfig, ProxyBuffering,
+            ProxyConfig, ProxyTimeouts, ReturnConfig, Segment, ServerConfig, UpstreamConfig,
+            UpstreamServer, Variable,
+        },
+    },
+    utils::system::get_cpu_count,
+};
+
+//listen 기본 backlog (nginx와 같은 511)
+const DEFAULT_BACKLOG: i32 = 511;
+
+//마스터 pid 파일 기본 경로 (prefix 기준)
+const DEFAULT_PID_PATH: &str = "rustify.pid";
+
+//index 지시어가 없을 때 디렉터리 요청에 찾아볼 파일
+const DEFAULT_INDEX: &str = "index.html";
+
+//open_file_cache의 inactive 기본값
+const DEFAULT_OPEN_FILE_CACHE_INACTIVE: Duration = Duration::from_secs(60);
+
+//upstream server의 max_fails, fail_timeout 기본값
+const DEFAULT_MAX_FAILS: u32 = 1;
+const DEFAULT_FAIL_TIMEOUT: Duration = Duration::from_secs(10);
+
+//upstream server weight 최댓값 (consistent 해시 링 크기가 가중치에 비례하므로 제한)
+const MAX_WEIGHT: u32 = 1000;
+
+//health_check 기본값 (상태 코드는 2xx, 3xx면 정상)
+const DEFAULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(1);
+const DEFAULT_HEALTH_CHECK_STATUS: (u16, u16) = (200, 399);
+
+//circuit_breaker의 timeout 기본값
+const DEFAULT_CIRCUIT_BREAKER_TIMEOUT: Duration = Duration::from_secs(30);
+
+//upstream의 keepalive(서버당 유휴 연결 수), keepalive_timeout 기본값
+const DEFAULT_UPSTREAM_KEEPALIVE: usize = 100;
+const DEFAULT_UPSTREAM_KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(60);
+
+//graceful 종료 시 워커가 처리 중인 연결을 기다리는 기본 시간
+const DEFAULT_WORKER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+//알려진 지시어 이름 (모르는 지시어와 위치가 잘못된 지시어를 구분하기 위함)
+const KNOWN_DIRECTIVES: &[&str] = &[
+    "pid",
+    "worker_processes",
+    "thread_pool_size",
+    "worker_shutdown_timeout",
+    "events",
+    "worker_connections",
+    "use",
+    "accept_strategy",
+    "http",
+    "keepalive_timeout",
+    "keepalive_requests",
+    "client_timeout",
+    "send_timeout",
+    "client_max_body_size",
+    "sendfile",
+    "open_file_cache",
+    "open_file_cache_valid",
+    "open_file_cache_errors",
+    "upstream",
+    "least_conn",
+    "ip_hash",
+    "hash",
+    "random",
+    "health_check",
+    "circuit_breaker",
+    "keepalive",
+    "server",
+    "listen",
+    "server_name",
+    "location",
+    "return",
+    "proxy_pass",
+    "proxy_next_upstream",
+    "proxy_next_upstream_tries",
+    "proxy_next_upstream_timeout",
+    "proxy_connect_timeout",
+    "proxy_send_timeout",
+    "proxy_read_timeout",
+    "proxy_total_timeout",
+    "proxy_request_buffering",
+    "proxy_buffering",
+    "proxy_buffers",
+    "proxy_max_temp_file_size",
+    "proxy_temp_path",
+    "root",
+    "index",
+    "expires",
+    "cache_control",
+    "error_page",
+];
+
+/*
+ 설정 파일을 읽어서 해석 (에러는 "경로:줄:칸: 메시지" 형식)
+ - prefix: 설정 안의 상대 경로(pid 등)의 기준 디렉터리
+*/
+pub fn load(path: &Path, prefix: &Path) -> Result<Config, ConfigError> {
+    let display = path.display().to_string();
+
+    let source = fs::read_to_string(path).map_err(|e| {
+        ConfigError::message(format!("failed to read configuration file: {}", e))
+            .with_path(&display)
+    })?;
+
+    load_str(&source, prefix).map_err(|e| e.with_path(&display))
+}
+
+fn load_str(source: &str, prefix: &Path) -> Result<Config, ConfigError> {
+    let directives = parse(source)?;
+
+    let mut pid: PathBuf = PathBuf::from(DEFAULT_PID_PATH);
+    let mut worker_processes: Option<usize> = None;
+    let mut thread_pool_size: usize = 4;
+    let mut worker_shutdown_timeout: Duration = DEFAULT_WORKER_SHUTDOWN_TIMEOUT;
+    let mut events = EventsConfig::default();
+    let mut http: Option<(HttpConfig, Vec<ServerListens>)> = None;
+
+    let mut seen = Seen::default();
+
+    for directive in &directives {
+        seen.check(directive)?;
+
+        match directive.name.as_str() {
+            "pid" => {
+                expect_args(directive, 1, 1)?;
+                pid = PathBuf::from(&directive.args[0].value);
+            }
+            "worker_processes" => {
+                expect_args(directive, 1, 1)?;
+                let arg = &directive.args[0];
+                worker_processes = Some(if arg.value == "auto" {
+                    get_cpu_count()
+                } else {
+                    parse_positive(directive, arg)?
+                });
+            }
+            "thread_pool_size" => {
+                expect_args(directive, 1, 1)?;
+                thread_pool_size = parse_positive(directive, &directive.args[0])?;
+            }
+            "worker_shutdown_timeout" => {
+                expect_args(directive, 1, 1)?;
+                worker_shutdown_timeout = parse_time(directive, &directive.args[0])?;
+            }
+            "events" => {
+                events = parse_events(directive)?;
+            }
+            "http" => {
+                http = Some(parse_http(directive)?);
+            }
+            _ => return Err(unexpected(directive)),
+        }
+    }
+
+    let Some((mut http, server_listens)) = http else {
+        return Err(ConfigError::message("no \"http\" block in configuration"));
+    };
+    let mut listeners = collect_listeners(&server_listens)?;
+
+    //Unix 소켓, 정적 파일 루트, proxy_temp_path의 상대 경로는 prefix 기준
+    for listen in &mut listeners {
+        if let Address::Unix(path) = &mut listen.addr {
+            *path = prefix.join(&*path);
+        }
+    }
+    for server in http
+        .upstreams
+        .iter_mut()
+        .flat_map(|upstream| &mut upstream.servers)
+    {
+        if let Address::Unix(path) = &mut server.addr {
+            *path = prefix.join(&*path);
+        }
+    }
+    for location in http
+        .servers
+        .iter_mut()
+        .flat_map(|server| &mut server.locations)
+    {
+        if let Some(proxy) = &mut location.proxy {
+            proxy.buffering.temp_path = prefix.join(&proxy.buffering.temp_path);
+        }
+    }
+    for server in &mut http.servers {
+        let roots = server
+            .locations
+            .iter_mut()
+            .map(|location| &mut location.files.root);
+        for root in std::iter::once(&mut server.files.root)
+            .chain(roots)
+            .flatten()
+        {
+            *root = prefix.join(&*root);
+        }
+    }
+
+    Ok(Config {
+        pid: prefix.join(pid),
+        worker_processes: worker_processes.unwrap_or_else(get_cpu_count),
+        thread_pool_size,
+        worker_shutdown_timeout,
+        events,
+        http,
+        listeners,
+    })
+}
+
+fn parse_events(directive: &Directive) -> Result<EventsConfig, ConfigError> {
+    let block = expect_block(directive, 0)?;
+    let mut events = EventsConfig::default();
+    let mut seen = Seen::default();
+
+    for directive in block {
+        seen.check(directive)?;
+
+        match directive.name.as_str() {
+            "worker_connections" => {
+                expect_args(directive, 1, 1)?;
+                events.worker_connections = parse_positive(directive, &directive.args[0])?;
+            }
+            "use" => {
+                expect_args(directive, 1, 1)?;
+                events.method = parse_event_method(directive, &directive.args[0])?;
+            }
+            "accept_strategy" => {
+                expect_args(directive, 1, 2)?;
+                events.accept_strategy = parse_accept_strategy(directive)?;
+            }
+            _ => return Err(unexpected(directive)),
+        }
+    }
+
+    Ok(events)
+}
+
+fn parse_event_method(directive: &Directive, arg: &Arg) -> Result<EventMethod, ConfigError> {
+    match arg.value.as_str() {
+        #[cfg(target_os = "linux")]
+        "epoll" => Ok(EventMethod::Poller),
+        #[cfg(target_os = "macos")]
+        "kqueue" => Ok(EventMethod::Poller),
+        #[cfg(all(target_os = "linux", feature = "io-uring"))]
+        "io_uring" => Ok(EventMethod::IoUring),
+        #[cfg(all(target_os = "linux", not(feature = "io-uring")))]
+        "io_uring" => Err(ConfigError::new(
+            arg.position,
+            "io_uring support is not compiled in (build with --features io-uring)",
+        )),
+        _ => Err(invalid_value(directive, arg)),
+    }
+}
+
+/*
+ accept_strategy shared | reuseport [steer=cpu]
+ - reuseport는 커널이 SO_REUSEPORT 소켓 사이에 연결을 분배하는 Linux에서만 지원
+*/
+fn parse_accept_strategy(directive: &Directive) -> Result<AcceptStrategy, ConfigError> {
+    let arg = &directive.args[0];
+    let params = &directive.args[1..];
+
+    match arg.value.as_str() {
+        "shared" if params.is_empty() => Ok(AcceptStrategy::Shared),
+        "shared" => Err(invalid_args(directive)),
+        #[cfg(target_os = "linux")]
+        "reuseport" => {
+            let mut steer_by_cpu = false;
+
+            for param in params {
+                match param.value.as_str() {
+                    "steer=cpu" => steer_by_cpu = true,
+                    _ => {
+                        return Err(ConfigError::new(
+                            param.position,
+                            format!("invalid parameter \"{}\"", param.value),
+                        ));
+                    }
+                }
+            }
+
+            Ok(AcceptStrategy::Reuseport { steer_by_cpu })
+        }
+        #[cfg(not(target_os = "linux"))]
+        "reuseport" => Err(ConfigError::new(
+            arg.position,
+            "\"reuseport\" accept strategy is not supported on this platform",
+        )),
+        _ => Err(invalid_value(directive, arg)),
+    }
+}
+
+//server 블록마다 listen 주소 목록 (server 인덱스와 같은 순서)
+type ServerListens = Vec<Listen>;
+
+struct Listen {
+    addr: Address,
+    default_server: bool,
+    options: ListenOptions,
+    position: Position, //에러 위치 보고용
+}
+
+//listen 소켓 옵션 (지정하지 않은 값은 None, 같은 주소의 listen 중 하나에만 지정 가능)
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ListenOptions {
+    backlog: Option<i32>,
+    ipv6only: Option<bool>,
+    mode: Option<u32>,
+    rcvbuf: Option<i32>,
+    sndbuf: Option<i32>,
+    keepalive: Option<KeepaliveConfig>,
+    tcp_nodelay: Option<bool>,
+    #[cfg(target_os = "linux")]
+    deferred: bool,
+    #[cfg(target_os = "linux")]
+    fastopen: Option<i32>,
+    #[cfg(target_os = "linux")]
+    accept4: Option<bool>,
+}
+
+impl ListenOptions {
+    //기본값을 채워서 ListenConfig로
+    fn into_config(self, addr: Address, servers: Vec<usize>) -> ListenConfig {
+        ListenConfig {
+            addr,
+            backlog: self.backlog.unwrap_or(DEFAULT_BACKLOG),
+            ipv6only: self.ipv6only.unwrap_or(true),
+            mode: self.mode,
+            rcvbuf: self.rcvbuf,
+            sndbuf: self.sndbuf,
+            keepalive: self.keepalive,
+            tcp_nodelay: self.tcp_nodelay.unwrap_or(true),
+            #[cfg(target_os = "linux")]
+            deferred: self.deferred,
+            #[cfg(target_os = "linux")]
+            fastopen: self.fastopen,
+            #[cfg(target_os = "linux")]
+            accept4: self.accept4.unwrap_or(true),
+            servers,
+        }
+    }
+}
+
+fn parse_http(directive: &Directive) -> Result<(HttpConfig, Vec<ServerListens>), ConfigError> {
+    let block = expect_block(directive, 0)?;
+    let mut http = HttpConfig::default();
+    let mut server_listens: Vec<ServerListens> = Vec::new();
+    let mut seen = Seen::default();
+
+    //proxy_pass가 뒤에 나오는 upstream 이름도 찾을 수 있도록 upstream 블록을 먼저 읽음
+    for directive in block
+        .iter()
+        .filter(|directive| directive.name == "upstream")
+    {
+        let upstream = parse_upstream(directive)?;
+        if http
+            .upstreams
+            .iter()
+            .any(|other| other.name == upstream.name)
+        {
+            return Err(ConfigError::new(
+                directive.position,
+                format!("duplicate upstream \"{}\"", upstream.name),
+            ));
+        }
+        http.upstreams.push(upstream);
+    }
+
+    for directive in block {
+        if directive.name != "server" && directive.name != "upstream" {
+            seen.check(directive)?;
+        }
+
+        match directive.name.as_str() {
+            "keepalive_timeout" => {
+                expect_args(directive, 1, 1)?;
+                http.keepalive_timeout = parse_time(directive, &directive.args[0])?;
+            }
+            "keepalive_requests" => {
+                expect_args(directive, 1, 1)?;
+                http.keepalive_requests = parse_positive(directive, &directive.args[0])?;
+            }
+            "client_timeout" => {
+                expect_args(directive, 1, 1)?;
+                http.client_timeout = parse_time(directive, &directive.args[0])?;
+            }
+            "send_timeout" => {
+                expect_args(directive, 1, 1)?;
+                http.send_timeout = parse_time(directive, &directive.args[0])?;
+            }
+            "client_max_body_size" => {
+                expect_args(directive, 1, 1)?;
+                http.client_max_body_size = parse_size(directive, &directive.args[0])?;
+            }
+            "sendfile" => {
+                expect_args(directive, 1, 1)?;
+                http.sendfile = parse_flag(directive, &directive.args[0])?;
+            }
+            "open_file_cache" => {
+                expect_args(directive, 1, 2)?;
+                http.open_file_cache = parse_open_file_cache(directive)?;
+            }
+            "open_file_cache_valid" => {
+                expect_args(directive, 1, 1)?;
+                http.open_file_cache_valid = parse_time(directive, &directive.args[0])?;
+            }
+            "open_file_cache_errors" => {
+                expect_args(directive, 1, 1)?;
+                http.open_file_cache_errors = parse_flag(directive, &directive.args[0])?;
+            }
+            "upstream" => {}
+            "server" => {
+                let (server, listens) = parse_server(directive, &mut http.upstreams)?;
+                http.servers.push(server);
+                server_listens.push(listens);
+            }
+            _ => return Err(unexpected(directive)),
+        }
+    }
+
+    if http.servers.is_empty() {
+        return Err(ConfigError::new(
+            directive.position,
+            "no \"server\" block in \"http\"",
+        ));
+    }
+
+    Ok((http, server_listens))
+}
+
+/*
+ upstream 이름 {
+     server 주소 [weight=N] [max_fails=N] [fail_timeout=시간]; ...
+     [least_conn; | ip_hash; | hash 키 [consistent]; | random [two];]
+     [health_check ...;]
+     [circuit_breaker failures=N [timeout=시간];]
+     [keepalive N;] [keepalive_timeout 시간;]
+ }
+ - 주소: host[:port] (포트가 없으면 80, 호스트 이름이 여러 주소로 풀리면 주소마다 서버), unix:/path
+ - 분산 방식은 하나만, 없으면 가중치 라운드 로빈
+*/
+fn parse_upstream(directive: &Directive) -> Result<UpstreamConfig, ConfigError> {
+    let block = expect_block(directive, 1)?;
+    let [name] = directive.args.as_slice() else {
+        return Err(invalid_args(directive));
+    };
+
+    let mut upstream = UpstreamConfig {
+        name: name.value.clone(),
+        balance: Balance::RoundRobin,
+        servers: Vec::new(),
+        health_check: None,
+        circuit_breaker: None,
+        keepalive: DEFAULT_UPSTREAM_KEEPALIVE,
+        keepalive_timeout: DEFAULT_UPSTREAM_KEEPALIVE_TIMEOUT,
+    };
+    let mut balanced = false;
+    let mut seen = Seen::default();
+
+    for directive in block {
+        let balance = match directive.name.as_str() {
+            "server" => {
+                expect_args(directive, 1, usize::MAX)?;
+                upstream.servers.extend(parse_upstream_server(directive)?);
+                continue;
+            }
+            "health_check" => {
+                seen.check(directive)?;
+                expect_args(directive, 0, usize::MAX)?;
+                upstream.health_check = Some(parse_health_check(directive)?);
+                continue;
+            }
+            "circuit_breaker" => {
+                seen.check(directive)?;
+                expect_args(directive, 1, 2)?;
+                upstream.circuit_breaker = Some(parse_circuit_breaker(directive)?);
+                continue;
+            }
+            "keepalive" => {
+                seen.check(directive)?;
+                expect_args(directive, 1, 1)?;
+                let arg = &directive.args[0];
+                upstream.keepalive = arg
+                    .value
+                    .parse()
+                    .map_err(|_| invalid_value(directive, arg))?;
+                continue;
+            }
+            "keepalive_timeout" => {
+                seen.check(directive)?;
+                expect_args(directive, 1, 1)?;
+                upstream.keepalive_timeout = parse_time(directive, &directive.args[0])?;
+                continue;
+            }
+            "least_conn" => {
+                expect_args(directive, 0, 0)?;
+                Balance::LeastConn
+            }
+            "ip_hash" => {
+                expect_args(directive, 0, 0)?;
+                Balance::IpHash
+            }
+            "hash" => {
+                expect_args(directive, 1, 2)?;
+                let consistent = match directive.args.get(1) {
+                    Some(arg) if arg.value == "consistent" => true,
+                    Some(arg) => return Err(invalid_value(directive, arg)),
+                    None => false,
+                };
+                Balance::Hash {
+                    key: parse_variables(directive, &directive.args[0])?,
+                    consistent,
+                }
+            }
+            "random" => {
+                expect_args(directive, 0, 1)?;
+                let two = match directive.args.first() {
+                    Some(arg) if arg.value == "two" => true,
+                    Some(arg) => return Err(invalid_value(directive, arg)),
+                    None => false,
+                };
+                Balance::Random { two }
+            }
+            _ => return Err(unexpected(directive)),
+        };
+
+        if balanced {
+            return Err(ConfigError::new(
+                directive.position,
+                "load balancing method redefined",
+            ));
+        }
+        upstream.balance = balance;
+        balanced = true;
+    }
+
+    if upstream.servers.is_empty() {
+        return Err(ConfigError::new(
+            directive.position,
+            "no servers are inside upstream",
+        ));
+    }
+
+    Ok(upstream)
+}
+
+//upstream 안의 server 주소 [weight=N] [max_fails=N] [fail_timeout=시간]
+fn parse_upstream_server(directive: &Directive) -> Result<Vec<UpstreamServer>, ConfigError> {
+    let arg = &directive.args[0];
+    let addrs = parse_upstream_addrs(&arg.value).map_err(|message| {
+        ConfigError::new(
+            arg.position,
+            format!("{} in upstream \"{}\"", message, arg.value),
+        )
+    })?;
+
+    let mut weight: u32 = 1;
+    let mut max_fails = DEFAULT_MAX_FAILS;
+    let mut fail_timeout = DEFAULT_FAIL_TIMEOUT;
+
+    for param in &directive.args[1..] {
+        let (key, value) = param
+            .value
+            .split_once('=')
+            .ok_or_else(|| invalid_value(directive, param))?;
+
+        match key {
+            "weight" => {
+                weight = parse_param(directive, param, value, parse_positive)?
+                    .try_into()
+                    .ok()
+                    .filter(|&weight| weight <= MAX_WEIGHT)
+                    .ok_or_else(|| invalid_value(directive, param))?;
+            }
+            "max_fails" => {
+                max_fails = value.parse().map_err(|_| invalid_value(directive, param))?;
+            }
+            "fail_timeout" => {
+                fail_timeout = parse_param(directive, param, value, parse_time)?;
+            }
+            _ => return Err(invalid_value(directive, param)),
+        }
+    }
+
+    Ok(addrs
+        .into_iter()
+        .map(|addr| UpstreamServer {
+            addr,
+            weight,
+            max_fails,
+            fail_timeout,
+        })
+        .collect())
+}
+
+//circuit_breaker failures=N [timeout=시간]  (timeout 기본 30s)
+fn parse_circuit_breaker(directive: &Directive) -> Result<CircuitBreakerConfig, ConfigError> {
+    let mut failures: Option<u32> = None;
+    let mut timeout = DEFAULT_CIRCUIT_BREAKER_TIMEOUT;
+
+    for param in &directive.args {
+        match param.value.split_once('=') {
+            Some(("failures", value)) => {
+                failures = Some(
+                    parse_param(directive, param, value, parse_positive)?
+                        .try_into()
+                        .map_err(|_| invalid_value(directive, param))?,
+                );
+            }
+            Some(("timeout", value)) => {
+                timeout = parse_param(directive, param, value, parse_time)?;
+                if timeout.is_zero() {
+                    return Err(invalid_value(directive, param));
+                }
+            }
+            _ => return Err(invalid_value(directive, param)),
+        }
+    }
+
+    let Some(failures) = failures else {
+        return Err(ConfigError::new(
+            directive.position,
+            "\"failures\" is required in \"circuit_breaker\" directive",
+        ));
+    };
+
+    Ok(CircuitBreakerConfig { failures, timeout })
+}
+
+//health_check [interval=시간] [timeout=시간] [fails=N] [passes=N] [uri=경로] [status=200,300-399] [body=문자열]
+fn parse_health_check(directive: &Directive) -> Result<HealthCheckConfig, ConfigError> {
+    let mut check = HealthCheckConfig {
+        interval: DEFAULT_HEALTH_CHECK_INTERVAL,
+        timeout: DEFAULT_HEALTH_CHECK_TIMEOUT,
+        fails: 1,
+        passes: 1,
+        uri: String::from("/"),
+        status: vec![DEFAULT_HEALTH_CHECK_STATUS],
+        body: None,
+    };
+
+    for param in &directive.args {
+        let (key, value) = param
+            .value
+            .split_once('=')
+            .ok_or_else(|| invalid_value(directive, param))?;
+
+        match key {
+            "interval" => {
+                check.interval = parse_param(directive, param, value, parse_time)?;
+                if check.interval.is_zero() {
+                    return Err(invalid_value(directive, param));
+                }
+            }
+            "timeout" => {
+                check.timeout = parse_param(directive, param, value, parse_time)?;
+                if check.timeout.is_zero() {
+                    return Err(invalid_value(directive, param));
+                }
+            }
+            "fails" | "passes" => {
+                let count: u32 = parse_param(directive, param, value, parse_positive)?
+                    .try_into()
+                    .map_err(|_| invalid_value(directive, param))?;
+                if key == "fails" {
+                    check.fails = count;
+                } else {
+                    check.passes = count;
+                }
+            }
+            "uri" if value.starts_with('/') => check.uri = value.to_string(),
+            "status" => {
+                check.status = value
+                    .split(',')
+                    .map(|range| {
+                        let (low, high) = range.split_once('-').unwrap_or((range, range));
+                        let code = |code: &str| {
+                            code.parse::<u16>()
+                                .ok()
+                                .filter(|code| (100..=599).contains(code))
+                        };
+                        match (code(low), code(high)) {
+                            (Some(low), Some(high)) if low <= high => Ok((low, high)),
+                            _ => Err(invalid_value(directive, param)),
+                        }
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            "body" if !value.is_empty() => check.body = Some(value.to_string()),
+            _ => return Err(invalid_value(directive, param)),
+        }
+    }
+
+    Ok(check)
+}
+
+/*
+ 업스트림 주소: host[:port] | unix:/path
+ - 포트가 없으면 80, 호스트 이름은 모든 주소로 풀어 둠 (중복 제거, 순서 유지)
+ - 에러는 호출한 지시어에 맞게 감싸도록 메시지만 돌려줌
+*/
+fn parse_upstream_addrs(value: &str) -> Result<Vec<Address>, &'static str> {
+    if let Some(path) = value.strip_prefix("unix:") {
+        if path.is_empty() {
+            return Err("no path");
+        }
+        return Ok(vec![Address::Unix(PathBuf::from(path))]);
+    }
+
+    if value.is_empty() {
+        return Err("no host");
+    }
+
+    //포트가 없으면 80 (IPv6 리터럴 [::1] 고려)
+    let value = match value.rfind(':') {
+        Some(i) if !value[i..].contains(']') => value.to_string(),
+        _ => format!("{}:80", value),
+    };
+
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Ok(vec![Address::Tcp(addr)]);
+    }
+
+    let (host, port) = value.rsplit_once(':').unwrap();
+    let port: u16 = port.parse().map_err(|_| "invalid port")?;
+    if host.starts_with('[') || host.is_empty() {
+        return Err("invalid address");
+    }
+
+    let mut addrs: Vec<Address> = Vec::new();
+    for addr in (host, port)
+        .to_socket_addrs()
+        .map_err(|_| "host not found")?
+    {
+        let addr = Address::Tcp(addr);
+        if !addrs.contains(&addr) {
+            addrs.push(addr);
+        }
+    }
+
+    if addrs.is_empty() {
+        return Err("host not found");
+    }
+
+    Ok(addrs)
+}
+
+/*
+ 변수가 섞인 값 해석: $이름 또는 ${이름} (이름은 영문자, 숫자, _)
+ - $remote_addr, $request_uri, $uri, $args, $query_string, $host, $request_method,
+   $http_이름, $arg_이름, $cookie_이름
+*/
+fn parse_variables(directive: &Directive, arg: &Arg) -> Result<Vec<Segment>, ConfigError> {
+    let value = arg.value.as_str();
+    let mut segments: Vec<Segment> = Vec::new();
+    let mut rest = value;
+
+    while let Some(start) = rest.find('$') {
+        if start > 0 {
+            segments.push(Segment::Text(rest[..start].to_string()));
+        }
+        rest = &rest[start + 1..];
+
+        let name = if let Some(braced) = rest.strip_prefix('{') {
+            let end = braced
+                .find('}')
+                .ok_or_else(|| invalid_value(directive, arg))?;
+            rest = &braced[end + 1..];
+            &braced[..end]
+        } else {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            let name = &rest[..end];
+            rest = &rest[end..];
+            name
+        };
+
+        let variable = match name {
+            "remote_addr" => Variable::RemoteAddr,
+            "request_uri" => Variable::RequestUri,
+            "uri" => Variable::Uri,
+            "args" | "query_string" => Variable::Args,
+            "host" => Variable::Host,
+            "request_method" => Variable::RequestMethod,
+            _ => match name.split_once('_') {
+                Some(("http", header)) if !header.is_empty() => {
+                    Variable::Http(header.replace('_', "-"))
+                }
+                Some(("arg", param)) if !param.is_empty() => Variable::Arg(param.to_string()),
+                Some(("cookie", cookie)) if !cookie.is_empty() => {
+                    Variable::Cookie(cookie.to_string())
+                }
+                _ if name.is_empty() => return Err(invalid_value(directive, arg)),
+                _ => {
+                    return Err(ConfigError::new(
+                        arg.position,
+                        format!("unknown \"{}\" variable", name),
+                    ));
+                }
+            },
+        };
+        segments.push(Segment::Variable(variable));
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest.to_string()));
+    }
+
+    Ok(segments)
+}
+
+fn parse_server(
+    directive: &Directive,
+    upstreams: &mut Vec<UpstreamConfig>,
+) -> Result<(ServerConfig, ServerListens), ConfigError> {
+    let block = expect_block(directive, 0)?;
+    let mut server = ServerConfig {
+        names: Vec::new(),
+        files: FilesConfig::default(),
+        error_pages: HashMap::new(),
+        locations: Vec::new(),
+    };
+    let mut listens: ServerListens = Vec::new();
+    let mut seen = Seen::default();
+
+    for directive in block {
+        match directive.name.as_str() {
+            "root" | "index" | "expires" | "cache_control" => seen.check(directive)?,
+            _ => {}
+        }
+
+        match directive.name.as_str() {
+            "listen" => {
+                expect_args(directive, 1, usize::MAX)?;
+                listens.extend(parse_listen(directive)?);
+            }
+            "server_name" => {
+                expect_args(directive, 1, usize::MAX)?;
+                server.names.extend(
+                    directive
+                        .args
+                        .iter()
+                        .map(|arg| arg.value.to_ascii_lowercase()),
+                );
+            }
+            "location" => {
+                let location = parse_location(directive, upstreams)?;
+
+                let duplicate = server
+                    .locations
+                    .iter()
+                    .any(|other| other.path == location.path && other.exact == location.exact);
+                if duplicate {
+                    return Err(ConfigError::new(
+                        directive.position,
+                        format!("duplicate location \"{}\"", location.path),
+                    ));
+                }
+
+                server.locations.push(location);
+            }
+            "root" | "index" | "expires" | "cache_control" => {
+                parse_files(directive, &mut server.files)?;
+            }
+            "error_page" => {
+                expect_args(directive, 2, usize::MAX)?;
+                parse_error_page(directive, &mut server.error_pages)?;
+            }
+            _ => return Err(unexpected(directive)),
+        }
+    }
+
+    //location은 정적 파일 설정을 지정하지 않은 항목만 server 값을 물려받음
+    if server.files.index.is_empty() {
+        server.files.index.push(DEFAULT_INDEX.to_string());
+    }
+    for location in &mut server.locations {
+        let (files, parent) = (&mut location.files, &server.files);
+        if files.root.is_none() {
+            files.root = parent.root.clone();
+        }
+        if files.index.is_empty() {
+            files.index = parent.index.clone();
+        }
+        if files.expires.is_none() {
+            files.expires = parent.expires;
+        }
+        if files.cache_control.is_none() {
+            files.cache_control = parent.cache_control.clone();
+        }
+    }
+
+    if listens.is_empty() {
+        return Err(ConfigError::new(
+            directive.position,
+            "\"listen\" directive is missing in \"server\"",
+        ));
+    }
+
+    Ok((server, listens))
+}
+
+/*
+ listen 주소 [default_server] [backlog=N] [ipv6only=on|off] [mode=0660] [rcvbuf=크기] [sndbuf=크기]
+        [so_keepalive=on|off|[idle]:[interval]:[count]] [tcp_nodelay=on|off]
+        [deferred] [fastopen=N] [accept4=on|off]
+ - 주소: 7878 (= 0.0.0.0:7878), *:7878, 127.0.0.1:7878, [::]:7878, [::1]:7878, localhost:7878, unix:/path
+ - 호스트 이름이 여러 주소로 풀리면(localhost → 127.0.0.1, ::1) 주소마다 listen
+ - ipv6only: IPv6 주소에만 지정 가능, 기본 on (off면 [::]:포트 하나로 IPv4 연결도 받음)
+ - mode: Unix 소켓 파일 권한 (8진수)
+ - so_keepalive, tcp_nodelay, deferred, fastopen은 TCP 주소에만, deferred/fastopen/accept4는 Linux에서만
+*/
+fn parse_listen(directive: &Directive) -> Result<Vec<Listen>, ConfigError> {
+    let arg = &directive.args[0];
+    let addrs = parse_listen_addrs(arg)?;
+
+    let mut default_server = false;
+    let mut options = ListenOptions::default();
+
+    //주소 종류에 맞지 않는 옵션
+    let unsupported = |param: &Arg, supported: bool| {
+        if supported {
+            return Ok(());
+        }
+        let name = param.value.split('=').next().unwrap_or_default();
+        Err(ConfigError::new(
+            param.position,
+            format!("{} is not supported on addr \"{}\"", name, arg.value),
+        ))
+    };
+    let tcp = addrs.iter().any(|addr| addr.tcp().is_some());
+    let ipv6 = addrs
+        .iter()
+        .any(|addr| addr.tcp().is_some_and(SocketAddr::is_ipv6));
+    let unix = matches!(addrs[..], [Address::Unix(_)]);
+
+    for param in &directive.args[1..] {
+        match param.value.split_once('=') {
+            None if param.value == "default_server" => default_server = true,
+            Some(("backlog", value)) => {
+                options.backlog = Some(parse_param(directive, param, value, parse_count)?);
+            }
+            Some(("ipv6only", value)) => {
+                unsupported(param, ipv6)?;
+                options.ipv6only = Some(parse_param(directive, param, value, parse_flag)?);
+            }
+            Some(("mode", value)) => {
+                unsupported(param, unix)?;
+                options.mode = Some(
+                    u32::from_str_radix(value, 8)
+                        .ok()
+                        .filter(|mode| *mode <= 0o777)
+                        .ok_or_else(|| invalid_value(directive, param))?,
+                );
+            }
+            Some(("rcvbuf", value)) => {
+                options.rcvbuf = Some(parse_param(directive, param, value, parse_buffer_size)?);
+            }
+            Some(("sndbuf", value)) => {
+                options.sndbuf = Some(parse_param(directive, param, value, parse_buffer_size)?);
+            }
+            Some(("so_keepalive", value)) => {
+                unsupported(param, tcp)?;
+                options.keepalive = Some(parse_keepalive(directive, param, value)?);
+            }
+            Some(("tcp_nodelay", value)) => {
+                unsupported(param, tcp)?;
+                options.tcp_nodelay = Some(parse_param(directive, param, value, parse_flag)?);
+            }
+            #[cfg(target_os = "linux")]
+            None if param.value == "deferred" => {
+                unsupported(param, tcp)?;
+                options.deferred = true;
+            }
+            #[cfg(target_os = "linux")]
+            Some(("fastopen", value)) => {
+                unsupported(param, tcp)?;
+                options.fastopen = Some(parse_param(directive, param, value, parse_count)?);
+            }
+            #[cfg(target_os = "linux")]
+            Some(("accept4", value)) => {
+                options.accept4 = Some(parse_param(directive, param, value, parse_flag)?);
+            }
+            _ => {
+                return Err(ConfigError::new(
+                    param.position,
+                    format!("invalid parameter \"{}\"", param.value),
+                ));
+            }
+        }
+    }
+
+    Ok(addrs
+        .into_iter()
+        .map(|addr| {
+            let mut options = options.clone();
+            //호스트 이름이 여러 종류의 주소로 풀린 경우 해당하지 않는 주소에서는 무시
+            if !addr.tcp().is_some_and(SocketAddr::is_ipv6) {
+                options.ipv6only = None;
+            }
+            Listen {
+                addr,
+                default_server,
+                options,
+                position: arg.position,
+            }
+        })
+        .collect())
+}
+
+//so_keepalive=on|off|[idle]:[interval]:[count] (예: 30m::10)
+fn parse_keepalive(
+    directive: &Directive,
+    param: &Arg,
+    value: &str,
+) -> Result<KeepaliveConfig, ConfigError> {
+    match value {
+        "on" | "off" => {
+            return Ok(KeepaliveConfig {
+                enabled: value == "on",
+                idle: None,
+                interval: None,
+                count: None,
+            });
+        }
+        _ => {}
+    }
+
+    let parts: Vec<&str> = value.split(':').collect();
+    let [idle, interval, count] = parts[..] else {
+        return Err(invalid_value(directive, param));
+    };
+    let time = |part: &str| -> Result<Option<Duration>, ConfigError> {
+        if part.is_empty() {
+            return Ok(None);
+        }
+        let time = parse_param(directive, param, part, parse_time)?;
+        //커널은 초 단위 (0초는 허용하지 않음)
+        if time < Duration::from_secs(1) {
+            return Err(invalid_value(directive, param));
+        }
+        Ok(Some(time))
+    };
+
+    let keepalive = KeepaliveConfig {
+        enabled: true,
+        idle: time(idle)?,
+        interval: time(interval)?,
+        count: match count {
+            "" => None,
+            count => Some(parse_param(directive, param, count, parse_count)?),
+        },
+    };
+
+    if keepalive.idle.is_none() && keepalive.interval.is_none() && keepalive.count.is_none() {
+        return Err(invalid_value(directive, param));
+    }
+
+    Ok(keepalive)
+}
+
+//listen 주소를 소켓 주소 목록으로 (중복 제거, 순서 유지)
+fn parse_listen_addrs(arg: &Arg) -> Result<Vec<Address>, ConfigError> {
+    if let Some(path) = arg.value.strip_prefix("unix:") {
+        if path.is_empty() {
+            return Err(ConfigError::new(
+                arg.position,
+                format!("no path in \"{}\" of the \"listen\" directive", arg.value),
+            ));
+        }
+        return Ok(vec![Address::Unix(PathBuf::from(path))]);
+    }
+
+    let value = if arg.value.bytes().all(|b| b.is_ascii_digit()) {
+        format!("0.0.0.0:{}", arg.value)
+    } else if let Some(port) = arg.value.strip_prefix("*:") {
+        format!("0.0.0.0:{}", port)
+    } else {
+        arg.value.clone()
+    };
+
+    if let Ok(addr) = value.parse::<SocketAddr>() {
+        return Ok(vec![Address::Tcp(addr)]);
+    }
+
+    let error = |message: &str| {
+        ConfigError::new(
+            arg.position,
+            format!(
+                "{} in \"{}\" of the \"listen\" directive",
+                message, arg.value
+            ),
+        )
+    };
+
+    let (host, port) = value.rsplit_once(':').ok_or_else(|| error("no port"))?;
+    let port: u16 = port.parse().map_err(|_| error("invalid port"))?;
+
+    //대괄호 주소는 IPv6 리터럴만 가능 (SocketAddr로 파싱되지 않았으면 잘못된 주소)
+    if host.starts_with('[') || host.is_empty() {
+        return Err(error("invalid address"));
+    }
+
+    let mut addrs: Vec<Address> = Vec::new();
+    for addr in (host, port)
+        .to_socket_addrs()
+        .map_err(|_| error("host not found"))?
+    {
+        let addr = Address::Tcp(addr);
+        if !addrs.contains(&addr) {
+            addrs.push(addr);
+        }
+    }
+
+    if addrs.is_empty() {
+        return Err(error("host not found"));
+    }
+
+    Ok(addrs)
+}
+
+/*
+ location [=] 경로 { ... }
+ - = 이면 경로가 정확히 같을 때만, 아니면 접두사 매칭 (가장 긴 접두사 우선)
+*/
+fn parse_location(
+    directive: &Directive,
+    upstreams: &mut Vec<UpstreamConfig>,
+) -> Result<LocationConfig, ConfigError> {
+    let block = expect_block(directive, 2)?;
+
+    let (exact, path) = match directive.args.as_slice() {
+        [path] => (false, path),
+        [modifier, path] if modifier.value == "=" => (true, path),
+        [modifier, _] => {
+            return Err(ConfigError::new(
+                modifier.position,
+                format!("invalid location modifier \"{}\"", modifier.value),
+            ));
+        }
+        _ => return Err(invalid_args(directive)),
+    };
+
+    if !path.value.starts_with('/') {
+        return Err(invalid_value(directive, path));
+    }
+
+    let mut location = LocationConfig {
+        path: path.value.clone(),
+        exact,
+        ret: None,
+        proxy: None,
+        files: FilesConfig::default(),
+    };
+    let mut next_upstream = NextUpstream::default();
+    let mut timeouts = ProxyTimeouts::default();
+    let mut buffering = ProxyBuffering::default();
+    let mut seen = Seen::default();
+
+    for directive in block {
+        seen.check(directive)?;
+
+        match directive.name.as_str() {
+            "return" => {
+                expect_args(directive, 1, 2)?;
+                location.ret = Some(parse_return(directive)?);
+            }
+            "proxy_pass" => {
+                expect_args(directive, 1, 1)?;
+                location.proxy = Some(parse_proxy_pass(directive, upstreams)?);
+            }
+            "proxy_next_upstream" => {
+                expect_args(directive, 1, usize::MAX)?;
+                parse_next_upstream(directive, &mut next_upstream)?;
+            }
+            "proxy_next_upstream_tries" => {
+                expect_args(directive, 1, 1)?;
+                let arg = &directive.args[0];
+                next_upstream.tries = arg
+                    .value
+                    .parse()
+                    .map_err(|_| invalid_value(directive, arg))?;
+            }
+            "proxy_next_upstream_timeout" => {
+                expect_args(directive, 1, 1)?;
+                next_upstream.timeout_total = parse_time(directive, &directive.args[0])?;
+            }
+            "proxy_connect_timeout" | "proxy_send_timeout" | "proxy_read_timeout" => {
+                expect_args(directive, 1, 1)?;
+                let arg = &directive.args[0];
+                let timeout = parse_time(directive, arg)?;
+                if timeout.is_zero() {
+                    return Err(invalid_value(directive, arg));
+                }
+                match directive.name.as_str() {
+                    "proxy_connect_timeout" => timeouts.connect = timeout,
+                    "proxy_send_timeout" => timeouts.send = timeout,
+                    _ => timeouts.read = timeout,
+                }
+            }
+            "proxy_total_timeout" => {
+                expect_args(directive, 1, 1)?;
+                timeouts.total = parse_time(directive, &directive.args[0])?;
+            }
+            "proxy_request_buffering" => {
+                expect_args(directive, 1, 1)?;
+                buffering.request = parse_flag(directive, &directive.args[0])?;
+            }
+            "proxy_buffering" => {
+                expect_args(directive, 1, 1)?;
+                buffering.response = parse_flag(directive, &directive.args[0])?;
+            }
+            "proxy_buffers" => {
+                expect_args(directive, 2, 2)?;
+                let size = &directive.args[1];
+                buffering.buffers = parse_positive(directive, &directive.args[0])?;
+                buffering.buffer_size = parse_size(directive, size)?
+                    .try_into()
+                    .ok()
+                    .filter(|size| *size > 0)
+                    .ok_or_else(|| invalid_value(directive, size))?;
+            }
+            "proxy_max_temp_file_size" => {
+                expect_args(directive, 1, 1)?;
+                buffering.max_temp_file_size = parse_size(directive, &directive.args[0])?;
+            }
+            "proxy_temp_path" => {
+                expect_args(directive, 1, 1)?;
+                buffering.temp_path = PathBuf::from(&directive.args[0].value);
+            }
+            "root" | "index" | "expires" | "cache_control" => {
+                parse_files(directive, &mut location.files)?;
+            }
+            _ => return Err(unexpected(directive)),
+        }
+    }
+
+    //proxy_*는 proxy_pass보다 먼저 나올 수 있으므로 다 읽은 뒤 적용
+    if let Some(proxy) = &mut location.proxy {
+        proxy.next_upstream = next_upstream;
+        proxy.timeouts = timeouts;
+        proxy.buffering = buffering;
+    }
+
+    Ok(location)
+}
+
+//proxy_next_upstream error | timeout | http_502 | http_503 | http_504 | non_idempotent ... | off
+fn parse_next_upstream(
+    directive: &Directive,
+    next_upstream: &mut NextUpstream,
+) -> Result<(), ConfigError> {
+    next_upstream.error = false;
+    next_upstream.timeout = false;
+    next_upstream.statuses.clear();
+    next_upstream.non_idempotent = false;
+
+    if let [arg] = directive.args.as_slice()
+        && arg.value == "off"
+    {
+        return Ok(());
+    }
+
+    for arg in &directive.args {
+        match arg.value.as_str() {
+            "error" => next_upstream.error = true,
+            "timeout" => next_upstream.timeout = true,
+            "http_502" => next_upstream.statuses.push(502),
+            "http_503" => next_upstream.statuses.push(503),
+            "http_504" => next_upstream.statuses.push(504),
+            "non_idempotent" => next_upstream.non_idempotent = true,
+            _ => return Err(invalid_value(directive, arg)),
+        }
+    }
+
+    Ok(())
+}
+
+//return 코드 [텍스트];  return URL;  (URL만 있으면 302)
+fn parse_return(directive: &Directive) -> Result<ReturnConfig, ConfigError> {
+    let first = &directive.args[0];
+
+    if directive.args.len() == 1 && first.value.contains("://") {
+        return Ok(ReturnConfig {
+            status: StatusCode::Found,
+            text: Some(first.value.clone()),
+        });
+    }
+
+    let code = first
+        .value
+        .parse::<u16>()
+        .ok()
+        .filter(|code| (200..=599).contains(code))
+        .ok_or_else(|| invalid_value(directive, first))?;
+
+    Ok(ReturnConfig {
+        status: StatusCode::from_u16(code),
+        text: directive.args.get(1).map(|arg| arg.value.clone()),
+    })
+}
+
+/*
+ proxy_pass http://upstream이름[/uri] | http://호스트[:포트][/uri] | http://unix:/경로[:/uri]
+ - 같은 이름의 upstream이 있으면 그 그룹으로 보냄
+ - 아니면 주소로 풀어서 그 주소만 가진 그룹을 만듦 (같은 주소를 쓰는 location끼리 공유, 라운드 로빈)
+*/
+fn parse_proxy_pass(
+    directive: &Directive,
+    upstreams: &mut Vec<UpstreamConfig>,
+) -> Result<ProxyConfig, ConfigError> {
+    let arg = &directive.args[0];
+    let error = |message: &str| {
+        ConfigError::new(
+            arg.position,
+            format!(
+                "{} in \"{}\" of the \"proxy_pass\" directive",
+                message, arg.value
+            ),
+        )
+    };
+
+    let Some(rest) = arg.value.strip_prefix("http://") else {
+        return Err(error("invalid URL prefix"));
+    };
+
+    let (name, uri) = if let Some(path) = rest.strip_prefix("unix:") {
+        match path.split_once(':') {
+            Some((path, uri)) => (&rest[..5 + path.len()], Some(uri)),
+            None => (rest, None),
+        }
+    } else {
+        match rest.find('/') {
+            Some(i) => (&rest[..i], Some(&rest[i..])),
+            None => (rest, None),
+        }
+    };
+    if uri.is_some_and(|uri| !uri.starts_with('/')) {
+        return Err(error("invalid URI"));
+    }
+
+    let upstream = match upstreams.iter().position(|upstream| upstream.name == name) {
+        Some(index) => index,
+        None => {
+            let servers = parse_upstream_addrs(name)
+                .map_err(error)?
+                .into_iter()
+                .map(|addr| UpstreamServer {
+                    addr,
+                    weight: 1,
+                    max_fails: DEFAULT_MAX_FAILS,
+                    fail_timeout: DEFAULT_FAIL_TIMEOUT,
+                })
+                .collect();
+            upstreams.push(UpstreamConfig {
+                name: name.to_string(),
+                balance: Balance::RoundRobin,
+                servers,
+                health_check: None,
+                circuit_breaker: None,
+                keepalive: DEFAULT_UPSTREAM_KEEPALIVE,
+                keepalive_timeout: DEFAULT_UPSTREAM_KEEPALIVE_TIMEOUT,
+            });
+            upstreams.len() - 1
+        }
+    };
+
+    Ok(ProxyConfig {
+        upstream,
+        uri: uri.map(str::to_string),
+        next_upstream: NextUpstream::default(),
+        timeouts: ProxyTimeouts::default(),
+        buffering: ProxyBuffering::default(),
+    })
+}
+
+//open_file_cache off | max=N [inactive=시간]  (inactive 기본 60s)
+fn parse_open_file_cache(
+    directive: &Directive,
+) -> Result<Option<OpenFileCacheConfig>, ConfigError> {
+    let first = &directive.args[0];
+    if first.value == "off" {
+        if directive.args.len() > 1 {
+            return Err(invalid_args(directive));
+        }
+        return Ok(None);
+    }
+
+    let mut max: Option<usize> = None;
+    let mut inactive = DEFAULT_OPEN_FILE_CACHE_INACTIVE;
+
+    for arg in &directive.args {
+        match arg.value.split_once('=') {
+            Some(("max", value)) => max = Some(parse_param(directive, arg, value, parse_positive)?),
+            Some(("inactive", value)) => inactive = parse_param(directive, arg, value, parse_time)?,
+            _ => return Err(invalid_value(directive, arg)),
+        }
+    }
+
+    let Some(max) = max else {
+        return Err(ConfigError::new(
+            directive.position,
+            "\"open_file_cache\" must have the \"max\" parameter",
+        ));
+    };
+
+    Ok(Some(OpenFileCacheConfig { max, inactive }))
+}
+
+//정적 파일 지시어 (server, location 공통)
+fn parse_files(directive: &Directive, files: &mut FilesConfig) -> Result<(), ConfigError> {
+    match directive.name.as_str() {
+        "root" => {
+            expect_args(directive, 1, 1)?;
+            files.root = Some(PathBuf::from(&directive.args[0].value));
+        }
+        "index" => {
+            expect_args(directive, 1, usize::MAX)?;
+            files.index = parse_index(directive)?;
+        }
+        "expires" => {
+            expect_args(directive, 1, 1)?;
+            let arg = &directive.args[0];
+            files.expires = Some(match arg.value.as_str() {
+                "off" => Expires::Off,
+                "epoch" => Expires::Epoch,
+                "max" => Expires::Max,
+                _ => Expires::After(parse_time(directive, arg)?),
+            });
+        }
+        "cache_control" => {
+            expect_args(directive, 1, 1)?;
+            let arg = &directive.args[0];
+            if arg.value.is_empty() || arg.value.contains(['\r', '\n']) {
+                return Err(invalid_value(directive, arg));
+            }
+            files.cache_control = Some(arg.value.clone());
+        }
+        _ => return Err(unexpected(directive)),
+    }
+
+    Ok(())
+}
+
+//index 파일...;  (경로 구분자가 들어간 이름은 루트 밖을 가리킬 수 있으므로 허용하지 않음)
+fn parse_index(directive: &Directive) -> Result<Vec<String>, ConfigError> {
+    directive
+        .args
+        .iter()
+        .map(|arg| {
+            if arg.value.is_empty() || arg.value.contains('/') || arg.value == ".." {
+                return Err(invalid_value(directive, arg));
+            }
+            Ok(arg.value.clone())
+        })
+        .collect()
+}
+
+//error_page 코드... URI;  (URI는 같은 server의 location으로 찾아서 디스크에서 읽음)
+fn parse_error_page(
+    directive: &Directive,
+    error_pages: &mut HashMap<u16, String>,
+) -> Result<(), ConfigError> {
+    let (uri, codes) = directive.args.split_last().unwrap();
+
+    if !uri.value.starts_with('/') {
+        return Err(invalid_value(directive, uri));
+    }
+
+    for arg in codes {
+        let code = arg
+            .value
+            .parse::<u16>()
+            .ok()
+            .filter(|code| (300..=599).contains(code))
+            .ok_or_else(|| invalid_value(directive, arg))?;
+        error_pages.insert(code, uri.value.clone());
+    }
+
+    Ok(())
+}
+
+/*
+ server별 listen을 주소 단위로 모음
+ - 같은 주소의 옵션 충돌, default_server 중복 검사
+ - ipv6only=off인 [::]:포트는 같은 포트의 IPv4 주소와 함께 쓸 수 없음 (이미 IPv4 연결까지 받으므로 bind가 충돌)
+*/
+fn collect_listeners(server_listens: &[ServerListens]) -> Result<Vec<ListenConfig>, ConfigError> {
+    let mut addrs: Vec<Address> = Vec::new();
+    let mut options: Vec<ListenOptions> = Vec::new();
+    let mut servers: Vec<Vec<usize>> = Vec::new();
+    let mut defaults: Vec<bool> = Vec::new();
+    let mut positions: Vec<Position> = Vec::new();
+
+    for (server, listens) in server_listens.iter().enumerate() {
+        for listen in listens {
+            let index = match addrs.iter().position(|addr| *addr == listen.addr) {
+                Some(index) => index,
+                None => {
+                    addrs.push(listen.addr.clone());
+                    options.push(ListenOptions::default());
+                    servers.push(Vec::new());
+                    defaults.push(false);
+                    positions.push(listen.position);
+                    addrs.len() - 1
+                }
+            };
+
+            //옵션은 같은 주소의 listen 중 하나에만 (둘 다 지정했으면 같아야 함)
+            if listen.options != ListenOptions::default() {
+                if options[index] != ListenOptions::default() && options[index] != listen.options {
+                    return Err(ConfigError::new(
+                        listen.position,
+                        format!("duplicate listen options for {}", listen.addr),
+                    ));
+                }
+                options[index] = listen.options.clone();
+            }
+
+            if servers[index].contains(&server) {
+                return Err(ConfigError::new(
+                    listen.position,
+                    format!("duplicate listen {}", listen.addr),
+                ));
+            }
+
+            if listen.default_server {
+                if defaults[index] {
+                    return Err(ConfigError::new(
+                        listen.position,
+                        format!("a duplicate default server for {}", listen.addr),
+                    ));
+                }
+                defaults[index] = true;
+                servers[index].insert(0, server);
+            } else {
+                servers[index].push(server);
+            }
+        }
+    }
+
+    let listeners: Vec<ListenConfig> = addrs
+        .into_iter()
+        .zip(options)
+        .zip(servers)
+        .map(|((addr, options), servers)| options.into_config(addr, servers))
+        .collect();
+
+    for dual in &listeners {
+        let Some(addr) = dual.addr.tcp() else {
+            continue;
+        };
+        if !addr.ip().is_unspecified() || addr.is_ipv4() || dual.ipv6only {
+            continue;
+        }
+
+        if let Some(index) = listeners.iter().position(|l| {
+            l.addr
+                .tcp()
+                .is_some_and(|other| other.is_ipv4() && other.port() == addr.port())
+        }) {
+            return Err(ConfigError::new(
+                positions[index],
+                format!(
+                    "listen {} conflicts with {} ipv6only=off",
+                    listeners[index].addr, dual.addr
+                ),
+            ));
+        }
+    }
+
+    Ok(listeners)
+}
+
+// ============= 검증 헬퍼 =============
+
+//블록 안에서 같은 지시어가 두 번 나오면 에러
+#[derive(Default)]
+struct Seen(HashSet<String>);
+
+impl Seen {
+    fn check(&mut self, directive: &Directive) -> Result<(), ConfigError> {
+        if !self.0.insert(directive.name.clone()) {
+            return Err(ConfigError::new(
+                directive.position,
+                format!("\"{}\" directive is duplicate", directive.name),
+            ));
+        }
+        Ok(())
+    }
+}
+
+//인자 수 검사 + 블록이 붙으면 안 되는 지시어 검사
+fn expect_args(directive: &Directive, min: usize, max: usize) -> Result<(), ConfigError> {
+    if directive.block.is_some() {
+        return Err(ConfigError::new(
+            directive.position,
+            format!(
+                "directive \"{}\" is not terminated by \";\"",
+                directive.name
+            ),
+        ));
+    }
+    if directive.args.len() < min || directive.args.len() > max {
+        return Err(invalid_args(directive));
+    }
+    Ok(())
+}
+
+//블록 지시어 검사 (args: 블록 앞 인자의 최대 개수)
+fn expect_block(directive: &Directive, args: usize) -> Result<&[Directive], ConfigError> {
+    let Some(block) = &directive.block else {
+        return Err(ConfigError::new(
+            directive.position,
+            format!("directive \"{}\" has no opening \"{{\"", directive.name),
+        ));
+    };
+    if directive.args.len() > args {
+        return Err(invalid_args(directive));
+    }
+    Ok(block)
+}
+
+fn invalid_args(directive: &Directive) -> ConfigError {
+    ConfigError::new(
+        directive.position,
+        format!(
+            "invalid number of arguments in \"{}\" directive",
+            directive.name
+        ),
+    )
+}
+
+fn invalid_value(directive: &Directive, arg: &Arg) -> ConfigError {
+    ConfigError::new(
+        arg.position,
+        format!(
+            "invalid value \"{}\" in \"{}\" directive",
+            arg.value, directive.name
+        ),
+    )
+}
+
+//모르는 지시어인지, 알지만 이 블록에 올 수 없는 지시어인지
+fn unexpected(directive: &Directive) -> ConfigError {
+    let message = if KNOWN_DIRECTIVES.contains(&directive.name.as_str()) {
+        format!("\"{}\" directive is not allowed here", directive.name)
+    } else {
+        format!("unknown directive \"{}\"", directive.name)
+    };
+    ConfigError::new(directive.position, message)
+}
+
+//key=value 파라미터의 값 부분 해석 (에러는 파라미터 전체를 가리킴)
+fn parse_param<T>(
+    directive: &Directive,
+    param: &Arg,
+    value: &str,
+    parse: fn(&Directive, &Arg) -> Result<T, ConfigError>,
+) -> Result<T, ConfigError> {
+    let arg = Arg {
+        value: value.to_string(),
+        position: param.position,
+    };
+    parse(directive, &arg).map_err(|_| invalid_value(directive, param))
+}
+
+//on | off
+fn parse_flag(directive: &Directive, arg: &Arg) -> Result<bool, ConfigError> {
+    match arg.value.as_str() {
+        "on" => Ok(true),
+        "off" => Ok(false),
+        _ => Err(invalid_value(directive, arg)),
+    }
+}
+
+//소켓 옵션용 양의 정수 (c_int 범위)
+fn parse_count(directive: &Directive, arg: &Arg) -> Result<i32, ConfigError> {
+    arg.value
+        .parse::<i32>()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| invalid_value(directive, arg))
+}
+
+//소켓 버퍼 크기 (크기 단위 허용, c_int 범위)
+fn parse_buffer_size(directive: &Directive, arg: &Arg) -> Result<i32, ConfigError> {
+    parse_size(directive, arg)?
+        .try_into()
+        .ok()
+        .filter(|size: &i32| *size > 0)
+        .ok_or_else(|| invalid_value(directive, arg))
+}
+
+fn parse_positive(directive: &Directive, arg: &Arg) -> Result<usize, ConfigError> {
+    arg.value
+        .parse::<usize>()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| invalid_value(directive, arg))
+}
+
+//시간: 500ms, 75s, 10m, 1h, 1d (단위가 없으면 초)
+fn parse_time(directive: &Directive, arg: &Arg) -> Result<Duration, ConfigError> {
+    let value = arg.value.as_str();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+
+    let number: u64 = number.parse().map_err(|_| invalid_value(directive, arg))?;
+
+    let duration = match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "" | "s" => Some(Duration::from_secs(number)),
+        "m" => number.checked_mul(60).map(Duration::from_secs),
+        "h" => number.checked_mul(3600).map(Duration::from_secs),
+        "d" => number.checked_mul(86400).map(Duration::from_secs),
+        _ => None,
+    };
+
+    duration.ok_or_else(|| invalid_value(directive, arg))
+}
+
+//크기: 512, 16k, 1m, 1g (대소문자 무관)
+fn parse_size(directive: &Directive, arg: &Arg) -> Result<u64, ConfigError> {
+    let value = arg.value.to_ascii_lowercase();
+    let (number, multiplier) = match value.as_bytes().last() {
+        Some(b'k') => (&value[..value.len() - 1], 1024),
+        Some(b'm') => (&value[..value.len() - 1], 1024 * 1024),
+        Some(b'g') => (&value[..value.len() - 1], 1024 * 1024 * 1024),
+        _ => (value.as_str(), 1),
+    };
+
+    number
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| invalid_value(directive, arg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //설정 에러 메시지 ("줄:칸: 메시지")
+    fn load_error(source: &str) -> String {
+        match load_str(source, Path::new("/srv/rustify")) {
+            Ok(_) => panic!("configuration loaded: {}", source),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    //값 파서에 넘길 지시어 하나 (인자는 1:5에 있음)
+    fn arg(value: &str) -> (Directive, Arg) {
+        let arg = Arg {
+            value: value.to_string(),
+            position: Position { line: 1, column: 5 },
+        };
+        let directive = Directive {
+            name: String::from("test"),
+            args: vec![arg.clone()],
+            block: None,
+            position: Position { line: 1, column: 1 },
+        };
+        (directive, arg)
+    }
+
+    fn time(value: &str) -> Option<Duration> {
+        let (directive, arg) = arg(value);
+        parse_time(&directive, &arg).ok()
+    }
+
+    fn size(value: &str) -> Option<u64> {
+        let (directive, arg) = arg(value);
+        parse_size(&directive, &arg).ok()
+    }
+
+    fn positive(value: &str) -> Option<usize> {
+        let (directive, arg) = arg(value);
+        parse_positive(&directive, &arg).ok()
+    }
+
+    #[test]
+    fn parses_time_values() {
+        assert_eq!(time("500ms"), Some(Duration::from_millis(500)));
+        assert_eq!(time("75"), Some(Duration::from_secs(75)));
+        assert_eq!(time("75s"), Some(Duration::from_secs(75)));
+        assert_eq!(time("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(time("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(time("1d"), Some(Duration::from_secs(86400)));
+        assert_eq!(time("0"), Some(Duration::ZERO));
+
+        for value in [
+            "",
+            "s",
+            "1.5s",
+            "-1s",
+            "10x",
+            "1 s",
+            "1S",
+            "999999999999999999d",
+        ] {
+            assert_eq!(time(value), None, "{}", value);
+        }
+
+        let (directive, arg) = arg("10x");
+        assert_eq!(
+            parse_time(&directive, &arg).unwrap_err().to_string(),
+            "1:5: invalid value \"10x\" in \"test\" directive"
+        );
+    }
+
+    #[test]
+    fn parses_size_values() {
+        assert_eq!(size("512"), Some(512));
+        assert_eq!(size("16k"), Some(16 * 1024));
+        assert_eq!(size("16K"), Some(16 * 1024));
+        assert_eq!(size("1m"), Some(1024 * 1024));
+        assert_eq!(size("1g"), Some(1024 * 1024 * 1024));
+        assert_eq!(size("0"), Some(0));
+
+        for value in ["", "k", "1.5m", "-1", "1t", "99999999999999999g"] {
+            assert_eq!(size(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn parses_positive_values() {
+        assert_eq!(positive("1"), Some(1));
+        assert_eq!(positive("1024"), Some(1024));
+
+        for value in ["0", "-1", "", "1k", "auto"] {
+            assert_eq!(positive(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_duplicate_unknown_and_misplaced_directives() {
+        assert_eq!(
+            load_error(
+                "worker_processes 1;\nworker_processes 2;\nhttp { server { listen 8080; } }\n"
+            ),
+            "2:1: \"worker_processes\" directive is duplicate"
+        );
+        assert_eq!(
+            load_error("http {\n    server {\n        lisen 8080;\n    }\n}\n"),
+            "3:9: unknown directive \"lisen\""
+        );
+        assert_eq!(
+            load_error("http {\n    pid a.pid;\n    server { listen 8080; }\n}\n"),
+            "2:5: \"pid\" directive is not allowed here"
+        );
+        assert_eq!(
+            load_error(
+                "http {\n    server {\n        listen 8080;\n        worker_connections 10;\n    }\n}\n"
+            ),
+            "4:9: \"worker_connections\" directive is not allowed here"
+        );
+        assert_eq!(
+            load_error("worker_processes;\nhttp { server { listen 8080; } }\n"),
+            "1:1: invalid number of arguments in \"worker_processes\" directive"
+        );
+        assert_eq!(
+            load_error("http;\n"),
+            "1:1: directive \"http\" has no opening \"{\""
+        );
+        assert_eq!(
+            load_error("pid a.pid;\n"),
+            "no \"http\" block in configuration"
+        );
+    }
+
+    #[test]
+    fn joins_relative_paths_with_prefix() {
+        let source = "\
+pid logs/rustify.pid;
+http {
+    upstream app {
+        server unix:run/app.sock;
+        server unix:/run/abs.sock;
+    }
+    server {
+        listen unix:run/rustify.sock;
+        root html;
+        location /api/ {
+            proxy_pass http://app;
+            proxy_temp_path tmp/proxy;
+        }
+        location /abs/ {
+            root /var/www;
+        }
+    }
+}
+";
+        let config = match load_str(source, Path::new("/srv/rustify")) {
+            Ok(config) => config,
+            Err(e) => panic!("{}", e),
+        };
+        let prefixed = |path: &str| Path::new("/srv/rustify").join(path);
+
+        assert_eq!(config.pid, prefixed("logs/rustify.pid"));
+        assert!(
+            matches!(&config.listeners[0].addr, Address::Unix(path) if *path == prefixed("run/rustify.sock"))
+        );
+
+        let servers = &config.http.upstreams[0].servers;
+        assert!(
+            matches!(&servers[0].addr, Address::Unix(path) if *path == prefixed("run/app.sock"))
+        );
+        assert!(
+            matches!(&servers[1].addr, Address::Unix(path) if path == Path::new("/run/abs.sock"))
+        );
+
+        let server = &config.http.servers[0];
+        assert_eq!(server.files.root, Some(prefixed("html")));
+        let proxy = server.locations[0].proxy.as_ref().unwrap();
+        assert_eq!(proxy.buffering.temp_path, prefixed("tmp/proxy"));
+        assert_eq!(
+            server.locations[1].files.root,
+            Some(PathBuf::from("/var/www"))
+        );
+    }
+
+    #[test]
+    fn limits_upstream_server_weight() {
+        let config = |weight: &str| {
+            format!(
+                "http {{\n    upstream app {{\n        server 127.0.0.1:8001 weight={};\n    }}\n    server {{ listen 8080; }}\n}}\n",
+                weight
+            )
+        };
+
+        let http = match load_str(&config("1000"), Path::new("/srv/rustify")) {
+            Ok(config) => config.http,
+            Err(e) => panic!("{}", e),
+        };
+        assert_eq!(http.upstreams[0].servers[0].weight, 1000);
+
+        assert_eq!(
+            load_error(&config("1001")),
+            "3:31: invalid value \"weight=1001\" in \"server\" directive"
+        );
+        assert_eq!(
+            load_error(&config("4294967296")),
+            "3:31: invalid value \"weight=4294967296\" in \"server\" directive"
+        );
+    }
+}
@@ -1 +1,17 @@
-pub mod host;
+mod loader;
+mod parser;
+mod settings;
+
+pub use loader::load;
+#[cfg(target_os = "linux")]
+pub use settings::AcceptStrategy;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub use settings::EventMethod;
+pub use settings::{
+    Address, Balance, CircuitBreakerConfig, Config, Expires, FilesConfig, HealthCheckConfig,
+    HttpConfig, ListenConfig, LocationConfig, NextUpstream, ProxyBuffering, ProxyConfig,
+    ProxyTimeouts, ReturnConfig, Segment, ServerConfig, UpstreamConfig, UpstreamServer, Variable,
+};
+
+//기본 설정 파일 경로 (실행 위치 기준)
+pub const DEFAULT_CONFIG_PATH: &str = "conf/rustify.conf";
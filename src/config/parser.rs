@@ -0,0 +1,356 @@
+use std::{error::Error, fmt};
+
+/*
+ 설정 파일 문법 (nginx와 같은 블록 문법)
+ - 지시어: 이름 인자... ;
+ - 블록:   이름 인자... { 지시어... }
+ - 인자는 공백으로 구분, 공백/;/{/}가 들어가면 "..." 또는 '...'로 감쌈 (\" \\ \n \t 이스케이프)
+ - # 부터 줄 끝까지 주석
+*/
+
+//파일 안의 위치 (1부터 시작, column은 문자 단위)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+//인자 하나와 그 위치 (값 검증 에러를 인자 위치로 보고하기 위함)
+#[derive(Debug, Clone)]
+pub struct Arg {
+    pub value: String,
+    pub position: Position,
+}
+
+#[derive(Debug, Clone)]
+pub struct Directive {
+    pub name: String,
+    pub args: Vec<Arg>,
+    pub block: Option<Vec<Directive>>,
+    pub position: Position,
+}
+
+//설정 에러 (파일 경로는 로드할 때 채움)
+#[derive(Debug, Clone)]
+pub struct ConfigError {
+    pub path: Option<String>,
+    pub position: Option<Position>,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(position: Position, message: impl Into<String>) -> ConfigError {
+        ConfigError {
+            path: None,
+            position: Some(position),
+            message: message.into(),
+        }
+    }
+
+    //위치가 없는 에러 (파일 읽기 실패, 필수 블록 누락 등)
+    pub fn message(message: impl Into<String>) -> ConfigError {
+        ConfigError {
+            path: None,
+            position: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn with_path(mut self, path: &str) -> ConfigError {
+        self.path = Some(path.to_string());
+        self
+    }
+}
+
+//예) conf/rustify.conf:12:9: unknown directive "lisen"
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(path) = &self.path {
+            write!(f, "{}:", path)?;
+        }
+        if let Some(position) = self.position {
+            write!(f, "{}:", position)?;
+        }
+        if self.path.is_some() || self.position.is_some() {
+            f.write_str(" ")?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ConfigError {}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Semicolon,
+    BlockStart,
+    BlockEnd,
+    Eof,
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Lexer<'a> {
+        Lexer {
+            chars: source.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    //공백과 주석을 건너뛰고 다음 토큰과 시작 위치
+    fn next(&mut self) -> Result<(Token, Position), ConfigError> {
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('#') => {
+                    while self.chars.peek().is_some_and(|c| *c != '\n') {
+                        self.bump();
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        let position = self.position();
+
+        let token = match self.chars.peek().copied() {
+            None => Token::Eof,
+            Some(';') => {
+                self.bump();
+                Token::Semicolon
+            }
+            Some('{') => {
+                self.bump();
+                Token::BlockStart
+            }
+            Some('}') => {
+                self.bump();
+                Token::BlockEnd
+            }
+            Some(quote @ ('"' | '\'')) => {
+                self.bump();
+                Token::Word(self.quoted(quote, position)?)
+            }
+            Some(_) => {
+                let mut word = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_whitespace() || matches!(c, ';' | '{' | '}' | '#' | '"' | '\'') {
+                        break;
+                    }
+                    word.push(c);
+                    self.bump();
+                }
+                Token::Word(word)
+            }
+        };
+
+        Ok((token, position))
+    }
+
+    fn quoted(&mut self, quote: char, start: Position) -> Result<String, ConfigError> {
+        let mut value = String::new();
+
+        loop {
+            match self.bump() {
+                None => return Err(ConfigError::new(start, "unterminated quoted string")),
+                Some(c) if c == quote => return Ok(value),
+                Some('\\') => match self.bump() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some(c) => value.push(c),
+                    None => return Err(ConfigError::new(start, "unterminated quoted string")),
+                },
+                Some(c) => value.push(c),
+            }
+        }
+    }
+}
+
+//설정 파일 전체를 지시어 트리로 파싱 (지시어 이름/인자 검증은 loader에서)
+pub fn parse(source: &str) -> Result<Vec<Directive>, ConfigError> {
+    let mut lexer = Lexer::new(source);
+    parse_block(&mut lexer, false)
+}
+
+//nested: { } 블록 안이면 true (} 로 끝나야 함), 최상위면 파일 끝으로 끝남
+fn parse_block(lexer: &mut Lexer, nested: bool) -> Result<Vec<Directive>, ConfigError> {
+    let mut directives: Vec<Directive> = Vec::new();
+
+    loop {
+        let (token, position) = lexer.next()?;
+
+        let name = match token {
+            Token::Word(name) => name,
+            Token::BlockEnd if nested => return Ok(directives),
+            Token::Eof if !nested => return Ok(directives),
+            Token::Eof => {
+                return Err(ConfigError::new(
+                    position,
+                    "unexpected end of file, expecting \"}\"",
+                ));
+            }
+            Token::BlockEnd => return Err(ConfigError::new(position, "unexpected \"}\"")),
+            Token::Semicolon => return Err(ConfigError::new(position, "unexpected \";\"")),
+            Token::BlockStart => return Err(ConfigError::new(position, "unexpected \"{\"")),
+        };
+
+        let mut args: Vec<Arg> = Vec::new();
+
+        loop {
+            let (token, arg_position) = lexer.next()?;
+
+            match token {
+                Token::Word(value) => args.push(Arg {
+                    value,
+                    position: arg_position,
+                }),
+                Token::Semicolon => {
+                    directives.push(Directive {
+                        name,
+                        args,
+                        block: None,
+                        position,
+                    });
+                    break;
+                }
+                Token::BlockStart => {
+                    let block = parse_block(lexer, true)?;
+                    directives.push(Directive {
+                        name,
+                        args,
+                        block: Some(block),
+                        position,
+                    });
+                    break;
+                }
+                Token::BlockEnd | Token::Eof => {
+                    return Err(ConfigError::new(
+                        arg_position,
+                        format!("directive \"{}\" is not terminated by \";\"", name),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> String {
+        parse(source).unwrap_err().to_string()
+    }
+
+    fn values(args: &[Arg]) -> Vec<&str> {
+        args.iter().map(|arg| arg.value.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_directives_blocks_and_positions() {
+        let source = "worker_processes 2; # 주석\nhttp {\n    server_name a.com *.b.com;\n    location / { root html; }\n}\n";
+        let directives = parse(source).unwrap();
+        assert_eq!(directives.len(), 2);
+
+        let workers = &directives[0];
+        assert_eq!(workers.name, "worker_processes");
+        assert_eq!(values(&workers.args), ["2"]);
+        assert_eq!(workers.position, Position { line: 1, column: 1 });
+        assert!(workers.block.is_none());
+
+        let http = directives[1].block.as_ref().unwrap();
+        assert_eq!(directives[1].position, Position { line: 2, column: 1 });
+        assert_eq!(values(&http[0].args), ["a.com", "*.b.com"]);
+        assert_eq!(
+            http[0].args[1].position,
+            Position {
+                line: 3,
+                column: 23
+            }
+        );
+
+        let location = &http[1];
+        assert_eq!(values(&location.args), ["/"]);
+        let root = &location.block.as_ref().unwrap()[0];
+        assert_eq!(root.name, "root");
+        assert_eq!(
+            root.position,
+            Position {
+                line: 4,
+                column: 18
+            }
+        );
+    }
+
+    #[test]
+    fn parses_quoted_arguments() {
+        let source = r#"return 200 "a b;{}\"\n" 'x\'y' ab"c";"#;
+        let directives = parse(source).unwrap();
+        assert_eq!(
+            values(&directives[0].args),
+            ["200", "a b;{}\"\n", "x'y", "ab", "c"]
+        );
+        assert_eq!(
+            directives[0].args[2].position,
+            Position {
+                line: 1,
+                column: 25
+            }
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_position() {
+        assert_eq!(error("pid \"a.pid;\n"), "1:5: unterminated quoted string");
+        assert_eq!(error("pid 'a\\"), "1:5: unterminated quoted string");
+        assert_eq!(error("http {\n    }\n}\n"), "3:1: unexpected \"}\"");
+        assert_eq!(error("pid a.pid;;"), "1:11: unexpected \";\"");
+        assert_eq!(error("{ pid a.pid; }"), "1:1: unexpected \"{\"");
+        assert_eq!(
+            error("http {\n    server {\n"),
+            "3:1: unexpected end of file, expecting \"}\""
+        );
+        assert_eq!(
+            error("http {\n    pid a.pid\n}\n"),
+            "3:1: directive \"pid\" is not terminated by \";\""
+        );
+        assert_eq!(
+            error("pid a.pid"),
+            "1:10: directive \"pid\" is not terminated by \";\""
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use std::{
+    collections::HashMap, fmt, net::SocketAddr, path::PathBuf, str::FromStr, time::Duration,
+};
+
+use Rustify::http::{Limits, StatusCode};
+
+/*
+ 설정 파일을 해석한 결과
+ - main에서 한 번 로드해서 마스터와 (fork된) 워커에 넘김
+ - 워커는 자기 복사본만 읽으므로 공유/잠금이 필요 없음
+*/
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub pid: PathBuf,                      //마스터 pid 파일 (prefix 적용됨)
+    pub worker_processes: usize,           //auto면 CPU 코어 수
+    pub thread_pool_size: usize,           //워커별 업스트림(proxy_pass) I/O 스레드 수
+    pub worker_shutdown_timeout: Duration, //graceful 종료(quit, reload) 시 처리 중인 연결을 기다리는 최대 시간
+    pub events: EventsConfig,
+    pub http: HttpConfig,
+    pub listeners: Vec<ListenConfig>, //모든 server의 listen 주소 (중복 제거)
+}
+
+#[derive(Debug, Clone)]
+pub struct EventsConfig {
+    pub worker_connections: usize, //워커 하나가 동시에 유지하는 최대 연결 수
+    pub method: EventMethod,
+    pub accept_strategy: AcceptStrategy,
+}
+
+//워커 이벤트 루프 방식 (events { use ...; })
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventMethod {
+    Poller, //플랫폼 기본 (Linux: epoll, macOS: kqueue)
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    IoUring,
+}
+
+/*
+ 워커에 연결을 나눠 주는 방식 (events { accept_strategy ...; })
+ - Shared: 마스터가 주소마다 소켓 하나를 만들고 모든 워커가 공유, 연결이 오면 EPOLLEXCLUSIVE로 한 워커만 깨움
+ - Reuseport: 마스터가 주소마다 워커 수만큼 SO_REUSEPORT 소켓을 만들고 워커마다 하나씩 사용, 커널이 4-tuple 해시로 분배
+   steer_by_cpu면 CBPF 프로그램으로 연결을 받은 CPU 번호로 소켓을 고르고 워커를 해당 CPU에 고정
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptStrategy {
+    Shared,
+    #[cfg(target_os = "linux")]
+    Reuseport {
+        steer_by_cpu: bool,
+    },
+}
+
+impl AcceptStrategy {
+    //listen 주소 하나당 마스터가 만들 소켓 수 (Unix 소켓은 SO_REUSEPORT 분배가 없으므로 항상 공유)
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    pub fn sockets_per_listener(self, addr: &Address, worker_processes: usize) -> usize {
+        match (self, addr) {
+            (_, Address::Unix(_)) | (AcceptStrategy::Shared, _) => 1,
+            #[cfg(target_os = "linux")]
+            (AcceptStrategy::Reuseport { .. }, Address::Tcp(_)) => worker_processes,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub keepalive_timeout: Duration, //keep-alive 연결에서 다음 요청을 기다리는 최대 시간
+    pub keepalive_requests: usize,   //연결 하나로 처리할 최대 요청 수, 넘으면 응답 후 연결 종료
+    pub client_timeout: Duration, //요청 도중(헤더/바디 수신 중) 클라이언트가 아무것도 보내지 않을 때 기다리는 최대 시간
+    pub send_timeout: Duration,   //응답 전송 시 소켓 쓰기 최대 대기 시간
+    pub client_max_body_size: u64,
+    pub sendfile: bool, //정적 파일을 sendfile로 전송 (io_uring 이벤트 루프는 항상 버퍼 복사)
+    pub open_file_cache: Option<OpenFileCacheConfig>, //워커별 열린 파일 캐시 (없으면 off)
+    pub open_file_cache_valid: Duration, //캐시 항목을 디스크에서 다시 확인하는 주기
+    pub open_file_cache_errors: bool, //없는 파일 등 에러 결과도 캐시
+    pub upstreams: Vec<UpstreamConfig>, //upstream 블록 + proxy_pass에 주소를 직접 쓴 암묵적 그룹
+    pub servers: Vec<ServerConfig>,
+}
+
+impl HttpConfig {
+    //요청 파서 제한값
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_body_size: self.client_max_body_size,
+            ..Limits::default()
+        }
+    }
+}
+
+//open_file_cache max=N [inactive=시간]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenFileCacheConfig {
+    pub max: usize,         //워커마다 유지할 최대 항목 수 (열린 fd 수)
+    pub inactive: Duration, //이 시간 동안 쓰이지 않은 항목은 닫음
+}
+
+//수신 소켓 하나
+#[derive(Debug, Clone)]
+pub struct ListenConfig {
+    pub addr: Address,
+    pub backlog: i32,
+    pub ipv6only: bool, //IPv6 주소만 의미 있음 (false면 IPv4 연결도 받는 dual-stack 소켓)
+    pub mode: Option<u32>, //Unix 소켓 파일 권한 (없으면 umask에 따름)
+    pub rcvbuf: Option<i32>, //SO_RCVBUF (없으면 커널 기본값, accept한 연결이 물려받음)
+    pub sndbuf: Option<i32>, //SO_SNDBUF
+    pub keepalive: Option<KeepaliveConfig>, //SO_KEEPALIVE (없으면 커널 기본값)
+    pub tcp_nodelay: bool, //accept한 연결에 TCP_NODELAY 설정 (기본 on)
+    #[cfg(target_os = "linux")]
+    pub deferred: bool, //TCP_DEFER_ACCEPT: 클라이언트가 데이터를 보낸 뒤에야 accept됨
+    #[cfg(target_os = "linux")]
+    pub fastopen: Option<i32>, //TCP_FASTOPEN 큐 길이 (SYN에 실린 데이터를 바로 받음)
+    #[cfg(target_os = "linux")]
+    pub accept4: bool, //accept4(SOCK_NONBLOCK | SOCK_CLOEXEC)로 논블로킹 설정까지 한 번에 (기본 on)
+    pub servers: Vec<usize>, //이 주소로 들어온 요청을 받을 server 인덱스 (첫 번째가 기본 server)
+}
+
+//so_keepalive=on|off|[idle]:[interval]:[count] (비어 있는 값은 커널 기본값)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepaliveConfig {
+    pub enabled: bool,
+    pub idle: Option<Duration>, //TCP_KEEPIDLE: 유휴 상태가 이만큼 지나면 probe 시작
+    pub interval: Option<Duration>, //TCP_KEEPINTVL: probe 간격
+    pub count: Option<i32>,     //TCP_KEEPCNT: 응답이 없으면 연결을 끊기까지 보낼 probe 수
+}
+
+/*
+ 소켓 주소 (listen, 업스트림 공통)
+ - Tcp: IPv4/IPv6 주소와 포트
+ - Unix: Unix 도메인 소켓 파일 경로 (설정에서는 unix:/path)
+*/
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Address {
+    Tcp(SocketAddr),
+    Unix(PathBuf),
+}
+
+impl Address {
+    pub fn tcp(&self) -> Option<&SocketAddr> {
+        match self {
+            Address::Tcp(addr) => Some(addr),
+            Address::Unix(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::Tcp(addr) => write!(f, "{}", addr),
+            Address::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+//Display 형식을 다시 읽음 (unix:/path 또는 IP:포트)
+impl FromStr for Address {
+    type Err = std::net::AddrParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("unix:") {
+            Some(path) => Ok(Address::Unix(PathBuf::from(path))),
+            None => s.parse().map(Address::Tcp),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub names: Vec<String>, //server_name (소문자, *.example.com 와일드카드 가능)
+    pub files: FilesConfig, //맞는 location이 없을 때 쓰는 정적 파일 설정 (location의 기본값)
+    pub error_pages: HashMap<u16, String>, //error_page: 상태 코드 → 디스크에서 읽을 페이지 URI
+    pub locations: Vec<LocationConfig>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LocationConfig {
+    pub path: String,
+    pub exact: bool,                //location = /path
+    pub ret: Option<ReturnConfig>,  //return 지시어
+    pub proxy: Option<ProxyConfig>, //proxy_pass 지시어
+    pub files: FilesConfig,         //지정하지 않은 항목은 server 값을 물려받음
+}
+
+/*
+ proxy_pass http://upstream이름[/uri] | http://주소[:포트][/uri] | http://unix:/경로[:/uri]
+ - uri가 없으면 요청 URI를 그대로 전달, 있으면 location 경로 부분을 uri로 바꿔서 전달
+*/
+#[derive(Debug, Clone)]
+pub struct ProxyConfig {
+    pub upstream: usize, //http.upstreams 인덱스 (주소를 직접 쓰면 그 주소만 가진 그룹)
+    pub uri: Option<String>,
+    pub next_upstream: NextUpstream,
+    pub timeouts: ProxyTimeouts,
+    pub buffering: ProxyBuffering,
+}
+
+/*
+ 업스트림 타임아웃 (넘으면 504, 응답 헤더를 보낸 뒤면 응답을 끊음)
+ - proxy_connect_timeout: 연결 수립
+ - proxy_send_timeout: 요청 바디를 보내는 동안 업스트림이 받아 가지 않고 멈춰 있는 시간
+ - proxy_read_timeout: 응답 헤더/바디 조각 사이에 아무것도 오지 않는 시간
+ - proxy_total_timeout: 요청 하나를 시작해서 응답 바디를 다 받을 때까지 (0이면 제한 없음, 재시도마다 새로 셈)
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyTimeouts {
+    pub connect: Duration,
+    pub send: Duration,
+    pub read: Duration,
+    pub total: Duration,
+}
+
+impl Default for ProxyTimeouts {
+    fn default() -> Self {
+        ProxyTimeouts {
+            connect: Duration::from_secs(60),
+            send: Duration::from_secs(60),
+            read: Duration::from_secs(60),
+            total: Duration::ZERO,
+        }
+    }
+}
+
+/*
+ 업스트림 요청/응답 버퍼링
+ - proxy_request_buffering on: 클라이언트 요청 바디를 다 받은 뒤 보냄 (다른 서버로 다시 보낼 수 있음)
+   off: 받는 대로 업스트림에 흘려보냄 (chunked, 다시 보낼 수 없음)
+ - proxy_buffering on: 클라이언트가 느려도 업스트림 응답을 계속 읽어 버퍼에 쌓음
+   (proxy_buffers 개수 * 크기만큼 메모리, 넘으면 proxy_temp_path 아래 임시 파일에 최대 proxy_max_temp_file_size까지)
+   off: 클라이언트가 읽어 가는 만큼만 업스트림에서 읽음
+*/
+#[derive(Debug, Clone)]
+pub struct ProxyBuffering {
+    pub request: bool,
+    pub response: bool,
+    pub buffers: usize,          //메모리 버퍼 개수
+    pub buffer_size: usize,      //버퍼 하나의 크기 (임시 파일에서 한 번에 읽는 크기)
+    pub max_temp_file_size: u64, //0이면 임시 파일을 쓰지 않음 (메모리가 차면 업스트림 읽기를 멈춤)
+    pub temp_path: PathBuf,
+}
+
+impl Default for ProxyBuffering {
+    fn default() -> Self {
+        ProxyBuffering {
+            request: true,
+            response: true,
+            buffers: 8,
+            buffer_size: 8 * 1024,
+            max_temp_file_size: 1024 * 1024 * 1024,
+            temp_path: std::env::temp_dir(),
+        }
+    }
+}
+
+/*
+ 업스트림 요청이 실패했을 때 같은 그룹의 다른 서버로 다시 보낼 조건
+ - proxy_next_upstream error | timeout | http_502 | http_503 | http_504 | non_idempotent | off ...
+ - proxy_next_upstream_tries N: 처음 요청을 포함한 최대 시도 횟수 (0이면 서버 수만큼)
+ - proxy_next_upstream_timeout 시간: 처음 요청부터 이 시간이 지나면 더 시도하지 않음 (0이면 제한 없음)
+ - 응답 헤더를 클라이언트에 보내기 전에만 다시 보냄, 조건에 맞는 실패는 서버 실패(max_fails 등)로도 셈
+*/
+#[derive(Debug, Clone)]
+pub struct NextUpstream {
+    pub error: bool,          //연결 실패, 요청 전송/응답 헤더 수신 중 에러
+    pub timeout: bool,        //연결/응답 헤더 타임아웃
+    pub statuses: Vec<u16>,   //이 상태 코드 응답이면 다음 서버로 (502, 503, 504 중)
+    pub non_idempotent: bool, //POST, PATCH 등도 다시 보냄 (기본은 멱등 메서드만)
+    pub tries: usize,
+    pub timeout_total: Duration,
+}
+
+impl Default for NextUpstream {
+    fn default() -> Self {
+        NextUpstream {
+            error: true,
+            timeout: true,
+            statuses: Vec::new(),
+            non_idempotent: false,
+            tries: 0,
+            timeout_total: Duration::ZERO,
+        }
+    }
+}
+
+/*
+ upstream 이름 { server ...; 분산 방식; health_check ...; keepalive ...; }
+ - keepalive: 워커마다 서버 하나당 유지할 유휴 연결 수 (0이면 요청마다 새로 연결)
+ - keepalive_timeout: 유휴 연결을 닫기까지의 시간
+*/
+#[derive(Debug, Clone)]
+pub struct UpstreamConfig {
+    pub name: String,
+    pub balance: Balance,
+    pub servers: Vec<UpstreamServer>, //호스트 이름은 설정을 읽을 때 주소마다 하나씩 풀어 둠
+    pub health_check: Option<HealthCheckConfig>,
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    pub keepalive: usize,
+    pub keepalive_timeout: Duration,
+}
+
+/*
+ upstream의 server 주소 [weight=N] [max_fails=N] [fail_timeout=시간]
+ - fail_timeout 안에 max_fails번 실패하면 fail_timeout 동안 보내지 않음 (max_fails=0이면 끔)
+*/
+#[derive(Debug, Clone)]
+pub struct UpstreamServer {
+    pub addr: Address,
+    pub weight: u32,
+    pub max_fails: u32,
+    pub fail_timeout: Duration,
+}
+
+/*
+ circuit_breaker failures=N [timeout=시간]
+ - 서버마다 연속 failures번 실패하면 회로를 열어 timeout 동안 요청을 보내지 않음 (open)
+ - timeout이 지나면 모든 워커 중 요청 하나만 시험으로 보냄 (half-open)
+   성공하면 회로를 닫고, 실패하면 다시 timeout 동안 열어 둠
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerConfig {
+    pub failures: u32,
+    pub timeout: Duration,
+}
+
+/*
+ health_check [interval=시간] [timeout=시간] [fails=N] [passes=N] [uri=경로] [status=코드,범위] [body=문자열]
+ - interval마다 모든 워커 중 하나가 서버마다 GET uri를 보내서 상태 코드와 바디를 확인
+ - fails번 연속 실패하면 비정상, 비정상인 서버는 passes번 연속 성공하면 다시 정상
+*/
+#[derive(Debug, Clone)]
+pub struct HealthCheckConfig {
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub fails: u32,
+    pub passes: u32,
+    pub uri: String,
+    pub status: Vec<(u16, u16)>, //허용 상태 코드 범위 (양 끝 포함)
+    pub body: Option<String>,    //바디에 들어 있어야 하는 문자열
+}
+
+/*
+ upstream 부하 분산 방식
+ - RoundRobin: 기본, 가중치 라운드 로빈
+ - LeastConn: least_conn, 진행 중인 요청 수 / 가중치가 가장 작은 서버
+ - IpHash: ip_hash, 클라이언트 주소로 서버 고정
+ - Hash: hash 키 [consistent], 요청 값(변수)으로 서버 고정, consistent면 해시 링 (서버가 바뀌어도 일부 키만 이동)
+ - Random: random [two], 가중치 랜덤, two면 두 개를 뽑아 요청 수가 적은 쪽 (power of two choices)
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Balance {
+    RoundRobin,
+    LeastConn,
+    IpHash,
+    Hash { key: Vec<Segment>, consistent: bool },
+    Random { two: bool },
+}
+
+//변수가 섞인 설정 값 ("$host$request_uri" → [Variable(Host), Variable(RequestUri)])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Text(String),
+    Variable(Variable),
+}
+
+//요청 변수
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Variable {
+    RemoteAddr,     //$remote_addr (Unix 소켓이면 "unix:")
+    RequestUri,     //$request_uri: 쿼리 포함 원본 URI
+    Uri,            //$uri: 쿼리 없는 경로
+    Args,           //$args, $query_string
+    Host,           //$host: Host 헤더 (포트 제외, 소문자)
+    RequestMethod,  //$request_method
+    Http(String),   //$http_이름: 요청 헤더 (이름의 _는 -로)
+    Arg(String),    //$arg_이름: 쿼리 파라미터
+    Cookie(String), //$cookie_이름
+}
+
+//정적 파일 응답 설정
+#[derive(Debug, Clone, Default)]
+pub struct FilesConfig {
+    pub root: Option<PathBuf>, //정적 파일 루트 (prefix 적용됨, 없으면 정적 파일을 서빙하지 않음)
+    pub index: Vec<String>,    //디렉터리 요청(/로 끝나는 경로)에 찾아볼 파일 (순서대로)
+    pub expires: Option<Expires>, //Expires, Cache-Control: max-age 헤더
+    pub cache_control: Option<String>, //Cache-Control 헤더 값 (expires가 만든 값보다 우선)
+}
+
+//expires 지시어
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expires {
+    Off,             //헤더를 추가하지 않음 (server 설정을 끌 때)
+    Epoch,           //1970년으로 (Cache-Control: no-cache)
+    Max,             //2037년 말로 (Cache-Control: max-age=10년)
+    After(Duration), //응답 시각 + 기간
+}
+
+//return 코드 [텍스트 | URL]
+#[derive(Debug, Clone)]
+pub struct ReturnConfig {
+    pub status: StatusCode,
+    pub text: Option<String>, //3xx면 Location URL, 나머지는 응답 바디
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        EventsConfig {
+            worker_connections: 1024,
+            method: EventMethod::Poller,
+            accept_strategy: AcceptStrategy::Shared,
+        }
+    }
+}
+
+impl Default for HttpConfig {
+    //nginx 기본값과 맞춤
+    fn default() -> Self {
+        HttpConfig {
+            keepalive_timeout: Duration::from_secs(75),
+            keepalive_requests: 1000,
+            client_timeout: Duration::from_secs(60),
+            send_timeout: Duration::from_secs(60),
+            client_max_body_size: 1024 * 1024,
+            sendfile: true,
+            open_file_cache: None,
+            open_file_cache_valid: Duration::from_secs(60),
+            open_file_cache_errors: false,
+            upstreams: Vec::new(),
+            servers: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    cell::RefCell,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"]; //1970-01-01은 목요일
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+thread_local! {
+    //Date 헤더는 초 단위라 같은 초 안에서는 다시 포맷할 필요가 없음 (nginx의 시간 캐시와 같은 아이디어)
+    static CACHED: RefCell<(u64, String)> = const { RefCell::new((u64::MAX, String::new())) };
+}
+
+//현재 시각의 IMF-fixdate (Date 헤더용, 스레드별 캐시)
+pub fn now_http_date() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    CACHED.with(|cached| {
+        let mut cached = cached.borrow_mut();
+        if cached.0 != secs {
+            *cached = (secs, format_unix(secs));
+        }
+        cached.1.clone()
+    })
+}
+
+/*
+ IMF-fixdate 포맷 (RFC 9110 5.6.7)
+ - 예) Sun, 06 Nov 1994 08:49:37 GMT
+*/
+pub fn format_http_date(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    format_unix(secs)
+}
+
+fn format_unix(secs: u64) -> String {
+    let days = secs / 86400;
+    let rem = secs % 86400;
+    let (year, month, day) = civil_from_days(days as i64);
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        DAYS[(days % 7) as usize],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/*
+ HTTP-date 파싱
+ - IMF-fixdate:  Sun, 06 Nov 1994 08:49:37 GMT
+ - RFC 850:      Sunday, 06-Nov-94 08:49:37 GMT
+ - asctime:      Sun Nov  6 08:49:37 1994
+*/
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let value = value.trim();
+
+    let (year, month, day, time) = if let Some((_, rest)) = value.split_once(", ") {
+        let parts: Vec<&str> = rest.split([' ', '-']).collect();
+        match parts.as_slice() {
+            [day, month, year, time, "GMT"] => {
+                let mut year: i64 = year.parse().ok()?;
+                if year < 100 {
+                    //RFC 850 두 자리 연도
+                    year += if year < 70 { 2000 } else { 1900 };
+                }
+                (year, parse_month(month)?, day.parse().ok()?, *time)
+            }
+            _ => return None,
+        }
+    } else {
+        let parts: Vec<&str> = value.split_whitespace().collect();
+        match parts.as_slice() {
+            [_, month, day, time, year] => (
+                year.parse().ok()?,
+                parse_month(month)?,
+                day.parse().ok()?,
+                *time,
+            ),
+            _ => return None,
+        }
+    };
+
+    let mut hms = time.split(':').map(|n| n.parse::<u64>().ok());
+    let (Some(Some(h)), Some(Some(m)), Some(Some(s)), None) =
+        (hms.next(), hms.next(), hms.next(), hms.next())
+    else {
+        return None;
+    };
+
+    if !(1..=31).contains(&day) || h > 23 || m > 59 || s > 60 {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    if days < 0 {
+        return None;
+    }
+
+    let secs = days as u64 * 86400 + h * 3600 + m * 60 + s;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+fn parse_month(name: &str) -> Option<u32> {
+    MONTHS.iter().position(|m| *m == name).map(|i| i as u32 + 1)
+}
+
+//(연, 월, 일) → 1970-01-01 기준 일수
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+//1970-01-01 기준 일수 → (연, 월, 일) (Howard Hinnant의 civil_from_days 알고리즘)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unix(value: &str) -> Option<u64> {
+        parse_http_date(value).map(|time| time.duration_since(UNIX_EPOCH).unwrap().as_secs())
+    }
+
+    #[test]
+    fn parses_all_three_formats() {
+        //RFC 9110 5.6.7의 예
+        for value in [
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            "Sunday, 06-Nov-94 08:49:37 GMT",
+            "Sun Nov  6 08:49:37 1994",
+            "  Sun, 06 Nov 1994 08:49:37 GMT ",
+        ] {
+            assert_eq!(unix(value), Some(784_111_777), "{}", value);
+        }
+
+        //RFC 850 두 자리 연도: 70 미만은 20xx
+        assert_eq!(unix("Thursday, 01-Jan-70 00:00:00 GMT"), Some(0));
+        assert_eq!(unix("Sunday, 13-Sep-20 12:26:40 GMT"), Some(1_600_000_000));
+        assert_eq!(unix("Thu Feb 29 00:00:00 2024"), Some(1_709_164_800));
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for value in [
+            "",
+            "yesterday",
+            "Sun, 06 Nov 1994 08:49:37 UTC",
+            "Sun, 06 Foo 1994 08:49:37 GMT",
+            "Sun, 32 Nov 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 24:00:00 GMT",
+            "Sun, 06 Nov 1994 08:49 GMT",
+            "Sun, 06 Nov 1994 08:49:37:00 GMT",
+            "Sun Nov 6 08:49:37",
+            "Wed, 31 Dec 1969 23:59:59 GMT",
+        ] {
+            assert_eq!(parse_http_date(value), None, "{}", value);
+        }
+    }
+
+    #[test]
+    fn formats_imf_fixdate() {
+        let time = UNIX_EPOCH + Duration::from_secs(784_111_777);
+        assert_eq!(format_http_date(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(parse_http_date(&format_http_date(time)), Some(time));
+        assert_eq!(
+            format_http_date(UNIX_EPOCH),
+            "Thu, 01 Jan 1970 00:00:00 GMT"
+        );
+    }
+}
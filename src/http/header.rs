@@ -0,0 +1,100 @@
+use std::fmt;
+
+/*
+ 대소문자 구분 없는 헤더 맵
+ - HTTP 헤더 이름은 대소문자를 구분하지 않음 (RFC 9110 5.1)
+ - 같은 이름의 헤더가 여러 번 올 수 있으므로 (Set-Cookie 등) Vec로 순서 그대로 보관
+ - 원래 이름의 대소문자는 그대로 유지 (프록시로 넘길 때 원본 형태 유지)
+*/
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>, //(이름, 값)
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        HeaderMap {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        HeaderMap {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    //첫 번째로 일치하는 헤더 값
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    //같은 이름의 헤더 값 전체
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.entries
+            .iter()
+            .filter(move |(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries
+            .iter()
+            .any(|(n, _)| n.eq_ignore_ascii_case(name))
+    }
+
+    //기존 값을 모두 지우고 하나로 교체
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name: String = name.into();
+        self.remove(&name);
+        self.entries.push((name, value.into()));
+    }
+
+    //기존 값은 유지하고 뒤에 추가
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.entries.push((name.into(), value.into()));
+    }
+
+    //해당 이름의 헤더를 모두 제거 (제거된 개수 리턴)
+    pub fn remove(&mut self, name: &str) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+        before - self.entries.len()
+    }
+
+    /*
+     콤마로 구분된 리스트 헤더에 토큰이 있는지 확인
+     - 예) Connection: keep-alive, Upgrade / Transfer-Encoding: gzip, chunked
+     - 같은 이름의 헤더가 여러 줄로 와도 모두 검사
+    */
+    pub fn has_token(&self, name: &str, token: &str) -> bool {
+        self.get_all(name)
+            .flat_map(|v| v.split(','))
+            .any(|t| t.trim().eq_ignore_ascii_case(token))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(n, v)| (n.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+impl fmt::Display for HeaderMap {
+    //와이어 포맷 그대로 출력 (Name: value\r\n)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.entries {
+            write!(f, "{}: {}\r\n", name, value)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+/*
+ HTTP/1.1 프로토콜 모듈
+ - header: 대소문자 구분 없는 헤더 맵
+ - request: 파싱이 끝난 요청 (메서드, 경로, 쿼리, 헤더, 바디)
+ - parser: 소켓에서 조금씩 들어오는 바이트를 요청으로 조립하는 증분 파서
+ - response: 상태 코드/헤더/바디를 가진 응답과 직렬화
+ - date: HTTP-date 포맷 (Date, Last-Modified 헤더)과 파싱 (If-Modified-Since 등)
+*/
+pub mod date;
+mod header;
+mod parser;
+mod request;
+mod response;
+mod status;
+
+pub use header::HeaderMap;
+pub use parser::{BodyDecoder, ChunkState, Limits, ParseError, RequestParser};
+pub use request::{Method, Request, Version};
+pub use response::{Body, BodyWriter, Response, SERVER_NAME};
+pub use status::StatusCode;
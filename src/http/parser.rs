@@ -0,0 +1,952 @@
+use std::{error, fmt};
+
+use crate::http::{HeaderMap, Method, Request, StatusCode, Version};
+
+//청크 크기 라인 / 트레일러 한 줄의 최대 길이
+const MAX_CHUNK_LINE: usize = 4096;
+
+/*
+ 파서 크기 제한
+ - max_request_line: 요청 라인 최대 길이 (초과 시 414)
+ - max_header_size: 요청 라인 + 헤더 전체 최대 바이트 (초과 시 431)
+ - max_headers: 헤더 최대 개수 (초과 시 431)
+ - max_body_size: 바디 최대 바이트 (초과 시 413, nginx의 client_max_body_size)
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    pub max_request_line: usize,
+    pub max_header_size: usize,
+    pub max_headers: usize,
+    pub max_body_size: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_request_line: 8 * 1024,
+            max_header_size: 32 * 1024,
+            max_headers: 100,
+            max_body_size: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    BadRequest(&'static str),     //400: 문법 오류
+    UriTooLong,                   //414
+    HeaderFieldsTooLarge,         //431
+    PayloadTooLarge,              //413
+    NotImplemented(&'static str), //501: 지원하지 않는 Transfer-Encoding
+    VersionNotSupported,          //505
+}
+
+impl ParseError {
+    //클라이언트에게 돌려줄 상태 코드
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ParseError::BadRequest(_) => StatusCode::BadRequest,
+            ParseError::UriTooLong => StatusCode::UriTooLong,
+            ParseError::HeaderFieldsTooLarge => StatusCode::RequestHeaderFieldsTooLarge,
+            ParseError::PayloadTooLarge => StatusCode::ContentTooLarge,
+            ParseError::NotImplemented(_) => StatusCode::NotImplemented,
+            ParseError::VersionNotSupported => StatusCode::HttpVersionNotSupported,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::BadRequest(reason) => write!(f, "bad request: {}", reason),
+            ParseError::UriTooLong => f.write_str("request line too long"),
+            ParseError::HeaderFieldsTooLarge => f.write_str("request header fields too large"),
+            ParseError::PayloadTooLarge => f.write_str("request body too large"),
+            ParseError::NotImplemented(reason) => write!(f, "not implemented: {}", reason),
+            ParseError::VersionNotSupported => f.write_str("http version not supported"),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+/*
+ 바디 디코더 (Content-Length / chunked)
+ - 입력을 받은 만큼만 소비하고, 디코딩된 바이트는 out에 이어 붙임
+ - 소켓에서 조금씩 읽히는 상황을 가정하므로 언제든 중간에 끊겨도 됨
+*/
+#[derive(Debug, Clone)]
+pub enum BodyDecoder {
+    Empty,
+    Length {
+        remaining: u64,
+    },
+    Chunked {
+        state: ChunkState,
+        total: u64,
+        max: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkState {
+    Size,      //청크 크기 라인 대기 (1a;ext=1\r\n)
+    Data(u64), //남은 청크 데이터
+    DataEnd,   //청크 데이터 뒤 CRLF
+    Trailer,   //0 청크 이후 트레일러 (빈 줄로 끝)
+    Done,
+}
+
+impl BodyDecoder {
+    pub fn length(length: u64) -> BodyDecoder {
+        if length == 0 {
+            BodyDecoder::Empty
+        } else {
+            BodyDecoder::Length { remaining: length }
+        }
+    }
+
+    pub fn chunked(max: u64) -> BodyDecoder {
+        BodyDecoder::Chunked {
+            state: ChunkState::Size,
+            total: 0,
+            max,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        match self {
+            BodyDecoder::Empty => true,
+            BodyDecoder::Length { remaining } => *remaining == 0,
+            BodyDecoder::Chunked { state, .. } => *state == ChunkState::Done,
+        }
+    }
+
+    //input에서 소비한 바이트 수 리턴 (디코딩 결과는 out에 추가)
+    pub fn decode(&mut self, input: &[u8], out: &mut Vec<u8>) -> Result<usize, ParseError> {
+        match self {
+            BodyDecoder::Empty => Ok(0),
+            BodyDecoder::Length { remaining } => {
+                let take = (*remaining).min(input.len() as u64) as usize;
+                out.extend_from_slice(&input[..take]);
+                *remaining -= take as u64;
+                Ok(take)
+            }
+            BodyDecoder::Chunked { state, total, max } => {
+                decode_chunked(state, total, *max, input, out)
+            }
+        }
+    }
+}
+
+fn decode_chunked(
+    state: &mut ChunkState,
+    total: &mut u64,
+    max: u64,
+    input: &[u8],
+    out: &mut Vec<u8>,
+) -> Result<usize, ParseError> {
+    let mut pos = 0;
+
+    loop {
+        let rest = &input[pos..];
+
+        match *state {
+            ChunkState::Size => {
+                let Some((line, used)) = take_line(rest, MAX_CHUNK_LINE)? else {
+                    return Ok(pos);
+                };
+                pos += used;
+
+                let size = parse_chunk_size(line)?;
+                if total.saturating_add(size) > max {
+                    return Err(ParseError::PayloadTooLarge);
+                }
+                *total += size;
+
+                *state = if size == 0 {
+                    ChunkState::Trailer
+                } else {
+                    ChunkState::Data(size)
+                };
+            }
+            ChunkState::Data(remaining) => {
+                if rest.is_empty() {
+                    return Ok(pos);
+                }
+                let take = remaining.min(rest.len() as u64) as usize;
+                out.extend_from_slice(&rest[..take]);
+                pos += take;
+
+                *state = if remaining == take as u64 {
+                    ChunkState::DataEnd
+                } else {
+                    ChunkState::Data(remaining - take as u64)
+                };
+            }
+            ChunkState::DataEnd => {
+                let Some((line, used)) = take_line(rest, 2)? else {
+                    return Ok(pos);
+                };
+                if !line.is_empty() {
+                    return Err(ParseError::BadRequest("missing CRLF after chunk data"));
+                }
+                pos += used;
+                *state = ChunkState::Size;
+            }
+            ChunkState::Trailer => {
+                //트레일러 필드는 사용하지 않으므로 버림
+                let Some((line, used)) = take_line(rest, MAX_CHUNK_LINE)? else {
+                    return Ok(pos);
+                };
+                pos += used;
+
+                if line.is_empty() {
+                    *state = ChunkState::Done;
+                } else if line[0] == b' ' || line[0] == b'\t' {
+                    return Err(ParseError::BadRequest("obsolete line folding in trailer"));
+                }
+            }
+            ChunkState::Done => return Ok(pos),
+        }
+    }
+}
+
+//청크 크기(16진수) 파싱, ';' 뒤 확장(chunk-ext)은 무시
+fn parse_chunk_size(line: &[u8]) -> Result<u64, ParseError> {
+    let digits_end = line
+        .iter()
+        .position(|b| !b.is_ascii_hexdigit())
+        .unwrap_or(line.len());
+
+    let (digits, rest) = line.split_at(digits_end);
+
+    if digits.is_empty() || digits.len() > 16 {
+        return Err(ParseError::BadRequest("invalid chunk size"));
+    }
+
+    let rest = trim_ows(rest);
+    if !rest.is_empty() && rest[0] != b';' {
+        return Err(ParseError::BadRequest("invalid chunk size"));
+    }
+
+    let digits = std::str::from_utf8(digits).unwrap();
+    u64::from_str_radix(digits, 16).map_err(|_| ParseError::BadRequest("invalid chunk size"))
+}
+
+/*
+ 증분 HTTP/1.1 요청 파서
+ - 호출자는 소켓에서 읽은 데이터를 하나의 버퍼에 계속 이어 붙이고, 같은 버퍼로 parse()를 반복 호출
+ - 이미 확인한 위치를 기억하므로 매번 처음부터 다시 스캔하지 않음
+ - 요청이 완성되면 (요청, 소비한 바이트 수)를 리턴하고 다음 요청을 받을 수 있게 초기화
+   → 소비한 바이트 이후의 데이터는 파이프라이닝된 다음 요청
+ - 바디를 다 받기 전에 요청을 넘겨야 하면 stream_body()로 꺼내고, 나머지 바디는 decode_body()로 받음
+*/
+#[derive(Debug)]
+pub struct RequestParser {
+    limits: Limits,
+    state: State,
+}
+
+#[derive(Debug)]
+enum State {
+    Head {
+        scanned: usize, //헤더 끝(빈 줄)을 찾기 위해 이미 훑어본 바이트 수
+    },
+    Body {
+        request: Box<Request>,
+        pos: usize, //버퍼에서 다음으로 디코딩할 위치
+        decoder: BodyDecoder,
+        body: Vec<u8>,
+    },
+    Streaming {
+        decoder: BodyDecoder, //요청은 이미 넘겼고 바디만 받는 중
+    },
+}
+
+impl RequestParser {
+    pub fn new(limits: Limits) -> RequestParser {
+        RequestParser {
+            limits,
+            state: State::Head { scanned: 0 },
+        }
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    //요청 헤더 수신이 끝나고 바디를 받는 중인지
+    pub fn is_reading_body(&self) -> bool {
+        matches!(self.state, State::Body { .. })
+    }
+
+    //요청을 넘긴 뒤 나머지 바디를 받는 중인지 (stream_body() 이후 바디 끝까지)
+    pub fn is_streaming_body(&self) -> bool {
+        matches!(self.state, State::Streaming { .. })
+    }
+
+    //헤더를 다 받고 바디를 받는 중인 요청 (바디는 아직 비어 있음)
+    pub fn head(&self) -> Option<&Request> {
+        match &self.state {
+            State::Body { request, .. } => Some(request),
+            _ => None,
+        }
+    }
+
+    /*
+     바디를 받는 중인 요청을 바디 없이 꺼냄 (바디를 받기 전이면 None)
+     - (요청, 지금까지 디코딩한 바디, 버퍼에서 소비한 바이트 수)
+     - 이후 바디는 decode_body()로 받고, 다 받으면 다음 요청을 파싱할 수 있음
+    */
+    pub fn stream_body(&mut self) -> Option<(Request, Vec<u8>, usize)> {
+        if !self.is_reading_body() {
+            return None;
+        }
+
+        let State::Body {
+            request,
+            pos,
+            decoder,
+            body,
+        } = std::mem::replace(&mut self.state, State::Head { scanned: 0 })
+        else {
+            unreachable!();
+        };
+
+        self.state = State::Streaming { decoder };
+        Some((*request, body, pos))
+    }
+
+    //stream_body() 이후 바디 디코딩 (버퍼에서 소비한 바이트 수 리턴, 바디가 끝나면 다음 요청 대기로)
+    pub fn decode_body(&mut self, buf: &[u8], out: &mut Vec<u8>) -> Result<usize, ParseError> {
+        let State::Streaming { decoder } = &mut self.state else {
+            return Ok(0);
+        };
+
+        let used = decoder.decode(buf, out)?;
+        if decoder.is_done() {
+            self.state = State::Head { scanned: 0 };
+        }
+        Ok(used)
+    }
+
+    //버퍼가 비었고 아무것도 파싱하지 않은 상태인지 (요청 사이 유휴 상태)
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, State::Head { scanned: 0 })
+    }
+
+    pub fn reset(&mut self) {
+        self.state = State::Head { scanned: 0 };
+    }
+
+    pub fn parse(&mut self, buf: &[u8]) -> Result<Option<(Request, usize)>, ParseError> {
+        if self.is_streaming_body() {
+            return Ok(None);
+        }
+
+        if let State::Head { scanned } = &mut self.state {
+            let Some(head_len) = find_head_end(buf, scanned, &self.limits)? else {
+                return Ok(None);
+            };
+
+            let (request, decoder) = parse_head(&buf[..head_len], &self.limits)?;
+            self.state = State::Body {
+                request: Box::new(request),
+                pos: head_len,
+                decoder,
+                body: Vec::new(),
+            };
+        }
+
+        let State::Body {
+            pos, decoder, body, ..
+        } = &mut self.state
+        else {
+            unreachable!();
+        };
+
+        *pos += decoder.decode(&buf[*pos..], body)?;
+
+        if !decoder.is_done() {
+            return Ok(None);
+        }
+
+        let State::Body {
+            mut request,
+            pos,
+            body,
+            ..
+        } = std::mem::replace(&mut self.state, State::Head { scanned: 0 })
+        else {
+            unreachable!();
+        };
+
+        request.set_body(body);
+        Ok(Some((*request, pos)))
+    }
+}
+
+/*
+ 헤더 블록 끝 위치(빈 줄 다음) 찾기
+ - CRLF CRLF 뿐만 아니라 LF LF도 허용 (RFC 9112 2.2)
+ - 찾지 못했는데 제한을 넘었으면 에러
+*/
+fn find_head_end(
+    buf: &[u8],
+    scanned: &mut usize,
+    limits: &Limits,
+) -> Result<Option<usize>, ParseError> {
+    //요청 라인 앞의 빈 줄은 무시 (RFC 9112 2.2)
+    let start = buf
+        .iter()
+        .position(|b| *b != b'\r' && *b != b'\n')
+        .unwrap_or(buf.len());
+
+    let from = (*scanned).max(start);
+
+    for i in from..buf.len() {
+        if buf[i] != b'\n' {
+            continue;
+        }
+
+        let blank_line = (i > start && buf[i - 1] == b'\n')
+            || (i > start + 1 && buf[i - 1] == b'\r' && buf[i - 2] == b'\n');
+
+        if blank_line {
+            if i + 1 - start > limits.max_header_size {
+                return Err(ParseError::HeaderFieldsTooLarge);
+            }
+            return Ok(Some(i + 1));
+        }
+    }
+
+    //다음 호출 때는 마지막 몇 바이트만 다시 보면 됨
+    *scanned = buf.len().saturating_sub(3).max(start);
+
+    let pending = &buf[start..];
+    let request_line_len = pending
+        .iter()
+        .position(|b| *b == b'\n')
+        .unwrap_or(pending.len());
+    if request_line_len > limits.max_request_line {
+        return Err(ParseError::UriTooLong);
+    }
+    if pending.len() > limits.max_header_size {
+        return Err(ParseError::HeaderFieldsTooLarge);
+    }
+
+    Ok(None)
+}
+
+//요청 라인 + 헤더 파싱 후 바디 디코더 결정
+fn parse_head(head: &[u8], limits: &Limits) -> Result<(Request, BodyDecoder), ParseError> {
+    let mut lines = head
+        .split(|b| *b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .skip_while(|line| line.is_empty());
+
+    let request_line = lines
+        .next()
+        .ok_or(ParseError::BadRequest("empty request"))?;
+    if request_line.len() > limits.max_request_line {
+        return Err(ParseError::UriTooLong);
+    }
+    let (method, target, version) = parse_request_line(request_line)?;
+
+    let mut headers = HeaderMap::with_capacity(16);
+
+    for line in lines {
+        if line.is_empty() {
+            break;
+        }
+
+        //obs-fold (이전 헤더 값을 다음 줄로 이어 쓰기)는 거부 (RFC 9112 5.2)
+        if line[0] == b' ' || line[0] == b'\t' {
+            return Err(ParseError::BadRequest("obsolete line folding"));
+        }
+
+        if headers.len() >= limits.max_headers {
+            return Err(ParseError::HeaderFieldsTooLarge);
+        }
+
+        let (name, value) = parse_header_line(line)?;
+        headers.append(name, value);
+    }
+
+    let decoder = body_decoder(&headers, version, limits)?;
+
+    if version == Version::Http11 && headers.get_all("Host").count() != 1 {
+        return Err(ParseError::BadRequest("missing or duplicate Host header"));
+    }
+
+    Ok((Request::new(method, target, version, headers), decoder))
+}
+
+//METHOD SP request-target SP HTTP-version
+fn parse_request_line(line: &[u8]) -> Result<(Method, &str, Version), ParseError> {
+    let line =
+        std::str::from_utf8(line).map_err(|_| ParseError::BadRequest("invalid request line"))?;
+
+    let mut parts = line.split(' ');
+    let (Some(method), Some(target), Some(version), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(ParseError::BadRequest("invalid request line"));
+    };
+
+    if method.is_empty() || !method.bytes().all(is_token_char) {
+        return Err(ParseError::BadRequest("invalid method"));
+    }
+
+    if target.is_empty() || target.bytes().any(|b| b <= b' ' || b == 0x7f) {
+        return Err(ParseError::BadRequest("invalid request target"));
+    }
+
+    Ok((Method::from_token(method), target, parse_version(version)?))
+}
+
+fn parse_version(version: &str) -> Result<Version, ParseError> {
+    match version {
+        "HTTP/1.1" => Ok(Version::Http11),
+        "HTTP/1.0" => Ok(Version::Http10),
+        _ => {
+            //형식은 맞지만 지원하지 않는 버전 (HTTP/2.0, HTTP/0.9 등)
+            let bytes = version.as_bytes();
+            if bytes.len() == 8
+                && version.starts_with("HTTP/")
+                && bytes[5].is_ascii_digit()
+                && bytes[6] == b'.'
+                && bytes[7].is_ascii_digit()
+            {
+                Err(ParseError::VersionNotSupported)
+            } else {
+                Err(ParseError::BadRequest("invalid http version"))
+            }
+        }
+    }
+}
+
+//field-name ":" OWS field-value OWS
+fn parse_header_line(line: &[u8]) -> Result<(&str, &str), ParseError> {
+    let colon = line
+        .iter()
+        .position(|b| *b == b':')
+        .ok_or(ParseError::BadRequest("header without colon"))?;
+
+    let (name, value) = (&line[..colon], trim_ows(&line[colon + 1..]));
+
+    //이름과 콜론 사이 공백도 여기서 걸러짐 (RFC 9112 5.1)
+    if name.is_empty() || !name.iter().copied().all(is_token_char) {
+        return Err(ParseError::BadRequest("invalid header name"));
+    }
+
+    if value
+        .iter()
+        .any(|b| (*b < b' ' && *b != b'\t') || *b == 0x7f)
+    {
+        return Err(ParseError::BadRequest("invalid header value"));
+    }
+
+    let name = std::str::from_utf8(name).unwrap();
+    let value =
+        std::str::from_utf8(value).map_err(|_| ParseError::BadRequest("invalid header value"))?;
+
+    Ok((name, value))
+}
+
+/*
+ 바디 길이 결정 (RFC 9112 6.3)
+ - Transfer-Encoding과 Content-Length가 함께 오면 요청 스머글링 위험이 있으므로 거부
+ - Transfer-Encoding은 chunked만 지원
+ - Content-Length가 여러 개면 값이 모두 같아야 함
+*/
+fn body_decoder(
+    headers: &HeaderMap,
+    version: Version,
+    limits: &Limits,
+) -> Result<BodyDecoder, ParseError> {
+    let has_te = headers.contains("Transfer-Encoding");
+    let has_cl = headers.contains("Content-Length");
+
+    if has_te {
+        if has_cl {
+            return Err(ParseError::BadRequest(
+                "both Transfer-Encoding and Content-Length",
+            ));
+        }
+        if version == Version::Http10 {
+            return Err(ParseError::BadRequest(
+                "Transfer-Encoding in HTTP/1.0 request",
+            ));
+        }
+
+        let mut codings = headers
+            .get_all("Transfer-Encoding")
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|c| !c.is_empty());
+
+        return match (codings.next(), codings.next()) {
+            (Some(coding), None) if coding.eq_ignore_ascii_case("chunked") => {
+                Ok(BodyDecoder::chunked(limits.max_body_size))
+            }
+            _ => Err(ParseError::NotImplemented("unsupported transfer coding")),
+        };
+    }
+
+    if has_cl {
+        let mut length: Option<u64> = None;
+
+        for value in headers.get_all("Content-Length").flat_map(|v| v.split(',')) {
+            let value = value.trim();
+            if value.is_empty() || value.len() > 19 || !value.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(ParseError::BadRequest("invalid Content-Length"));
+            }
+
+            let parsed: u64 = value.parse().unwrap();
+            if length.is_some_and(|l| l != parsed) {
+                return Err(ParseError::BadRequest("conflicting Content-Length"));
+            }
+            length = Some(parsed);
+        }
+
+        let length = length.unwrap_or(0);
+        if length > limits.max_body_size {
+            return Err(ParseError::PayloadTooLarge);
+        }
+
+        return Ok(BodyDecoder::length(length));
+    }
+
+    Ok(BodyDecoder::Empty)
+}
+
+//한 줄(LF로 끝남) 잘라내기, CR은 제거. 줄이 아직 덜 왔으면 None
+fn take_line(input: &[u8], max: usize) -> Result<Option<(&[u8], usize)>, ParseError> {
+    match input.iter().position(|b| *b == b'\n') {
+        Some(i) => {
+            let line = &input[..i];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.len() > max {
+                return Err(ParseError::BadRequest("line too long"));
+            }
+            Ok(Some((line, i + 1)))
+        }
+        None if input.len() > max + 1 => Err(ParseError::BadRequest("line too long")),
+        None => Ok(None),
+    }
+}
+
+fn trim_ows(mut bytes: &[u8]) -> &[u8] {
+    while let [b' ' | b'\t', rest @ ..] = bytes {
+        bytes = rest;
+    }
+    while let [rest @ .., b' ' | b'\t'] = bytes {
+        bytes = rest;
+    }
+    bytes
+}
+
+//tchar (RFC 9110 5.6.2)
+fn is_token_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[u8]) -> Result<Option<(Request, usize)>, ParseError> {
+        RequestParser::new(Limits::default()).parse(input)
+    }
+
+    fn parse_err(input: &[u8]) -> ParseError {
+        parse(input).expect_err("request should be rejected")
+    }
+
+    #[test]
+    fn parses_simple_get() {
+        let input = b"GET /index.html?a=1 HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let (request, consumed) = parse(input).unwrap().unwrap();
+
+        assert_eq!(consumed, input.len());
+        assert_eq!(request.method(), &Method::Get);
+        assert_eq!(request.path(), "/index.html");
+        assert_eq!(request.query(), Some("a=1"));
+        assert_eq!(request.version(), Version::Http11);
+        assert_eq!(request.header("host"), Some("example.com"));
+        assert!(request.body().is_empty());
+    }
+
+    #[test]
+    fn waits_for_more_data_byte_by_byte() {
+        let input = b"POST /upload HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello";
+        let mut parser = RequestParser::new(Limits::default());
+
+        for end in 1..input.len() {
+            assert!(
+                parser.parse(&input[..end]).unwrap().is_none(),
+                "end={}",
+                end
+            );
+        }
+
+        let (request, consumed) = parser.parse(input).unwrap().unwrap();
+        assert_eq!(consumed, input.len());
+        assert_eq!(request.body(), b"hello");
+        assert!(parser.is_idle());
+    }
+
+    #[test]
+    fn returns_offset_of_pipelined_request() {
+        let first = b"GET /a HTTP/1.1\r\nHost: a\r\n\r\n".as_slice();
+        let second = b"POST /b HTTP/1.1\r\nHost: a\r\nContent-Length: 2\r\n\r\nok".as_slice();
+        let input = [first, second].concat();
+        let mut parser = RequestParser::new(Limits::default());
+
+        let (request, consumed) = parser.parse(&input).unwrap().unwrap();
+        assert_eq!(request.path(), "/a");
+        assert_eq!(consumed, first.len());
+
+        let (request, consumed) = parser.parse(&input[first.len()..]).unwrap().unwrap();
+        assert_eq!(request.path(), "/b");
+        assert_eq!(request.body(), b"ok");
+        assert_eq!(consumed, second.len());
+    }
+
+    #[test]
+    fn ignores_leading_empty_lines_and_accepts_bare_lf() {
+        let input = b"\r\n\nGET / HTTP/1.1\nHost: a\n\n";
+        let (request, consumed) = parse(input).unwrap().unwrap();
+
+        assert_eq!(request.path(), "/");
+        assert_eq!(consumed, input.len());
+    }
+
+    #[test]
+    fn decodes_chunked_body_with_extensions_and_trailers() {
+        let input = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n\
+                      5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nX-Trailer: 1\r\n\r\nNEXT";
+        let (request, consumed) = parse(input).unwrap().unwrap();
+
+        assert_eq!(request.body(), b"hello world");
+        assert_eq!(&input[consumed..], b"NEXT");
+    }
+
+    #[test]
+    fn rejects_invalid_chunks() {
+        let head = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n";
+        for body in [
+            b"zz\r\n".as_slice(),
+            b"5\r\nhelloXX\r\n".as_slice(),
+            b"12345678901234567\r\n".as_slice(),
+        ] {
+            let input = [head.as_slice(), body].concat();
+            assert!(
+                matches!(parse_err(&input), ParseError::BadRequest(_)),
+                "{:?}",
+                String::from_utf8_lossy(body)
+            );
+        }
+
+        let input = [head.as_slice(), b"0\r\n folded\r\n\r\n"].concat();
+        assert!(matches!(parse_err(&input), ParseError::BadRequest(_)));
+    }
+
+    #[test]
+    fn rejects_transfer_encoding_with_content_length() {
+        let input = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\nContent-Length: 5\r\n\r\n";
+        assert!(matches!(parse_err(input), ParseError::BadRequest(_)));
+    }
+
+    #[test]
+    fn rejects_unsupported_transfer_codings() {
+        let input = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: gzip, chunked\r\n\r\n";
+        assert!(matches!(parse_err(input), ParseError::NotImplemented(_)));
+
+        let input = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: identity\r\n\r\n";
+        assert!(matches!(parse_err(input), ParseError::NotImplemented(_)));
+
+        let input = b"POST / HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n";
+        assert!(matches!(parse_err(input), ParseError::BadRequest(_)));
+    }
+
+    #[test]
+    fn checks_repeated_content_length() {
+        let input =
+            b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 2\r\nContent-Length: 2\r\n\r\nok";
+        assert_eq!(parse(input).unwrap().unwrap().0.body(), b"ok");
+
+        let input = b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 2, 3\r\n\r\nok";
+        assert!(matches!(parse_err(input), ParseError::BadRequest(_)));
+
+        for value in ["-1", "+2", "0x10", ""] {
+            let input = format!(
+                "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: {}\r\n\r\n",
+                value
+            );
+            assert!(
+                matches!(parse_err(input.as_bytes()), ParseError::BadRequest(_)),
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_obsolete_line_folding() {
+        let input = b"GET / HTTP/1.1\r\nHost: a\r\nX-Long: a\r\n b\r\n\r\n";
+        assert!(matches!(parse_err(input), ParseError::BadRequest(_)));
+    }
+
+    #[test]
+    fn rejects_invalid_header_names() {
+        let input = b"GET / HTTP/1.1\r\nHost : a\r\n\r\n";
+        assert!(matches!(parse_err(input), ParseError::BadRequest(_)));
+
+        let input = b"GET / HTTP/1.1\r\nHost: a\r\nNoColon\r\n\r\n";
+        assert!(matches!(parse_err(input), ParseError::BadRequest(_)));
+    }
+
+    #[test]
+    fn requires_single_host_for_http11() {
+        let input = b"GET / HTTP/1.1\r\n\r\n";
+        assert!(matches!(parse_err(input), ParseError::BadRequest(_)));
+
+        let input = b"GET / HTTP/1.1\r\nHost: a\r\nHost: b\r\n\r\n";
+        assert!(matches!(parse_err(input), ParseError::BadRequest(_)));
+
+        let input = b"GET / HTTP/1.0\r\n\r\n";
+        assert!(parse(input).unwrap().is_some());
+    }
+
+    #[test]
+    fn rejects_bad_request_lines_and_versions() {
+        assert!(matches!(
+            parse_err(b"GET  / HTTP/1.1\r\nHost: a\r\n\r\n"),
+            ParseError::BadRequest(_)
+        ));
+        assert!(matches!(
+            parse_err(b"GET / HTTX/1.1\r\nHost: a\r\n\r\n"),
+            ParseError::BadRequest(_)
+        ));
+        assert_eq!(
+            parse_err(b"GET / HTTP/2.0\r\nHost: a\r\n\r\n"),
+            ParseError::VersionNotSupported
+        );
+    }
+
+    #[test]
+    fn enforces_size_limits() {
+        let limits = Limits {
+            max_request_line: 32,
+            max_header_size: 128,
+            max_headers: 2,
+            max_body_size: 4,
+        };
+        let parse = |input: &[u8]| RequestParser::new(limits).parse(input).unwrap_err();
+
+        let long_uri = format!("GET /{} HTTP/1.1\r\n", "a".repeat(64));
+        assert_eq!(parse(long_uri.as_bytes()), ParseError::UriTooLong);
+
+        let big_header = format!("GET / HTTP/1.1\r\nX: {}\r\n", "a".repeat(200));
+        assert_eq!(
+            parse(big_header.as_bytes()),
+            ParseError::HeaderFieldsTooLarge
+        );
+
+        let many_headers = b"GET / HTTP/1.1\r\nHost: a\r\nA: 1\r\nB: 2\r\n\r\n";
+        assert_eq!(parse(many_headers), ParseError::HeaderFieldsTooLarge);
+
+        let long_body = b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\n";
+        assert_eq!(parse(long_body), ParseError::PayloadTooLarge);
+
+        let long_chunks =
+            b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\n";
+        assert_eq!(parse(long_chunks), ParseError::PayloadTooLarge);
+    }
+
+    #[test]
+    fn streams_body_after_head() {
+        let head = b"POST /up HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n".as_slice();
+        let input = [head, b"3\r\nabc\r\n2\r\nd"].concat();
+        let mut parser = RequestParser::new(Limits::default());
+
+        assert!(parser.stream_body().is_none());
+        assert!(parser.parse(&input).unwrap().is_none());
+        assert_eq!(parser.head().map(Request::path), Some("/up"));
+
+        let (request, body, consumed) = parser.stream_body().unwrap();
+        assert_eq!(request.path(), "/up");
+        assert!(request.body().is_empty());
+        assert_eq!(body, b"abcd");
+        assert_eq!(consumed, input.len());
+        assert!(parser.is_streaming_body());
+        assert!(parser.head().is_none());
+
+        //나머지 바디를 받는 동안에는 다음 요청을 파싱하지 않음
+        let rest = b"e\r\n0\r\n\r\nGET /next HTTP/1.1\r\nHost: a\r\n\r\n";
+        assert!(parser.parse(rest).unwrap().is_none());
+
+        let mut out = Vec::new();
+        let used = parser.decode_body(rest, &mut out).unwrap();
+        assert_eq!(out, b"e");
+        assert!(!parser.is_streaming_body());
+
+        let (request, _) = parser.parse(&rest[used..]).unwrap().unwrap();
+        assert_eq!(request.path(), "/next");
+    }
+
+    #[test]
+    fn decodes_streamed_body_in_pieces() {
+        let input = b"PUT / HTTP/1.1\r\nHost: a\r\nContent-Length: 6\r\n\r\n";
+        let mut parser = RequestParser::new(Limits::default());
+
+        assert!(parser.parse(input).unwrap().is_none());
+        let (_, body, consumed) = parser.stream_body().unwrap();
+        assert!(body.is_empty());
+        assert_eq!(consumed, input.len());
+
+        let mut out = Vec::new();
+        assert_eq!(parser.decode_body(b"abc", &mut out), Ok(3));
+        assert!(parser.is_streaming_body());
+        assert_eq!(parser.decode_body(b"defXYZ", &mut out), Ok(3));
+        assert_eq!(out, b"abcdef");
+        assert!(parser.is_idle());
+
+        //스트리밍 중이 아니면 아무것도 소비하지 않음
+        assert_eq!(parser.decode_body(b"XYZ", &mut out), Ok(0));
+    }
+
+    #[test]
+    fn rejects_invalid_streamed_chunks() {
+        let input = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n1\r\n";
+        let mut parser = RequestParser::new(Limits::default());
+
+        assert!(parser.parse(input).unwrap().is_none());
+        parser.stream_body().unwrap();
+
+        let mut out = Vec::new();
+        assert!(matches!(
+            parser.decode_body(b"aXX\r\n", &mut out),
+            Err(ParseError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn maps_errors_to_status_codes() {
+        assert_eq!(
+            ParseError::PayloadTooLarge.status_code(),
+            StatusCode::ContentTooLarge
+        );
+        assert_eq!(
+            ParseError::HeaderFieldsTooLarge.status_code(),
+            StatusCode::RequestHeaderFieldsTooLarge
+        );
+        assert_eq!(
+            ParseError::NotImplemented("x").status_code(),
+            StatusCode::NotImplemented
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::fmt;
+
+use crate::http::HeaderMap;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Head,
+    Post,
+    Put,
+    Delete,
+    Connect,
+    Options,
+    Trace,
+    Patch,
+    Other(String), //확장 메서드 (WebDAV 등), 토큰 문법만 검사
+}
+
+impl Method {
+    //메서드는 대소문자를 구분함 (RFC 9110 9.1)
+    pub fn from_token(token: &str) -> Method {
+        match token {
+            "GET" => Method::Get,
+            "HEAD" => Method::Head,
+            "POST" => Method::Post,
+            "PUT" => Method::Put,
+            "DELETE" => Method::Delete,
+            "CONNECT" => Method::Connect,
+            "OPTIONS" => Method::Options,
+            "TRACE" => Method::Trace,
+            "PATCH" => Method::Patch,
+            other => Method::Other(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Method::Get => "GET",
+            Method::Head => "HEAD",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Connect => "CONNECT",
+            Method::Options => "OPTIONS",
+            Method::Trace => "TRACE",
+            Method::Patch => "PATCH",
+            Method::Other(token) => token,
+        }
+    }
+
+    //같은 요청을 여러 번 보내도 결과가 같은 메서드 (RFC 9110 9.2.2), 확장 메서드는 알 수 없으므로 false
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            Method::Get
+                | Method::Head
+                | Method::Put
+                | Method::Delete
+                | Method::Options
+                | Method::Trace
+        )
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Version {
+    Http10,
+    Http11,
+}
+
+impl Version {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Version::Http10 => "HTTP/1.0",
+            Version::Http11 => "HTTP/1.1",
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/*
+ 파싱이 끝난 HTTP 요청
+ - target: 요청 라인의 원본 request-target (예: /search?q=rust)
+ - path: 쿼리를 제외한 경로 (퍼센트 디코딩 전 원본)
+ - query: '?' 뒤 문자열 (없으면 None)
+*/
+#[derive(Debug, Clone)]
+pub struct Request {
+    method: Method,
+    target: String,
+    path: String,
+    query: Option<String>,
+    version: Version,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl Request {
+    pub fn new(method: Method, target: &str, version: Version, headers: HeaderMap) -> Request {
+        let (path, query) = split_target(target);
+
+        Request {
+            method,
+            target: target.to_string(),
+            path,
+            query,
+            version,
+            headers,
+            body: Vec::new(),
+        }
+    }
+
+    pub fn method(&self) -> &Method {
+        &self.method
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    //쿼리 문자열에서 key에 해당하는 첫 번째 값 (디코딩 없이 원본 그대로)
+    pub fn query_param(&self, key: &str) -> Option<&str> {
+        self.query.as_deref()?.split('&').find_map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (k == key).then_some(v)
+        })
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    //응답 후 연결을 유지할지 (HTTP/1.1은 기본 유지, HTTP/1.0은 keep-alive를 명시해야 유지)
+    pub fn keep_alive(&self) -> bool {
+        match self.version {
+            Version::Http11 => !self.headers.has_token("Connection", "close"),
+            Version::Http10 => self.headers.has_token("Connection", "keep-alive"),
+        }
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)
+    }
+
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = body;
+    }
+
+    pub fn into_body(self) -> Vec<u8> {
+        self.body
+    }
+}
+
+/*
+ request-target에서 path와 query 분리
+ - origin-form: /path?query
+ - absolute-form: http://host/path?query (프록시 요청) → path 부분만 추출
+ - asterisk-form(*), authority-form(host:port)은 그대로 path로 취급
+*/
+fn split_target(target: &str) -> (String, Option<String>) {
+    let without_fragment = target.split('#').next().unwrap_or(target);
+
+    let origin = match without_fragment.find("://") {
+        Some(scheme_end) => {
+            let rest = &without_fragment[scheme_end + 3..];
+            match rest.find(['/', '?']) {
+                Some(i) if rest.as_bytes()[i] == b'/' => &rest[i..],
+                Some(i) => return ("/".to_string(), Some(rest[i + 1..].to_string())),
+                None => "/",
+            }
+        }
+        None => without_fragment,
+    };
+
+    match origin.split_once('?') {
+        Some((path, query)) => (path.to_string(), Some(query.to_string())),
+        None => (origin.to_string(), None),
+    }
+}
@@ -0,0 +1,461 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    fs::File,
+    io::{ErrorKind, Read, Result, Write},
+    os::unix::fs::FileExt,
+    sync::Arc,
+};
+
+use crate::http::{HeaderMap, Method, StatusCode, Version, date::now_http_date};
+
+pub const SERVER_NAME: &str = "Rustify";
+
+//한 번에 파일/스트림에서 읽어 오는 최대 크기
+const WRITE_CHUNK: usize = 64 * 1024;
+
+/*
+ 응답 바디
+ - Bytes: 메모리에 있는 바이트 (에러 페이지, 작은 응답)
+ - File: 파일의 offset부터 len 바이트 (정적 파일, 열린 파일 캐시와 fd를 공유할 수 있도록 Arc)
+ - Stream: 길이를 모르는 스트림 (프록시 응답 등) → chunked로 전송 (Parts 안에 있으면 모든 조각을 청크로)
+ - Parts: 여러 바디를 순서대로 이어 붙임 (multipart/byteranges: 구분 헤더 + 파일 구간)
+*/
+pub enum Body {
+    Empty,
+    Bytes(Vec<u8>),
+    File {
+        file: Arc<File>,
+        offset: u64,
+        len: u64,
+    },
+    Stream(Box<dyn Read + Send>),
+    Parts(Vec<Body>),
+}
+
+impl Body {
+    //길이를 알 수 있으면 Some (Content-Length로 사용)
+    pub fn len(&self) -> Option<u64> {
+        match self {
+            Body::Empty => Some(0),
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::File { len, .. } => Some(*len),
+            Body::Stream(_) => None,
+            Body::Parts(parts) => parts.iter().map(Body::len).sum(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Body::Empty => f.write_str("Empty"),
+            Body::Bytes(bytes) => write!(f, "Bytes({})", bytes.len()),
+            Body::File { offset, len, .. } => write!(f, "File({}..{})", offset, offset + len),
+            Body::Stream(_) => f.write_str("Stream"),
+            Body::Parts(parts) => f.debug_list().entries(parts).finish(),
+        }
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Body {
+        Body::Bytes(bytes)
+    }
+}
+
+impl From<String> for Body {
+    fn from(text: String) -> Body {
+        Body::Bytes(text.into_bytes())
+    }
+}
+
+impl From<&str> for Body {
+    fn from(text: &str) -> Body {
+        Body::Bytes(text.as_bytes().to_vec())
+    }
+}
+
+/*
+ HTTP 응답
+ - new()로 만들고 with_header()/with_body()로 이어서 조립
+ - prepare()에서 Date/Server/Content-Length(또는 chunked) 같은 자동 헤더를 채움
+ - 정적 파일, 프록시, 에러 페이지 모두 같은 경로(encode_head + BodyWriter)로 직렬화
+*/
+#[derive(Debug)]
+pub struct Response {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Body,
+    chunked: bool,   //prepare() 결과: 바디를 chunked로 인코딩할지
+    head_only: bool, //prepare() 결과: 바디를 보내지 않음 (HEAD, 204, 304 등)
+}
+
+impl Response {
+    pub fn new(status: StatusCode) -> Response {
+        Response {
+            status,
+            headers: HeaderMap::new(),
+            body: Body::Empty,
+            chunked: false,
+            head_only: false,
+        }
+    }
+
+    //상태 코드만 보여주는 기본 HTML 에러 페이지
+    pub fn error(status: StatusCode) -> Response {
+        let html = format!(
+            "<html>\r\n<head><title>{status}</title></head>\r\n<body>\r\n<center><h1>{status}</h1></center>\r\n<hr><center>{SERVER_NAME}</center>\r\n</body>\r\n</html>\r\n"
+        );
+
+        Response::new(status)
+            .with_header("Content-Type", "text/html; charset=utf-8")
+            .with_body(html)
+    }
+
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Response {
+        self.headers.insert(name, value);
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Body>) -> Response {
+        self.body = body.into();
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn set_status(&mut self, status: StatusCode) {
+        self.status = status;
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    pub fn set_body(&mut self, body: impl Into<Body>) {
+        self.body = body.into();
+    }
+
+    /*
+     전송 직전 자동 헤더 채우기
+     - Date, Server: 없으면 추가
+     - 바디 길이를 알면 Content-Length, 모르면 HTTP/1.1은 chunked / HTTP/1.0은 연결 종료로 끝 표시
+     - Stream 바디에 Content-Length가 이미 있으면 (프록시 응답) 그 길이만큼 그대로 전송
+     - 1xx/204/304 응답과 HEAD 요청은 바디 없이 헤더만 전송
+    */
+    pub fn prepare(&mut self, method: &Method, version: Version) {
+        if !self.headers.contains("Date") {
+            self.headers.insert("Date", now_http_date());
+        }
+        if !self.headers.contains("Server") {
+            self.headers.insert("Server", SERVER_NAME);
+        }
+
+        self.chunked = false;
+
+        if !self.status.allows_body() {
+            self.headers.remove("Transfer-Encoding");
+            if self.status != StatusCode::NotModified {
+                self.headers.remove("Content-Length");
+            }
+            self.body = Body::Empty;
+            self.head_only = true;
+            return;
+        }
+
+        match self.body.len() {
+            Some(len) => {
+                self.headers.remove("Transfer-Encoding");
+                self.headers.insert("Content-Length", len.to_string());
+            }
+            None if self.headers.contains("Content-Length") => {}
+            None if version == Version::Http11 => {
+                self.headers.insert("Transfer-Encoding", "chunked");
+                self.chunked = true;
+            }
+            None => {
+                //HTTP/1.0은 chunked를 모르므로 연결을 닫아서 바디 끝을 알림
+                self.headers.insert("Connection", "close");
+            }
+        }
+
+        self.head_only = *method == Method::Head;
+    }
+
+    //응답 후 연결을 닫아야 하는지 (Connection: close)
+    pub fn closes_connection(&self) -> bool {
+        self.headers.has_token("Connection", "close")
+    }
+
+    //상태 라인 + 헤더 + 빈 줄
+    pub fn encode_head(&self) -> Vec<u8> {
+        let mut head = Vec::with_capacity(256);
+        head.extend_from_slice(format!("HTTP/1.1 {}\r\n", self.status).as_bytes());
+        head.extend_from_slice(self.headers.to_string().as_bytes());
+        head.extend_from_slice(b"\r\n");
+        head
+    }
+
+    //prepare() 이후 바디를 조금씩 인코딩해 줄 writer
+    pub fn into_body_writer(self) -> BodyWriter {
+        let mut parts = VecDeque::new();
+        if !self.head_only {
+            flatten(self.body, &mut parts);
+        }
+
+        BodyWriter {
+            finished: parts.is_empty(),
+            parts,
+            chunked: self.chunked,
+            sent: 0,
+        }
+    }
+
+    //블로킹 소켓에 응답 전체를 전송
+    pub fn write_to<W: Write>(
+        mut self,
+        w: &mut W,
+        method: &Method,
+        version: Version,
+    ) -> Result<()> {
+        self.prepare(method, version);
+        self.write_prepared(w)
+    }
+
+    //prepare()가 끝난 응답을 블로킹 소켓에 전송
+    pub fn write_prepared<W: Write>(self, w: &mut W) -> Result<()> {
+        w.write_all(&self.encode_head())?;
+
+        let mut writer = self.into_body_writer();
+        let mut buf: Vec<u8> = Vec::with_capacity(WRITE_CHUNK);
+
+        loop {
+            let finished = writer.fill(&mut buf, WRITE_CHUNK)?;
+            w.write_all(&buf)?;
+            buf.clear();
+
+            if finished {
+                break;
+            }
+        }
+
+        w.flush()
+    }
+}
+
+//Parts를 펼쳐서 보낼 순서대로 (빈 조각은 제외)
+fn flatten(body: Body, out: &mut VecDeque<Body>) {
+    match body {
+        Body::Empty => {}
+        Body::Parts(parts) => {
+            for part in parts {
+                flatten(part, out);
+            }
+        }
+        body => out.push_back(body),
+    }
+}
+
+/*
+ 바디 인코더
+ - fill()을 부를 때마다 최대 max 바이트 정도를 out에 추가 (chunked면 청크 프레이밍 포함)
+ - 논블로킹 소켓에서는 버퍼가 비워질 때마다 다시 fill()을 호출하는 식으로 사용
+ - 맨 앞 조각이 파일이면 file_part()/advance()로 유저 공간 복사 없이 (sendfile) 보낼 수 있음
+*/
+pub struct BodyWriter {
+    parts: VecDeque<Body>, //아직 다 보내지 않은 조각
+    chunked: bool,
+    sent: u64, //맨 앞 File 조각에서 지금까지 보낸 바이트 수
+    finished: bool,
+}
+
+impl BodyWriter {
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    //맨 앞 조각이 파일이면 (파일, 보낼 위치, 남은 바이트 수)
+    pub fn file_part(&self) -> Option<(&File, u64, u64)> {
+        match self.parts.front() {
+            Some(Body::File { file, offset, len }) if !self.chunked => {
+                Some((file, *offset + self.sent, *len - self.sent))
+            }
+            _ => None,
+        }
+    }
+
+    //file_part()의 파일에서 n 바이트를 직접 보냄
+    pub fn advance(&mut self, n: u64) {
+        let Some(Body::File { len, .. }) = self.parts.front() else {
+            return;
+        };
+
+        self.sent += n;
+        if self.sent >= *len {
+            self.next_part();
+        }
+    }
+
+    fn next_part(&mut self) {
+        self.parts.pop_front();
+        self.sent = 0;
+        self.finished = self.parts.is_empty();
+    }
+
+    //바디를 끝까지 인코딩했으면 true
+    pub fn fill(&mut self, out: &mut Vec<u8>, max: usize) -> Result<bool> {
+        let Some(part) = self.parts.front_mut() else {
+            self.finished = true;
+            return Ok(true);
+        };
+
+        let start = out.len();
+        let done = match part {
+            Body::Empty | Body::Parts(_) => true,
+            Body::Bytes(bytes) => {
+                out.append(bytes);
+                true
+            }
+            Body::File { file, offset, len } => {
+                let remaining = *len - self.sent;
+                let want = remaining.min(max as u64) as usize;
+                out.resize(start + want, 0);
+
+                let n = loop {
+                    match file.read_at(&mut out[start..], *offset + self.sent) {
+                        Ok(n) => break n,
+                        Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                        Err(e) => {
+                            out.truncate(start);
+                            return Err(e);
+                        }
+                    }
+                };
+                out.truncate(start + n);
+
+                if n == 0 && want > 0 {
+                    //응답 도중 파일이 잘려 Content-Length만큼 보낼 수 없음
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
+
+                self.sent += n as u64;
+                self.sent == *len
+            }
+            Body::Stream(reader) => {
+                let mut chunk = vec![0u8; max];
+                let n = loop {
+                    match reader.read(&mut chunk) {
+                        Ok(n) => break n,
+                        Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                        Err(e) => return Err(e),
+                    }
+                };
+
+                out.extend_from_slice(&chunk[..n]);
+                n == 0
+            }
+        };
+
+        //chunked면 조각마다 청크로 감싸고, 끝 표시(0 청크)는 마지막 조각 뒤에 한 번만
+        if self.chunked {
+            frame_chunk(out, start);
+        }
+
+        if done {
+            self.next_part();
+            if self.finished && self.chunked {
+                out.extend_from_slice(b"0\r\n\r\n");
+            }
+        }
+
+        Ok(self.finished)
+    }
+}
+
+//out[start..]를 청크 하나로 감쌈 (빈 데이터는 끝 표시로 읽히므로 그대로 둠)
+fn frame_chunk(out: &mut Vec<u8>, start: usize) {
+    let len = out.len() - start;
+    if len == 0 {
+        return;
+    }
+
+    out.splice(start..start, format!("{:x}\r\n", len).into_bytes());
+    out.extend_from_slice(b"\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Cursor, process};
+
+    use super::*;
+
+    //응답을 직렬화해서 (헤더, 바디)로 나눔
+    fn serialize(response: Response, version: Version) -> (String, Vec<u8>) {
+        let mut out = Vec::new();
+        response.write_to(&mut out, &Method::Get, version).unwrap();
+
+        let end = out.windows(4).position(|w| w == b"\r\n\r\n").unwrap() + 4;
+        let body = out.split_off(end);
+        (String::from_utf8(out).unwrap(), body)
+    }
+
+    fn mixed_parts(file: &Arc<File>) -> Body {
+        Body::Parts(vec![
+            Body::from("ab"),
+            Body::Stream(Box::new(Cursor::new(b"cde".to_vec()))),
+            Body::File {
+                file: file.clone(),
+                offset: 1,
+                len: 3,
+            },
+            Body::Bytes(Vec::new()),
+            Body::from("f"),
+        ])
+    }
+
+    #[test]
+    fn frames_every_part_of_chunked_parts() {
+        let path = std::env::temp_dir().join(format!("rustify-response-{}", process::id()));
+        fs::write(&path, b"0123456").unwrap();
+        let file = Arc::new(File::open(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+
+        let response = Response::new(StatusCode::Ok).with_body(mixed_parts(&file));
+        let (head, body) = serialize(response, Version::Http11);
+        assert!(head.contains("Transfer-Encoding: chunked\r\n"));
+        assert!(!head.contains("Content-Length"));
+        assert_eq!(
+            body,
+            b"2\r\nab\r\n3\r\ncde\r\n3\r\n123\r\n1\r\nf\r\n0\r\n\r\n"
+        );
+
+        //HTTP/1.0은 청크 없이 이어 붙이고 연결을 닫아서 끝을 알림
+        let response = Response::new(StatusCode::Ok).with_body(mixed_parts(&file));
+        let (head, body) = serialize(response, Version::Http10);
+        assert!(head.contains("Connection: close\r\n"));
+        assert_eq!(body, b"abcde123f");
+    }
+
+    #[test]
+    fn terminates_stream_body_once() {
+        let stream = Body::Stream(Box::new(Cursor::new(b"hello".to_vec())));
+        let response = Response::new(StatusCode::Ok).with_body(stream);
+        let (_, body) = serialize(response, Version::Http11);
+        assert_eq!(body, b"5\r\nhello\r\n0\r\n\r\n");
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+
+/*
+ HTTP 상태 코드
+ - 자주 쓰는 코드는 이름으로, 나머지(업스트림이 보낸 임의의 코드 등)는 Other로 표현
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StatusCode {
+    Continue,
+    SwitchingProtocols,
+    Ok,
+    Created,
+    Accepted,
+    NoContent,
+    PartialContent,
+    MovedPermanently,
+    Found,
+    SeeOther,
+    NotModified,
+    TemporaryRedirect,
+    PermanentRedirect,
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    RequestTimeout,
+    Conflict,
+    LengthRequired,
+    PreconditionFailed,
+    ContentTooLarge,
+    UriTooLong,
+    UnsupportedMediaType,
+    RangeNotSatisfiable,
+    ExpectationFailed,
+    RequestHeaderFieldsTooLarge,
+    InternalServerError,
+    NotImplemented,
+    BadGateway,
+    ServiceUnavailable,
+    GatewayTimeout,
+    HttpVersionNotSupported,
+    Other(u16),
+}
+
+//(상태, 코드, 사유 문구)
+const KNOWN: &[(StatusCode, u16, &str)] = &[
+    (StatusCode::Continue, 100, "Continue"),
+    (StatusCode::SwitchingProtocols, 101, "Switching Protocols"),
+    (StatusCode::Ok, 200, "OK"),
+    (StatusCode::Created, 201, "Created"),
+    (StatusCode::Accepted, 202, "Accepted"),
+    (StatusCode::NoContent, 204, "No Content"),
+    (StatusCode::PartialContent, 206, "Partial Content"),
+    (StatusCode::MovedPermanently, 301, "Moved Permanently"),
+    (StatusCode::Found, 302, "Found"),
+    (StatusCode::SeeOther, 303, "See Other"),
+    (StatusCode::NotModified, 304, "Not Modified"),
+    (StatusCode::TemporaryRedirect, 307, "Temporary Redirect"),
+    (StatusCode::PermanentRedirect, 308, "Permanent Redirect"),
+    (StatusCode::BadRequest, 400, "Bad Request"),
+    (StatusCode::Unauthorized, 401, "Unauthorized"),
+    (StatusCode::Forbidden, 403, "Forbidden"),
+    (StatusCode::NotFound, 404, "Not Found"),
+    (StatusCode::MethodNotAllowed, 405, "Method Not Allowed"),
+    (StatusCode::RequestTimeout, 408, "Request Timeout"),
+    (StatusCode::Conflict, 409, "Conflict"),
+    (StatusCode::LengthRequired, 411, "Length Required"),
+    (StatusCode::PreconditionFailed, 412, "Precondition Failed"),
+    (StatusCode::ContentTooLarge, 413, "Content Too Large"),
+    (StatusCode::UriTooLong, 414, "URI Too Long"),
+    (
+        StatusCode::UnsupportedMediaType,
+        415,
+        "Unsupported Media Type",
+    ),
+    (
+        StatusCode::RangeNotSatisfiable,
+        416,
+        "Range Not Satisfiable",
+    ),
+    (StatusCode::ExpectationFailed, 417, "Expectation Failed"),
+    (
+        StatusCode::RequestHeaderFieldsTooLarge,
+        431,
+        "Request Header Fields Too Large",
+    ),
+    (
+        StatusCode::InternalServerError,
+        500,
+        "Internal Server Error",
+    ),
+    (StatusCode::NotImplemented, 501, "Not Implemented"),
+    (StatusCode::BadGateway, 502, "Bad Gateway"),
+    (StatusCode::ServiceUnavailable, 503, "Service Unavailable"),
+    (StatusCode::GatewayTimeout, 504, "Gateway Timeout"),
+    (
+        StatusCode::HttpVersionNotSupported,
+        505,
+        "HTTP Version Not Supported",
+    ),
+];
+
+impl StatusCode {
+    pub fn from_u16(code: u16) -> StatusCode {
+        KNOWN
+            .iter()
+            .find(|(_, c, _)| *c == code)
+            .map(|(status, _, _)| *status)
+            .unwrap_or(StatusCode::Other(code))
+    }
+
+    pub fn as_u16(&self) -> u16 {
+        match self {
+            StatusCode::Other(code) => *code,
+            known => KNOWN
+                .iter()
+                .find(|(status, _, _)| status == known)
+                .map(|(_, code, _)| *code)
+                .unwrap(),
+        }
+    }
+
+    //상태 라인에 쓰는 사유 문구 (모르는 코드는 클래스별 기본 문구)
+    pub fn reason(&self) -> &'static str {
+        let code = self.as_u16();
+        if let Some((_, _, reason)) = KNOWN.iter().find(|(_, c, _)| *c == code) {
+            return reason;
+        }
+
+        match code / 100 {
+            1 => "Informational",
+            2 => "Success",
+            3 => "Redirection",
+            4 => "Client Error",
+            _ => "Server Error",
+        }
+    }
+
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.as_u16())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.as_u16())
+    }
+
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.as_u16())
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        self.as_u16() >= 500
+    }
+
+    //1xx, 204, 304 응답은 바디를 가질 수 없음 (RFC 9112 6.3)
+    pub fn allows_body(&self) -> bool {
+        !(self.is_informational()
+            || matches!(self, StatusCode::NoContent | StatusCode::NotModified))
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.as_u16(), self.reason())
+    }
+}
@@ -1,15 +1,40 @@
+#![allow(non_snake_case)] //크레이트 이름(Rustify)은 프로젝트 이름 그대로 사용
+
+pub mod http;
+
 use std::{
     io::{Error, Result},
+    ops::BitOr,
     os::fd::RawFd,
     sync::{Arc, Mutex, mpsc},
     thread,
+    time::Duration,
 };
 
 #[cfg(target_os = "macos")]
-use libc::{EV_ADD, EV_ENABLE, EVFILT_READ};
+use libc::{
+    EV_ADD, EV_CLEAR, EV_DELETE, EV_DISABLE, EV_ENABLE, EV_EOF, EV_ERROR, EV_ONESHOT, EVFILT_READ,
+    EVFILT_WRITE, NOTE_LOWAT,
+};
 
 #[cfg(target_os = "linux")]
-use libc::{EPOLL_CTL_ADD, EPOLLIN, c_int, epoll_create1, epoll_ctl, epoll_event, epoll_wait};
+use libc::{
+    EPOLL_CLOEXEC, EPOLL_CTL_ADD, EPOLL_CTL_DEL, EPOLL_CTL_MOD, EPOLLERR, EPOLLET, EPOLLEXCLUSIVE,
+    EPOLLHUP, EPOLLIN, EPOLLONESHOT, EPOLLOUT, EPOLLRDHUP, c_int, epoll_create1, epoll_ctl,
+    epoll_event, epoll_wait,
+};
+
+/*
+ - FnOnce(): 한 번만 호출되는 클로저, 매개변수 없음
+ - Send: 다른 스레드로 안전하게 이동 가능
+ - 'static: 프로그램 전체 생명주기 동안 유효 (스레드가 언제 실행될지 모르니까)
+*/
+type Task = Box<dyn FnOnce() + Send + 'static>;
+
+struct Worker {
+    id: usize,                              //스레드 고유 ID
+    thread: Option<thread::JoinHandle<()>>, //스레드 핸들
+}
 
 /**
  ThreadPool 상세 분석
@@ -26,7 +51,7 @@ use libc::{EPOLL_CTL_ADD, EPOLLIN, c_int, epoll_create1, epoll_ctl, epoll_event,
    - 여러 워커가 동시에 메시지를 빼가지 못하도록 잠금
 
 **시각화:**
-```
+```text
 ThreadPool
 ├─ sender (송신자)
 └─ receiver (Arc<Mutex<수신자>>)
@@ -34,20 +59,8 @@ ThreadPool
    ├─ Worker 1 (Arc 복사본)
    ├─ Worker 2 (Arc 복사본)
    └─ Worker 3 (Arc 복사본)
+```
  */
-
-/*
- - FnOnce(): 한 번만 호출되는 클로저, 매개변수 없음
- - Send: 다른 스레드로 안전하게 이동 가능
- - 'static: 프로그램 전체 생명주기 동안 유효 (스레드가 언제 실행될지 모르니까)
-*/
-type Task = Box<dyn FnOnce() + Send + 'static>;
-
-struct Worker {
-    id: usize,                              //스레드 고유 ID
-    thread: Option<thread::JoinHandle<()>>, //스레드 핸들
-}
-
 pub struct ThreadPool {
     workers: Vec<Worker>,               //실제 작업 스레드들
     sender: Option<mpsc::Sender<Task>>, //작업을 워커에케 전달하는 채널의 송신자
@@ -88,7 +101,6 @@ impl Worker {
 /// 3.채널을 통해 보내려는 클로저를 가진 새로운 구조체 Job을 만듭니다.
 /// 4.execute 메서드는 송신자를 통하여 실행하려는 작업을 보냅니다.
 /// 5.Worker는 자신의 스레드에서 수신자에 대한 반복을 수행하고 자신이 받은 작업의 클로저를 실행합니다.
-
 impl ThreadPool {
     /// Create a new ThreadPool.
     ///
@@ -175,6 +187,120 @@ impl Drop for ThreadPool {
 
 extern crate libc;
 
+// ============= 이벤트 공통 타입 =============
+// 등록한 fd를 구분하기 위한 사용자 값 (이벤트가 발생하면 그대로 돌려받음)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Token(pub u64);
+
+/*
+ 감시할 이벤트와 트리거 방식 (| 로 조합)
+ - READABLE / WRITABLE: 읽기 / 쓰기 가능
+ - READ_CLOSED: 상대가 쓰기 방향을 닫음 (half-close 감지)
+ - EDGE: 엣지 트리거 (상태가 바뀔 때 한 번만 알림, WouldBlock까지 읽고 써야 함)
+ - ONESHOT: 이벤트 한 번 발생 후 비활성화, modify로 다시 활성화
+ - EXCLUSIVE: 여러 프로세스가 같은 fd를 기다릴 때 이벤트 하나에 한 곳만 깨움 (epoll 전용, 등록할 때만 지정 가능)
+ - 에러/hangup은 요청하지 않아도 항상 보고됨
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interest(u8);
+
+impl Interest {
+    pub const READABLE: Interest = Interest(0b0_0001);
+    pub const WRITABLE: Interest = Interest(0b0_0010);
+    pub const READ_CLOSED: Interest = Interest(0b0_0100);
+    pub const EDGE: Interest = Interest(0b0_1000);
+    pub const ONESHOT: Interest = Interest(0b1_0000);
+    pub const EXCLUSIVE: Interest = Interest(0b10_0000);
+
+    pub fn contains(self, other: Interest) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn with(self, other: Interest) -> Interest {
+        Interest(self.0 | other.0)
+    }
+
+    pub fn without(self, other: Interest) -> Interest {
+        Interest(self.0 & !other.0)
+    }
+}
+
+impl BitOr for Interest {
+    type Output = Interest;
+
+    fn bitor(self, other: Interest) -> Interest {
+        self.with(other)
+    }
+}
+
+// wait()로 돌려받은 이벤트 하나
+#[derive(Debug, Clone, Copy)]
+pub struct Event {
+    token: Token,
+    readable: bool,
+    writable: bool,
+    read_closed: bool,
+    hangup: bool,
+    error: bool,
+}
+
+impl Event {
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.readable
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+
+    // 상대가 쓰기 방향을 닫음 (남은 데이터는 아직 읽을 수 있음)
+    pub fn is_read_closed(&self) -> bool {
+        self.read_closed
+    }
+
+    // 양방향 모두 끊김
+    pub fn is_hangup(&self) -> bool {
+        self.hangup
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.error
+    }
+}
+
+/*
+ I/O 멀티플렉서 공통 인터페이스
+ - Linux는 Epoll, macOS는 Kqueue가 구현
+ - 워커 이벤트 루프는 이 트레이트에만 의존하므로 한 번만 작성하면 됨
+   (Linux CI에서 Epoll로 돌린 이벤트 루프 코드가 macOS에서도 그대로 Kqueue로 동작)
+*/
+pub trait Poller: Sized {
+    fn new() -> Result<Self>;
+
+    //fd 감시 시작
+    fn register(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()>;
+
+    //감시 이벤트/토큰 변경
+    fn reregister(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()>;
+
+    //감시 해제 (fd를 close하기 전에 호출)
+    fn deregister(&self, fd: RawFd) -> Result<()>;
+
+    //이벤트 대기 (timeout이 None이면 무한 대기), 받은 이벤트 수 리턴
+    fn poll(&self, events: &mut Events, timeout: Option<Duration>) -> Result<usize>;
+}
+
+//플랫폼 기본 Poller
+#[cfg(target_os = "linux")]
+pub type DefaultPoller = Epoll;
+
+#[cfg(target_os = "macos")]
+pub type DefaultPoller = Kqueue;
+
 // ============= macOS (kqueue) 구현 =============
 #[cfg(target_os = "macos")]
 pub struct Kqueue {
@@ -192,28 +318,67 @@ impl Kqueue {
             return Err(std::io::Error::last_os_error());
         }
 
+        //exec 시 자식에게 상속되지 않도록 CLOEXEC
+        unsafe { libc::fcntl(kq_fd, libc::F_SETFD, libc::FD_CLOEXEC) };
+
         Ok(Kqueue { kq_fd })
     }
 
-    //커널에 fd 등록
-    pub fn add(&self, fd: RawFd) -> Result<()> {
-        let changelist: libc::kevent = libc::kevent {
-            ident: fd as usize,             // 감시할 파일 디스크립터
-            filter: EVFILT_READ,            // 읽기 이벤트 감시
-            flags: EV_ADD | EV_ENABLE,      // 추가 + 활성화
-            fflags: 0,                      // 필터별 추가 플래그 (사용 안 함)
-            data: 0,                        // 필터별 데이터 (사용 안 함)
-            udata: fd as *mut libc::c_void, // 사용자 정의 데이터 (나중에 받을 때 사용), 여기서는 fd를 저장해서 나중에 "어떤 소켓의 이벤트인지" 구분
+    //커널에 fd 등록, 이벤트가 발생하면 token을 그대로 돌려받음
+    pub fn add(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()> {
+        self.modify(fd, token, interest)
+    }
+
+    /*
+     감시 이벤트 변경
+     - kqueue는 (fd, 필터) 쌍으로 등록되므로 읽기/쓰기 필터를 각각 추가
+     - 원하지 않는 필터는 삭제 대신 EV_DISABLE (등록된 적 없는 필터를 지우면 ENOENT)
+     - READ_CLOSED도 읽기 필터로 감지 (EV_EOF), READABLE 없이 READ_CLOSED만 원하면
+       NOTE_LOWAT을 최대로 줘서 데이터가 와도 깨우지 않고 EOF일 때만 깨움 (수신 버퍼가 가득 차면 깨어날 수 있음)
+     - EXCLUSIVE는 대응하는 기능이 없으므로 무시 (모든 대기자가 깨어남)
+    */
+    pub fn modify(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()> {
+        let mut flags: u16 = EV_ADD;
+        if interest.contains(Interest::EDGE) {
+            flags |= EV_CLEAR;
+        }
+        if interest.contains(Interest::ONESHOT) {
+            flags |= EV_ONESHOT;
+        }
+
+        let enable = |wanted: Interest| {
+            if interest.contains(wanted) {
+                flags | EV_ENABLE
+            } else {
+                flags | EV_DISABLE
+            }
+        };
+
+        let read = if interest.contains(Interest::READABLE) {
+            Self::change(fd, EVFILT_READ, flags | EV_ENABLE, token)
+        } else if interest.contains(Interest::READ_CLOSED) {
+            libc::kevent {
+                fflags: NOTE_LOWAT,
+                data: libc::intptr_t::MAX,
+                ..Self::change(fd, EVFILT_READ, flags | EV_ENABLE, token)
+            }
+        } else {
+            Self::change(fd, EVFILT_READ, flags | EV_DISABLE, token)
         };
 
+        let changelist: [libc::kevent; 2] = [
+            read,
+            Self::change(fd, EVFILT_WRITE, enable(Interest::WRITABLE), token),
+        ];
+
         let ret = unsafe {
             libc::kevent(
-                self.kq_fd,                         // kqueue fd
-                &changelist as *const libc::kevent, // 등록할 이벤트 리스트
-                1,                                  // changelist 개수
-                std::ptr::null_mut(),               // eventlist (받을 이벤트, 지금은 등록만)
-                0,                                  // eventlist 개수
-                std::ptr::null(),                   // timeout (사용 안 함)
+                self.kq_fd,              // kqueue fd
+                changelist.as_ptr(),     // 등록할 이벤트 리스트
+                changelist.len() as i32, // changelist 개수
+                std::ptr::null_mut(),    // eventlist (받을 이벤트, 지금은 등록만)
+                0,                       // eventlist 개수
+                std::ptr::null(),        // timeout (사용 안 함)
             )
         };
 
@@ -224,24 +389,63 @@ impl Kqueue {
         Ok(())
     }
 
-    //이벤트 대기(블로킹)
-    pub fn wait(&self, events: &mut [libc::kevent]) -> Result<usize> {
+    //감시 해제 (fd를 close하면 커널이 자동으로 지우지만, 명시적으로 정리할 때 사용)
+    pub fn delete(&self, fd: RawFd) -> Result<()> {
+        for filter in [EVFILT_READ, EVFILT_WRITE] {
+            let change = Self::change(fd, filter, EV_DELETE, Token(0));
+            unsafe {
+                libc::kevent(
+                    self.kq_fd,
+                    &change,
+                    1,
+                    std::ptr::null_mut(),
+                    0,
+                    std::ptr::null(),
+                )
+            };
+        }
+
+        Ok(())
+    }
+
+    fn change(fd: RawFd, filter: i16, flags: u16, token: Token) -> libc::kevent {
+        libc::kevent {
+            ident: fd as usize,                           // 감시할 파일 디스크립터
+            filter,                                       // 읽기/쓰기 필터
+            flags,                                        // 추가/삭제/활성화 등
+            fflags: 0,                                    // 필터별 추가 플래그 (사용 안 함)
+            data: 0,                                      // 필터별 데이터 (사용 안 함)
+            udata: token.0 as usize as *mut libc::c_void, // 사용자 정의 데이터, 이벤트가 오면 "어떤 연결인지" 구분
+        }
+    }
+
+    //이벤트 대기(블로킹, timeout이 None이면 무한 대기)
+    pub fn wait(&self, events: &mut Events, timeout: Option<Duration>) -> Result<usize> {
+        let timespec = timeout.map(|timeout| libc::timespec {
+            tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+            tv_nsec: timeout.subsec_nanos() as libc::c_long,
+        });
+
         let event_count = unsafe {
             libc::kevent(
-                self.kq_fd,          // kqueue fd
-                std::ptr::null(),    // changelist (없음)
-                0,                   // nchanges
-                events.as_mut_ptr(), // eventlist (발생한 이벤트를 여기에 저장)
-                events.len() as i32, // nevents (최대 128개)
-                std::ptr::null(),    // timeout (NULL = 무한 대기)
+                self.kq_fd,              // kqueue fd
+                std::ptr::null(),        // changelist (없음)
+                0,                       // nchanges
+                events.raw.as_mut_ptr(), // eventlist (발생한 이벤트를 여기에 저장)
+                events.raw.len() as i32, // nevents
+                timespec
+                    .as_ref()
+                    .map_or(std::ptr::null(), |t| t as *const libc::timespec), // NULL = 무한 대기
             )
         };
 
         if event_count < 0 {
+            events.len = 0;
             return Err(Error::last_os_error());
         }
 
-        Ok(event_count as usize)
+        events.len = event_count as usize;
+        Ok(events.len)
     }
 }
 
@@ -259,6 +463,69 @@ impl Drop for Kqueue {
     }
 }
 
+// wait()가 채워 주는 이벤트 버퍼 (kqueue는 필터마다 이벤트가 따로 옴)
+#[cfg(target_os = "macos")]
+pub struct Events {
+    raw: Vec<libc::kevent>,
+    len: usize,
+}
+
+#[cfg(target_os = "macos")]
+impl Events {
+    pub fn with_capacity(capacity: usize) -> Events {
+        Events {
+            raw: vec![unsafe { std::mem::zeroed::<libc::kevent>() }; capacity],
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Event> + '_ {
+        self.raw[..self.len].iter().map(|raw| {
+            let eof = raw.flags & EV_EOF != 0;
+
+            Event {
+                token: Token(raw.udata as usize as u64),
+                readable: raw.filter == EVFILT_READ,
+                writable: raw.filter == EVFILT_WRITE,
+                read_closed: eof && raw.filter == EVFILT_READ,
+                hangup: eof && raw.filter == EVFILT_WRITE,
+                error: raw.flags & EV_ERROR != 0,
+            }
+        })
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Poller for Kqueue {
+    fn new() -> Result<Self> {
+        Kqueue::new()
+    }
+
+    fn register(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()> {
+        self.add(fd, token, interest)
+    }
+
+    fn reregister(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()> {
+        self.modify(fd, token, interest)
+    }
+
+    fn deregister(&self, fd: RawFd) -> Result<()> {
+        self.delete(fd)
+    }
+
+    fn poll(&self, events: &mut Events, timeout: Option<Duration>) -> Result<usize> {
+        self.wait(events, timeout)
+    }
+}
+
 // ============= Linux (epoll) 구현 =============
 #[cfg(target_os = "linux")]
 pub struct Epoll {
@@ -268,8 +535,8 @@ pub struct Epoll {
 #[cfg(target_os = "linux")]
 impl Epoll {
     pub fn new() -> Result<Self> {
-        // epoll_create1(0)으로 epoll 인스턴스 생성
-        let epoll_fd: c_int = unsafe { epoll_create1(0) };
+        // epoll 인스턴스 생성 (exec 시 자식에게 상속되지 않도록 CLOEXEC)
+        let epoll_fd: c_int = unsafe { epoll_create1(EPOLL_CLOEXEC) };
 
         if epoll_fd < 0 {
             return Err(std::io::Error::last_os_error());
@@ -278,14 +545,37 @@ impl Epoll {
         Ok(Epoll { epoll_fd })
     }
 
-    // 커널에 fd 등록 (읽기 이벤트 감시)
-    pub fn add(&self, fd: RawFd) -> Result<()> {
+    // 커널에 fd 등록, 이벤트가 발생하면 token을 그대로 돌려받음
+    pub fn add(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()> {
+        self.ctl(EPOLL_CTL_ADD, fd, token, interest)
+    }
+
+    // 등록된 fd의 감시 이벤트/토큰 변경 (ONESHOT으로 비활성화된 fd를 다시 활성화할 때도 사용)
+    pub fn modify(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()> {
+        self.ctl(EPOLL_CTL_MOD, fd, token, interest)
+    }
+
+    // 감시 해제 (fd를 close하기 전에 호출)
+    pub fn delete(&self, fd: RawFd) -> Result<()> {
+        //커널 2.6.9 이전에는 DEL에도 NULL이 아닌 event 포인터가 필요했음
+        let mut event: epoll_event = epoll_event { events: 0, u64: 0 };
+
+        let ret = unsafe { epoll_ctl(self.epoll_fd, EPOLL_CTL_DEL, fd, &mut event) };
+
+        if ret < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    fn ctl(&self, op: c_int, fd: RawFd, token: Token, interest: Interest) -> Result<()> {
         let mut event: epoll_event = epoll_event {
-            events: EPOLLIN as u32, // 읽기 가능 이벤트
-            u64: fd as u64,         // 사용자 데이터 (fd를 저장)
+            events: Self::interest_to_events(interest),
+            u64: token.0, // 사용자 데이터 (연결 id 등 임의의 값)
         };
 
-        let ret = unsafe { epoll_ctl(self.epoll_fd, EPOLL_CTL_ADD as c_int, fd, &mut event) };
+        let ret = unsafe { epoll_ctl(self.epoll_fd, op, fd, &mut event) };
 
         if ret < 0 {
             return Err(std::io::Error::last_os_error());
@@ -294,22 +584,62 @@ impl Epoll {
         Ok(())
     }
 
-    // 이벤트 대기 (블로킹, timeout=-1 무한 대기)
-    pub fn wait(&self, events: &mut [epoll_event]) -> Result<usize> {
+    // EPOLLHUP, EPOLLERR은 요청하지 않아도 항상 보고됨
+    fn interest_to_events(interest: Interest) -> u32 {
+        let mut events: c_int = 0;
+
+        if interest.contains(Interest::READABLE) {
+            events |= EPOLLIN;
+        }
+        if interest.contains(Interest::WRITABLE) {
+            events |= EPOLLOUT;
+        }
+        if interest.contains(Interest::READ_CLOSED) {
+            events |= EPOLLRDHUP;
+        }
+        if interest.contains(Interest::EDGE) {
+            events |= EPOLLET;
+        }
+        if interest.contains(Interest::ONESHOT) {
+            events |= EPOLLONESHOT;
+        }
+        if interest.contains(Interest::EXCLUSIVE) {
+            events |= EPOLLEXCLUSIVE;
+        }
+
+        events as u32
+    }
+
+    /*
+     이벤트 대기 (블로킹)
+     - timeout: None이면 무한 대기, Some이면 최대 그 시간만큼 대기 (타이머 처리용)
+     - 밀리초 단위로 올림 (0.5ms를 0으로 내리면 바쁜 대기가 됨)
+    */
+    pub fn wait(&self, events: &mut Events, timeout: Option<Duration>) -> Result<usize> {
+        let timeout_ms: c_int = match timeout {
+            None => -1, // 무한 대기
+            Some(timeout) => {
+                let ms = timeout.as_nanos().div_ceil(1_000_000);
+                ms.min(c_int::MAX as u128) as c_int
+            }
+        };
+
         let event_count = unsafe {
             epoll_wait(
                 self.epoll_fd,
-                events.as_mut_ptr(),
-                events.len() as c_int,
-                -1, // 무한 대기
+                events.raw.as_mut_ptr(),
+                events.raw.len() as c_int,
+                timeout_ms,
             )
         };
 
         if event_count < 0 {
+            events.len = 0;
             return Err(Error::last_os_error());
         }
 
-        Ok(event_count as usize)
+        events.len = event_count as usize;
+        Ok(events.len)
     }
 }
 
@@ -321,3 +651,300 @@ impl Drop for Epoll {
         }
     }
 }
+
+// wait()가 채워 주는 이벤트 버퍼 (한 번에 최대 capacity개)
+#[cfg(target_os = "linux")]
+pub struct Events {
+    raw: Vec<epoll_event>,
+    len: usize,
+}
+
+#[cfg(target_os = "linux")]
+impl Events {
+    pub fn with_capacity(capacity: usize) -> Events {
+        Events {
+            raw: vec![epoll_event { events: 0, u64: 0 }; capacity],
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Event> + '_ {
+        self.raw[..self.len].iter().map(|raw| {
+            let flags = raw.events as c_int;
+
+            Event {
+                token: Token(raw.u64),
+                readable: flags & EPOLLIN != 0,
+                writable: flags & EPOLLOUT != 0,
+                read_closed: flags & EPOLLRDHUP != 0,
+                hangup: flags & EPOLLHUP != 0,
+                error: flags & EPOLLERR != 0,
+            }
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Poller for Epoll {
+    fn new() -> Result<Self> {
+        Epoll::new()
+    }
+
+    fn register(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()> {
+        self.add(fd, token, interest)
+    }
+
+    fn reregister(&self, fd: RawFd, token: Token, interest: Interest) -> Result<()> {
+        self.modify(fd, token, interest)
+    }
+
+    fn deregister(&self, fd: RawFd) -> Result<()> {
+        self.delete(fd)
+    }
+
+    fn poll(&self, events: &mut Events, timeout: Option<Duration>) -> Result<usize> {
+        self.wait(events, timeout)
+    }
+}
+
+// ============= Linux (io_uring) 구현 =============
+/*
+ 완료(completion) 기반 I/O (io-uring 기능)
+ - epoll은 "읽을 수 있다"만 알려주고 read/write 시스템 콜은 직접 해야 하지만,
+   io_uring은 accept/recv/send 자체를 커널에 제출하고 끝난 결과를 받음
+ - multishot accept/recv: 한 번 제출하면 에러가 나거나 소켓이 닫힐 때까지 계속 완료 이벤트를 보냄
+ - recv 데이터는 미리 제공한 버퍼 풀(provided buffers)에서 커널이 골라 채우고, 다 쓰면 recycle()로 반납
+ - 제출과 대기를 io_uring_enter 한 번으로 처리해서 요청당 시스템 콜 수가 크게 줄어듦
+*/
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub struct IoUring {
+    ring: io_uring::IoUring,
+    buffers: Box<[u8]>, //recv 버퍼 풀 (buffer_count * buffer_size), 커널이 쓰므로 크기가 바뀌면 안 됨
+    buffer_size: usize,
+}
+
+//완료 이벤트가 어떤 작업의 결과인지
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Accept,
+    Recv,
+    Send,
+}
+
+// wait()로 돌려받은 완료 이벤트 하나
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+#[derive(Debug, Clone, Copy)]
+pub struct Completion {
+    token: Token,
+    op: Op,
+    result: i32,
+    more: bool,
+    buffer: Option<u16>,
+}
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+impl Completion {
+    pub fn token(&self) -> Token {
+        self.token
+    }
+
+    pub fn op(&self) -> Op {
+        self.op
+    }
+
+    // accept는 새 fd, recv/send는 바이트 수 (recv의 0은 상대가 연결을 닫음)
+    pub fn result(&self) -> Result<usize> {
+        if self.result < 0 {
+            return Err(Error::from_raw_os_error(-self.result));
+        }
+        Ok(self.result as usize)
+    }
+
+    // multishot 작업이 계속 살아 있는지 (false면 다시 제출해야 이벤트를 더 받음)
+    pub fn has_more(&self) -> bool {
+        self.more
+    }
+
+    // recv 데이터가 담긴 버퍼 풀 id
+    pub fn buffer(&self) -> Option<u16> {
+        self.buffer
+    }
+}
+
+// user_data 하위 2비트에 작업 종류, 나머지에 토큰
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+const URING_OP_BITS: u64 = 2;
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+const URING_INTERNAL: u64 = 3; //버퍼 반납, 취소 요청의 완료 (wait()에서 걸러냄)
+
+//recv 버퍼 풀 그룹 id
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+const URING_BUFFER_GROUP: u16 = 0;
+
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+impl IoUring {
+    /*
+     링 생성 + recv 버퍼 풀 등록
+     - entries: 제출 큐 크기 (완료 큐는 커널이 그 2배로 잡음)
+     - buffer_count * buffer_size 만큼 메모리를 미리 잡고 커널에 제공
+    */
+    pub fn new(entries: u32, buffer_count: u16, buffer_size: usize) -> Result<Self> {
+        let ring = io_uring::IoUring::new(entries)?;
+
+        let mut uring = IoUring {
+            ring,
+            buffers: vec![0u8; buffer_count as usize * buffer_size].into_boxed_slice(),
+            buffer_size,
+        };
+
+        let entry = io_uring::opcode::ProvideBuffers::new(
+            uring.buffers.as_mut_ptr(),
+            buffer_size as i32,
+            buffer_count,
+            URING_BUFFER_GROUP,
+            0,
+        )
+        .build()
+        .user_data(URING_INTERNAL);
+        uring.push(entry)?;
+        uring.ring.submit()?;
+
+        Ok(uring)
+    }
+
+    // 리스너에 multishot accept 제출 (연결마다 Op::Accept 완료, 결과는 새 소켓 fd)
+    pub fn accept_multishot(&mut self, fd: RawFd, token: Token) -> Result<()> {
+        let entry = io_uring::opcode::AcceptMulti::new(io_uring::types::Fd(fd))
+            .flags(libc::SOCK_CLOEXEC)
+            .build()
+            .user_data(Self::user_data(token, Op::Accept));
+        self.push(entry)
+    }
+
+    // 소켓에 multishot recv 제출 (데이터가 올 때마다 버퍼 풀에서 하나를 골라 채움)
+    pub fn recv_multishot(&mut self, fd: RawFd, token: Token) -> Result<()> {
+        let entry = io_uring::opcode::RecvMulti::new(io_uring::types::Fd(fd), URING_BUFFER_GROUP)
+            .build()
+            .user_data(Self::user_data(token, Op::Recv));
+        self.push(entry)
+    }
+
+    /// 소켓에 send 제출 (일부만 보내고 완료될 수 있음)
+    ///
+    /// # Safety
+    /// 커널이 완료 이벤트를 보낼 때까지 `buf`가 가리키는 메모리를 해제하거나 수정하면 안 됨
+    pub unsafe fn send(&mut self, fd: RawFd, token: Token, buf: &[u8]) -> Result<()> {
+        let entry = io_uring::opcode::Send::new(
+            io_uring::types::Fd(fd),
+            buf.as_ptr(),
+            buf.len().min(u32::MAX as usize) as u32,
+        )
+        .flags(libc::MSG_NOSIGNAL)
+        .build()
+        .user_data(Self::user_data(token, Op::Send));
+        self.push(entry)
+    }
+
+    // 제출해 둔 작업 취소 (취소된 작업은 -ECANCELED, has_more() == false로 완료됨)
+    pub fn cancel(&mut self, token: Token, op: Op) -> Result<()> {
+        let entry = io_uring::opcode::AsyncCancel::new(Self::user_data(token, op))
+            .build()
+            .user_data(URING_INTERNAL);
+        self.push(entry)
+    }
+
+    // recv 완료 이벤트가 가리키는 버퍼 (recycle 전까지만 유효)
+    pub fn buffer(&self, id: u16, len: usize) -> &[u8] {
+        let start = id as usize * self.buffer_size;
+        &self.buffers[start..start + len.min(self.buffer_size)]
+    }
+
+    // 다 읽은 버퍼를 다시 커널에 제공
+    pub fn recycle(&mut self, id: u16) -> Result<()> {
+        let start = id as usize * self.buffer_size;
+        let entry = io_uring::opcode::ProvideBuffers::new(
+            self.buffers[start..].as_mut_ptr(),
+            self.buffer_size as i32,
+            1,
+            URING_BUFFER_GROUP,
+            id,
+        )
+        .build()
+        .user_data(URING_INTERNAL);
+        self.push(entry)
+    }
+
+    /*
+     쌓인 제출을 커널에 넘기고 완료 이벤트 대기
+     - timeout: None이면 완료가 하나라도 올 때까지 대기
+     - 완료 큐를 모두 비워 completions에 채움 (버퍼 반납 완료는 제외)
+    */
+    pub fn wait(
+        &mut self,
+        completions: &mut Vec<Completion>,
+        timeout: Option<Duration>,
+    ) -> Result<usize> {
+        completions.clear();
+
+        let submitted = match timeout {
+            None => self.ring.submit_and_wait(1),
+            Some(timeout) => {
+                let ts = io_uring::types::Timespec::from(timeout);
+                let args = io_uring::types::SubmitArgs::new().timespec(&ts);
+                self.ring.submitter().submit_with_args(1, &args)
+            }
+        };
+
+        match submitted {
+            Ok(_) => {}
+            Err(e) if e.raw_os_error() == Some(libc::ETIME) => {} //타임아웃
+            Err(e) => return Err(e),
+        }
+
+        for cqe in self.ring.completion() {
+            let user_data = cqe.user_data();
+            let op = match user_data & ((1 << URING_OP_BITS) - 1) {
+                0 => Op::Accept,
+                1 => Op::Recv,
+                2 => Op::Send,
+                _ => continue,
+            };
+
+            completions.push(Completion {
+                token: Token(user_data >> URING_OP_BITS),
+                op,
+                result: cqe.result(),
+                more: io_uring::cqueue::more(cqe.flags()),
+                buffer: io_uring::cqueue::buffer_select(cqe.flags()),
+            });
+        }
+
+        Ok(completions.len())
+    }
+
+    fn user_data(token: Token, op: Op) -> u64 {
+        token.0 << URING_OP_BITS | op as u64
+    }
+
+    fn push(&mut self, entry: io_uring::squeue::Entry) -> Result<()> {
+        loop {
+            //안전성: 엔트리가 가리키는 메모리(버퍼 풀, send 버퍼)는 완료될 때까지 유지됨
+            if unsafe { self.ring.submission().push(&entry) }.is_ok() {
+                return Ok(());
+            }
+
+            //제출 큐가 가득 차면 먼저 커널에 넘기고 다시 시도
+            self.ring.submit()?;
+        }
+    }
+}
@@ -1,64 +1,94 @@
-use std::{
-    io::{Error, Result, Write},
-    net::{TcpListener, TcpStream},
-};
+use std::{env, fs, io::Result, process};
 
+mod cli;
 mod config;
 mod server;
 mod utils;
 
-use libc::{getpid, getppid};
-
 use crate::{
-    config::host::HOST_ADDR,
-    server::{
-        master::start_master_process,
-        worker::{create_reusable_listener, start_worker_process},
-    },
-    utils::system::get_cpu_count,
+    cli::Options,
+    config::{Config, DEFAULT_CONFIG_PATH},
+    server::master::start_master_process,
 };
 
 fn main() -> Result<()> {
-    let worker_count: usize = get_cpu_count();
-
-    //서버 소켓 생성
-    // let server_tcp_socket: TcpListener = create_reusable_listener(HOST_ADDR).unwrap();
-
-    //워커 프로세스에게 fd, kqueue를 넘겨서 공유해줘야함
-    for id in 0..worker_count {
-        // println!("📍 Before fork (id={}), current PID: {}", id, unsafe {
-        //     libc::getpid()
-        // });
+    let options: Options = match cli::parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            eprintln!("{}", cli::USAGE);
+            process::exit(1);
+        }
+    };
 
-        //마스터 프로세스 기반 복제
-        match unsafe { { libc::fork() } } {
-            //자식 프로세스(Worker)
-            0 => {
-                // drop(server_tcp_socket); //부모 리스너 닫기
+    if options.help {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    if options.version {
+        cli::print_version();
+        return Ok(());
+    }
 
-                let my_pid = unsafe { getpid() };
-                let parent_pid = unsafe { getppid() };
+    //설정 파일 로드 (에러는 "경로:줄:칸: 메시지"로 출력하고 종료)
+    let config_path = options.config_path(DEFAULT_CONFIG_PATH);
+    let load_config = || -> std::result::Result<Config, String> {
+        let mut config =
+            config::load(&config_path, &options.prefix()).map_err(|e| e.to_string())?;
+        if let Some(workers) = options.workers {
+            config.worker_processes = workers;
+        }
+        Ok(config)
+    };
 
-                //각 worker가 자체 리스너 생성
-                start_worker_process(id, parent_pid)?; //워커들 무한루프로 계속 실행(블로킹)
-                std::process::exit(0); //종료 시그널이나 에러 발생시를 위한 자식 프로세스 종료
-            }
-            pid if pid > 0 => {
-                //부모 프로세스(Master)
-                // println!("✅ Spawned worker {} (PID: {})", id, pid);
+    let config: Config = match load_config() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            if options.test_config {
+                eprintln!(
+                    "❌ configuration file {} test failed",
+                    config_path.display()
+                );
             }
+            process::exit(1);
+        }
+    };
 
-            _ => {
-                eprintln!("❌ Fork failed");
-                return Err(Error::last_os_error());
-            }
+    if options.test_config {
+        println!(
+            "✅ configuration file {} syntax is ok",
+            config_path.display()
+        );
+        println!(
+            "✅ configuration file {} test is successful",
+            config_path.display()
+        );
+        return Ok(());
+    }
+
+    //-s: 실행 중인 마스터에 신호만 보내고 종료
+    if let Some(signal) = options.signal {
+        if let Err(e) = cli::send_signal(&config.pid, signal) {
+            eprintln!("❌ {}", e);
+            process::exit(1);
         }
+        return Ok(());
+    }
 
-        //     getpid()
-        // });
+    //마스터 pid 기록 (rustify -s 가 읽음)
+    if let Err(e) = fs::write(&config.pid, format!("{}\n", process::id())) {
+        eprintln!("❌ open() \"{}\" failed ({})", config.pid.display(), e);
+        process::exit(1);
     }
 
-    start_master_process();
+    //리스너 생성, 워커 fork와 재시작은 마스터가 관리 (reload 시 같은 옵션으로 설정을 다시 읽음)
+    let pid_path = config.pid.clone();
+    if let Err(e) = start_master_process(config, load_config) {
+        eprintln!("❌ {}", e);
+        let _ = fs::remove_file(&pid_path);
+        process::exit(1);
+    }
 
     Ok(())
 }
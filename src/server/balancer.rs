@@ -0,0 +1,662 @@
+use std::{
+    cell::{Cell, RefCell},
+    net::IpAddr,
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use Rustify::http::Request;
+
+use crate::{
+    config::{Balance, Segment, Variable},
+    server::upstream::Backend,
+};
+
+//consistent 해시 링에 서버 가중치 1당 넣는 점 개수 (nginx와 같은 160)
+const RING_POINTS_PER_WEIGHT: u32 = 160;
+
+//해시로 고른 서버를 쓸 수 없을 때 다시 해시해 보는 횟수 (넘으면 라운드 로빈)
+const MAX_REHASH: usize = 20;
+
+//요청 하나의 서버 선택에 쓰는 정보
+pub struct Context<'a> {
+    pub request: &'a Request,
+    pub client: Option<IpAddr>, //클라이언트 IP (Unix 소켓이면 None)
+    pub tried: &'a [usize],     //이 요청을 이미 보냈던 서버 (proxy_next_upstream으로 다시 보낼 때)
+}
+
+impl Context<'_> {
+    //backends[index]에 이 요청을 보낼 수 있는지 (이미 보냈던 서버, 실패/health_check/회로로 내려간 서버 제외)
+    pub fn usable(&self, backends: &[Backend], index: usize) -> bool {
+        !self.tried.contains(&index) && backends[index].is_available()
+    }
+}
+
+/*
+ upstream 그룹의 부하 분산 방식
+ - 요청마다 이벤트 루프 스레드에서 불리므로 상태는 Cell/RefCell로 충분 (워커마다 따로 가짐)
+ - backends의 진행 중인 요청 수는 업스트림 작업이 끝날 때 다른 스레드에서 줄어듦
+ - context.usable()이 false인 서버는 고르지 않음
+ - 새 방식은 이 트레잇을 구현하고 Balance와 new()에 추가
+*/
+pub trait LoadBalancer {
+    //요청을 보낼 backends 인덱스 (보낼 서버가 없으면 None)
+    fn select(&self, backends: &[Backend], context: &Context) -> Option<usize>;
+}
+
+//설정의 분산 방식에 맞는 LoadBalancer
+pub fn new(balance: &Balance, backends: &[Backend]) -> Box<dyn LoadBalancer> {
+    match balance {
+        Balance::RoundRobin => Box::new(RoundRobin::new()),
+        Balance::LeastConn => Box::new(LeastConn::new()),
+        Balance::IpHash => Box::new(IpHash::new()),
+        Balance::Hash {
+            key,
+            consistent: false,
+        } => Box::new(Hash::new(key.clone())),
+        Balance::Hash {
+            key,
+            consistent: true,
+        } => Box::new(ConsistentHash::new(key.clone(), backends)),
+        Balance::Random { two } => Box::new(Random::new(*two)),
+    }
+}
+
+/*
+ 가중치 라운드 로빈 (nginx의 smooth weighted round-robin)
+ - 매번 모든 서버의 current에 가중치를 더하고, 가장 큰 서버를 고른 뒤 가중치 합만큼 뺌
+ - 가중치 5:1:1이면 a a b a c a a 처럼 한 서버에 몰리지 않게 섞임
+*/
+pub struct RoundRobin {
+    current: RefCell<Vec<i64>>,
+}
+
+impl RoundRobin {
+    pub fn new() -> RoundRobin {
+        RoundRobin {
+            current: RefCell::new(Vec::new()),
+        }
+    }
+
+    //eligible이 true인 서버들 중에서만 고름
+    fn pick(&self, backends: &[Backend], eligible: impl Fn(usize) -> bool) -> Option<usize> {
+        let mut current = self.current.borrow_mut();
+        current.resize(backends.len(), 0);
+
+        let mut total: i64 = 0;
+        let mut best: Option<usize> = None;
+        for (index, backend) in backends.iter().enumerate() {
+            if !eligible(index) {
+                continue;
+            }
+            let weight = i64::from(backend.weight());
+            current[index] += weight;
+            total += weight;
+            if best.is_none_or(|best| current[index] > current[best]) {
+                best = Some(index);
+            }
+        }
+
+        let best = best?;
+        current[best] -= total;
+        Some(best)
+    }
+}
+
+impl LoadBalancer for RoundRobin {
+    fn select(&self, backends: &[Backend], context: &Context) -> Option<usize> {
+        self.pick(backends, |index| context.usable(backends, index))
+    }
+}
+
+/*
+ least_conn: 진행 중인 요청 수 / 가중치가 가장 작은 서버
+ - 같은 서버가 여럿이면 그 서버들끼리 가중치 라운드 로빈
+*/
+pub struct LeastConn {
+    round_robin: RoundRobin,
+}
+
+impl LeastConn {
+    pub fn new() -> LeastConn {
+        LeastConn {
+            round_robin: RoundRobin::new(),
+        }
+    }
+}
+
+impl LoadBalancer for LeastConn {
+    fn select(&self, backends: &[Backend], context: &Context) -> Option<usize> {
+        let best = (0..backends.len())
+            .filter(|&index| context.usable(backends, index))
+            .min_by(|&a, &b| compare_load(&backends[a], &backends[b]))?;
+
+        self.round_robin.pick(backends, |index| {
+            context.usable(backends, index)
+                && compare_load(&backends[index], &backends[best]).is_eq()
+        })
+    }
+}
+
+/*
+ ip_hash: 클라이언트 주소로 서버 고정 (IPv4는 앞 3바이트, 즉 /24 대역 단위, IPv6는 전체)
+ - Unix 소켓 클라이언트처럼 주소가 없으면 라운드 로빈
+ - 고른 서버를 쓸 수 없으면 다시 해시 (다른 서버에 고정된 클라이언트는 그대로)
+*/
+pub struct IpHash {
+    round_robin: RoundRobin,
+}
+
+impl IpHash {
+    pub fn new() -> IpHash {
+        IpHash {
+            round_robin: RoundRobin::new(),
+        }
+    }
+}
+
+impl LoadBalancer for IpHash {
+    fn select(&self, backends: &[Backend], context: &Context) -> Option<usize> {
+        let hash = match context.client {
+            Some(IpAddr::V4(ip)) => hash(&ip.octets()[..3]),
+            Some(IpAddr::V6(ip)) => hash(&ip.octets()),
+            None => return self.round_robin.select(backends, context),
+        };
+
+        rehash(backends, context, hash, &self.round_robin)
+    }
+}
+
+/*
+ hash 키: 키 값의 해시를 가중치 합으로 나눈 나머지로 서버 고정 (서버 수가 바뀌면 대부분의 키가 이동)
+ - 고른 서버를 쓸 수 없으면 ip_hash처럼 다시 해시
+*/
+pub struct Hash {
+    key: Vec<Segment>,
+    round_robin: RoundRobin,
+}
+
+impl Hash {
+    pub fn new(key: Vec<Segment>) -> Hash {
+        Hash {
+            key,
+            round_robin: RoundRobin::new(),
+        }
+    }
+}
+
+impl LoadBalancer for Hash {
+    fn select(&self, backends: &[Backend], context: &Context) -> Option<usize> {
+        let key = expand(&self.key, context);
+        rehash(backends, context, hash(key.as_bytes()), &self.round_robin)
+    }
+}
+
+/*
+ hash 키 consistent: ketama 해시 링
+ - 서버마다 "주소-번호"의 해시를 가중치 × 160개 링에 올려 두고, 키의 해시 다음에 오는 점의 서버를 고름
+ - 서버가 추가/제거되어도 그 서버 몫의 키만 이동 (캐시 서버 앞단 등)
+ - 고른 서버를 쓸 수 없으면 링을 따라 다음 서버
+*/
+pub struct ConsistentHash {
+    key: Vec<Segment>,
+    ring: Vec<(u32, usize)>, //(점, backends 인덱스), 점 순서로 정렬
+}
+
+impl ConsistentHash {
+    pub fn new(key: Vec<Segment>, backends: &[Backend]) -> ConsistentHash {
+        let mut ring: Vec<(u32, usize)> = Vec::new();
+        for (index, backend) in backends.iter().enumerate() {
+            let addr = backend.addr().to_string();
+            let points = backend
+                .weight()
+                .checked_mul(RING_POINTS_PER_WEIGHT)
+                .expect("weight is limited by the config loader");
+            for point in 0..points {
+                let name = format!("{}-{}", addr, point);
+                ring.push((hash(name.as_bytes()) as u32, index));
+            }
+        }
+        ring.sort_unstable();
+
+        ConsistentHash { key, ring }
+    }
+}
+
+impl LoadBalancer for ConsistentHash {
+    fn select(&self, backends: &[Backend], context: &Context) -> Option<usize> {
+        let key = expand(&self.key, context);
+        let point = hash(key.as_bytes()) as u32;
+
+        //링의 끝을 넘으면 처음으로
+        let at = self.ring.partition_point(|&(other, _)| other < point);
+        (0..self.ring.len())
+            .map(|offset| self.ring[(at + offset) % self.ring.len()].1)
+            .find(|&index| context.usable(backends, index))
+    }
+}
+
+/*
+ random [two]: 가중치 랜덤
+ - two: 가중치 랜덤으로 서로 다른 서버 둘을 뽑아 진행 중인 요청 수 / 가중치가 작은 쪽 (power of two choices)
+   모든 서버를 훑지 않고도 부하가 몰린 서버를 피함
+*/
+pub struct Random {
+    two: bool,
+    state: Cell<u64>, //xorshift 난수 상태
+}
+
+impl Random {
+    pub fn new(two: bool) -> Random {
+        //워커마다 다른 순서가 되도록 시각과 pid로 시작 (0이면 계속 0이므로 1로 보정)
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        let seed = hash(&(nanos ^ u64::from(process::id())).to_le_bytes()).max(1);
+
+        Random {
+            two,
+            state: Cell::new(seed),
+        }
+    }
+
+    fn next(&self) -> u64 {
+        let mut x = self.state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state.set(x);
+        x
+    }
+}
+
+impl LoadBalancer for Random {
+    fn select(&self, backends: &[Backend], context: &Context) -> Option<usize> {
+        let usable = |index| context.usable(backends, index);
+
+        let first = weighted(backends, self.next(), usable)?;
+        if !self.two {
+            return Some(first);
+        }
+
+        //가중치가 한 서버에 몰려 있거나 쓸 수 있는 서버가 하나면 같은 서버가 나오므로 몇 번만 다시 뽑음
+        let second = (0..MAX_REHASH)
+            .filter_map(|_| weighted(backends, self.next(), usable))
+            .find(|&second| second != first)
+            .unwrap_or(first);
+
+        if compare_load(&backends[second], &backends[first]).is_lt() {
+            Some(second)
+        } else {
+            Some(first)
+        }
+    }
+}
+
+//진행 중인 요청 수 / 가중치 비교 (나눗셈 없이 곱해서)
+fn compare_load(a: &Backend, b: &Backend) -> std::cmp::Ordering {
+    let a_load = a.active() as u64 * u64::from(b.weight());
+    let b_load = b.active() as u64 * u64::from(a.weight());
+    a_load.cmp(&b_load)
+}
+
+/*
+ 0..가중치 합 범위의 값(value를 나눈 나머지)이 떨어지는 서버 (가중치만큼 넓은 구간을 차지)
+ - eligible이 false인 서버는 구간에서 빼고 셈
+*/
+fn weighted(backends: &[Backend], value: u64, eligible: impl Fn(usize) -> bool) -> Option<usize> {
+    let weight = |index: usize| {
+        if eligible(index) {
+            u64::from(backends[index].weight())
+        } else {
+            0
+        }
+    };
+
+    let total: u64 = (0..backends.len()).map(weight).sum();
+    if total == 0 {
+        return None;
+    }
+
+    let mut point = value % total;
+    for index in 0..backends.len() {
+        let weight = weight(index);
+        if point < weight {
+            return Some(index);
+        }
+        point -= weight;
+    }
+    None
+}
+
+/*
+ 해시 값으로 서버 고르기 (ip_hash, hash)
+ - 모든 서버를 대상으로 고르므로 쓸 수 없는 서버가 생겨도 다른 서버에 고정된 키는 움직이지 않음
+ - 고른 서버를 쓸 수 없으면 해시를 바꿔 가며 다시, MAX_REHASH번 넘게 실패하면 라운드 로빈
+*/
+fn rehash(
+    backends: &[Backend],
+    context: &Context,
+    mut value: u64,
+    round_robin: &RoundRobin,
+) -> Option<usize> {
+    for _ in 0..MAX_REHASH {
+        let index = weighted(backends, value, |_| true)?;
+        if context.usable(backends, index) {
+            return Some(index);
+        }
+        value = hash(&value.to_le_bytes());
+    }
+
+    round_robin.select(backends, context)
+}
+
+/*
+ 워커 사이에서도 같은 값이 나와야 하므로 고정된 해시 (FNV-1a + murmur3 finalizer)
+ - std의 HashMap 해시는 프로세스마다 시드가 달라서 쓸 수 없음
+*/
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+
+    //FNV는 끝 바이트만 다른 키("주소-1", "주소-2")의 상위 비트가 비슷하므로 비트를 섞음
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^= hash >> 33;
+    hash
+}
+
+//변수가 섞인 설정 값을 요청 값으로 (없는 헤더/쿼리/쿠키는 빈 문자열)
+fn expand(segments: &[Segment], context: &Context) -> String {
+    let request = context.request;
+    let mut value = String::new();
+
+    for segment in segments {
+        match segment {
+            Segment::Text(text) => value.push_str(text),
+            Segment::Variable(variable) => match variable {
+                Variable::RemoteAddr => match context.client {
+                    Some(ip) => value.push_str(&ip.to_string()),
+                    None => value.push_str("unix:"),
+                },
+                Variable::RequestUri => value.push_str(request.target()),
+                Variable::Uri => value.push_str(request.path()),
+                Variable::Args => value.push_str(request.query().unwrap_or("")),
+                Variable::Host => {
+                    let host = request.header("Host").unwrap_or("");
+                    //포트 제외 (IPv6 리터럴 [::1]:80 고려)
+                    let host = match host.rfind(':') {
+                        Some(i) if !host[i..].contains(']') => &host[..i],
+                        _ => host,
+                    };
+                    value.push_str(&host.to_ascii_lowercase());
+                }
+                Variable::RequestMethod => value.push_str(request.method().as_str()),
+                Variable::Http(name) => {
+                    value.push_str(request.header(name).unwrap_or(""));
+                }
+                Variable::Arg(name) => value.push_str(request.query_param(name).unwrap_or("")),
+                Variable::Cookie(name) => {
+                    let cookie = request
+                        .headers()
+                        .get_all("Cookie")
+                        .flat_map(|header| header.split(';'))
+                        .find_map(|pair| {
+                            let (key, cookie) = pair.trim().split_once('=')?;
+                            (key == name).then_some(cookie)
+                        });
+                    value.push_str(cookie.unwrap_or(""));
+                }
+            },
+        }
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use Rustify::http::{HeaderMap, Method, Version};
+
+    use super::*;
+    use crate::{
+        config::{Address, HttpConfig, UpstreamConfig, UpstreamServer},
+        server::health::HealthTable,
+    };
+
+    //가중치마다 서버 하나 (127.0.0.1:8001부터), 요청은 보내지 않으므로 소켓을 열지 않음
+    fn backends(weights: &[u32]) -> Vec<Backend> {
+        let config = UpstreamConfig {
+            name: String::from("app"),
+            balance: Balance::RoundRobin,
+            servers: weights
+                .iter()
+                .enumerate()
+                .map(|(index, &weight)| UpstreamServer {
+                    addr: Address::Tcp(([127, 0, 0, 1], 8001 + index as u16).into()),
+                    weight,
+                    max_fails: 1,
+                    fail_timeout: Duration::from_secs(10),
+                })
+                .collect(),
+            health_check: None,
+            circuit_breaker: None,
+            keepalive: 0,
+            keepalive_timeout: Duration::from_secs(60),
+        };
+        let http = HttpConfig {
+            upstreams: vec![config.clone()],
+            ..HttpConfig::default()
+        };
+        let health = HealthTable::new(&http).unwrap();
+
+        config
+            .servers
+            .iter()
+            .enumerate()
+            .map(|(index, server)| {
+                let health = health.get(index, server.max_fails, server.fail_timeout, None);
+                Backend::new(server, health, &config, &[]).unwrap()
+            })
+            .collect()
+    }
+
+    fn request(target: &str) -> Request {
+        Request::new(Method::Get, target, Version::Http11, HeaderMap::new())
+    }
+
+    //$request_uri 키
+    fn uri_key() -> Vec<Segment> {
+        vec![Segment::Variable(Variable::RequestUri)]
+    }
+
+    #[test]
+    fn builds_consistent_ring_with_max_weight() {
+        let backends = backends(&[1000, 1]);
+        let balancer = ConsistentHash::new(uri_key(), &backends);
+        assert_eq!(balancer.ring.len(), 1001 * RING_POINTS_PER_WEIGHT as usize);
+
+        let request = request("/a");
+        let context = Context {
+            request: &request,
+            client: None,
+            tried: &[],
+        };
+        assert!(balancer.select(&backends, &context).is_some());
+    }
+
+    //같은 balancer로 count번 고른 서버들
+    fn picks(
+        balancer: &dyn LoadBalancer,
+        backends: &[Backend],
+        context: &Context,
+        count: usize,
+    ) -> Vec<usize> {
+        (0..count)
+            .map(|_| balancer.select(backends, context).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn interleaves_weighted_round_robin() {
+        let backends = backends(&[5, 1, 1]);
+        let balancer = RoundRobin::new();
+        let request = request("/");
+        let context = Context {
+            request: &request,
+            client: None,
+            tried: &[],
+        };
+
+        //a a b a c a a 가 반복
+        let expected = [0, 0, 1, 0, 2, 0, 0];
+        assert_eq!(picks(&balancer, &backends, &context, 7), expected);
+        assert_eq!(picks(&balancer, &backends, &context, 7), expected);
+
+        //쓸 수 없는 서버를 빼고도 가중치대로 섞임
+        let context = Context {
+            tried: &[0],
+            ..context
+        };
+        assert_eq!(picks(&balancer, &backends, &context, 4), [1, 2, 1, 2]);
+    }
+
+    #[test]
+    fn picks_least_loaded_and_rotates_ties() {
+        let backends = backends(&[1, 1, 2]);
+        let balancer = LeastConn::new();
+        let request = request("/");
+        let context = Context {
+            request: &request,
+            client: None,
+            tried: &[],
+        };
+
+        backends[0].set_active(1);
+        backends[1].set_active(3);
+        backends[2].set_active(1);
+        assert_eq!(balancer.select(&backends, &context), Some(2));
+
+        //요청 수 / 가중치가 같으면 (1/1, 2/2) 그 서버들끼리 가중치 라운드 로빈
+        backends[2].set_active(2);
+        let mut selected = picks(&balancer, &backends, &context, 3);
+        selected.sort();
+        assert_eq!(selected, [0, 2, 2]);
+
+        let context = Context {
+            tried: &[0],
+            ..context
+        };
+        assert_eq!(balancer.select(&backends, &context), Some(2));
+    }
+
+    #[test]
+    fn sticks_clients_by_ipv4_network() {
+        let backends = backends(&[1, 1, 1]);
+        let balancer = IpHash::new();
+        let request = request("/");
+        let select = |client: [u8; 4], tried: &[usize]| {
+            let context = Context {
+                request: &request,
+                client: Some(IpAddr::from(client)),
+                tried,
+            };
+            balancer.select(&backends, &context).unwrap()
+        };
+
+        //같은 /24 대역은 같은 서버
+        let pinned: Vec<usize> = (0..32).map(|net| select([10, 0, net, 1], &[])).collect();
+        for (net, &index) in (0..32).zip(&pinned) {
+            assert_eq!(select([10, 0, net, 200], &[]), index);
+        }
+        assert!((0..3).all(|index| pinned.contains(&index)));
+
+        //고정된 서버를 쓸 수 없으면 다른 서버로, 다른 서버에 고정된 대역은 그대로
+        for (net, &index) in (0..32).zip(&pinned) {
+            let rehashed = select([10, 0, net, 1], &[0]);
+            if index == 0 {
+                assert_ne!(rehashed, 0);
+            } else {
+                assert_eq!(rehashed, index);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_consistent_keys_when_a_server_is_removed() {
+        let before = backends(&[1, 1, 1, 1]);
+        let after = backends(&[1, 1, 1]);
+        let before_ring = ConsistentHash::new(uri_key(), &before);
+        let after_ring = ConsistentHash::new(uri_key(), &after);
+
+        let mut moved = 0;
+        for key in 0..200 {
+            let request = request(&format!("/item/{}", key));
+            let context = Context {
+                request: &request,
+                client: None,
+                tried: &[],
+            };
+            let old = before_ring.select(&before, &context).unwrap();
+            let new = after_ring.select(&after, &context).unwrap();
+
+            //빠진 서버(3번) 몫의 키만 이동
+            if old == 3 {
+                moved += 1;
+            } else {
+                assert_eq!(new, old, "{}", key);
+            }
+
+            //쓸 수 없으면 링을 따라 다음 서버 (이것도 빠진 것과 같은 결과)
+            let tried = [3];
+            let context = Context {
+                tried: &tried,
+                ..context
+            };
+            assert_eq!(before_ring.select(&before, &context), Some(new));
+        }
+        assert!(moved > 0);
+    }
+
+    #[test]
+    fn never_picks_tried_server_at_random() {
+        let backends = backends(&[1, 1, 1]);
+        let request = request("/");
+
+        for two in [false, true] {
+            let balancer = Random::new(two);
+            let context = Context {
+                request: &request,
+                client: None,
+                tried: &[0],
+            };
+            let selected = picks(&balancer, &backends, &context, 200);
+            assert!(!selected.contains(&0));
+            assert!(selected.contains(&1) && selected.contains(&2));
+
+            let context = Context {
+                tried: &[0, 2],
+                ..context
+            };
+            assert!(
+                picks(&balancer, &backends, &context, 50)
+                    .iter()
+                    .all(|&index| index == 1)
+            );
+
+            let context = Context {
+                tried: &[0, 1, 2],
+                ..context
+            };
+            assert_eq!(balancer.select(&backends, &context), None);
+        }
+    }
+}
@@ -0,0 +1,643 @@
+use std::{
+    io::{ErrorKind, Read, Result, Write},
+    net::IpAddr,
+    os::fd::{AsRawFd, RawFd},
+    time::Instant,
+};
+
+use Rustify::{
+    Interest, Token,
+    http::{BodyWriter, Method, Request, RequestParser, Response, Version},
+};
+
+use crate::{
+    config::HttpConfig,
+    server::{
+        proxy_body::Upload,
+        router::{Deferred, Peer, Reply, Router},
+        socket::Stream,
+    },
+};
+
+//한 번의 read 시스템 콜로 읽는 최대 크기
+const READ_CHUNK: usize = 16 * 1024;
+
+//쓰기 버퍼가 비었을 때 바디에서 한 번에 채우는 최대 크기
+const WRITE_CHUNK: usize = 64 * 1024;
+
+/*
+ 연결 상태
+ - ReadingHeaders: 요청 라인/헤더 수신 대기 (keep-alive 유휴 상태 포함)
+ - ReadingBody: 헤더는 다 받았고 바디 수신 중
+ - Uploading: 요청은 업스트림에 보냈고, 받는 바디를 그대로 흘려보내는 중 (proxy_request_buffering off)
+ - Waiting: 업스트림(proxy_pass) 응답 헤더 대기 (도착하면 알림으로 깨어남)
+ - Writing: 응답 전송 중 (소켓 버퍼가 가득 차면 EPOLLOUT을, 업스트림 바디가 아직 없으면 알림을 기다림)
+ - Closed: 이벤트 루프에서 정리해야 함
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    ReadingHeaders,
+    ReadingBody,
+    Uploading,
+    Waiting,
+    Writing,
+    Closed,
+}
+
+//업스트림 응답을 기다리는 요청과 응답을 보낼 때 필요한 요청 정보
+struct Awaiting {
+    deferred: Deferred,
+    method: Method,
+    version: Version,
+    keep_alive: bool,
+}
+
+/*
+ 논블로킹 클라이언트 연결
+ - 스레드를 점유하지 않고 이벤트가 올 때마다 할 수 있는 만큼만 읽고/쓰고 리턴
+ - 유휴 keep-alive 연결은 버퍼 몇 개만 차지하므로 워커 하나가 수만 개를 유지할 수 있음
+*/
+pub struct Connection {
+    stream: Stream,
+    token: Token,         //이벤트 루프의 연결 토큰 (업스트림 작업이 깨울 때 사용)
+    peer: Option<IpAddr>, //클라이언트 IP (Unix 소켓이면 None)
+    listener: usize,      //연결을 받은 config.listeners 인덱스 (server 선택용)
+    state: State,
+    parser: RequestParser,
+    read_buf: Vec<u8>,
+    write_buf: Vec<u8>,
+    written: usize,             //write_buf에서 이미 소켓으로 보낸 바이트 수
+    body: Option<BodyWriter>,   //전송 중인 응답 바디
+    upstream: Option<Awaiting>, //Uploading/Waiting 상태의 업스트림 요청
+    upload: Option<Upload>,     //Uploading 상태에서 요청 바디를 넘길 채널
+    stalled: bool,              //Writing 중 업스트림 바디가 아직 오지 않아 쓰기를 멈춤
+    close_after_write: bool,    //현재 응답을 보낸 뒤 연결 종료
+    peer_closed: bool,          //클라이언트가 쓰기 방향을 닫음 (read == 0)
+    served: usize,              //이 연결에서 처리한 요청 수
+    draining: bool,             //graceful 종료 중: 현재 요청까지만 응답하고 닫음
+    sendfile: bool, //파일 바디를 sendfile로 전송 (소켓이 지원하지 않으면 버퍼 복사로 전환)
+    last_active: Instant,
+}
+
+impl Connection {
+    pub fn new(stream: Stream, token: Token, listener: usize, http: &HttpConfig) -> Connection {
+        Connection {
+            peer: stream.peer_ip(),
+            stream,
+            token,
+            listener,
+            state: State::ReadingHeaders,
+            parser: RequestParser::new(http.limits()),
+            read_buf: Vec::new(),
+            write_buf: Vec::new(),
+            written: 0,
+            body: None,
+            upstream: None,
+            upload: None,
+            stalled: false,
+            close_after_write: false,
+            peer_closed: false,
+            served: 0,
+            draining: false,
+            sendfile: http.sendfile,
+            last_active: Instant::now(),
+        }
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state == State::Closed
+    }
+
+    /*
+     상태에 맞는 감시 이벤트
+     - 응답 전송 중이면 쓰기, 업스트림을 기다리는 중이면 클라이언트가 닫는지만 (요청 취소), 나머지는 읽기
+     - 업스트림이 요청 바디를 가져가지 않아 업로드가 막혔으면 읽지 않음 (클라이언트 쪽 TCP 흐름 제어)
+    */
+    pub fn interest(&self) -> Interest {
+        match self.state {
+            State::Waiting => Interest::READ_CLOSED,
+            State::Uploading if self.is_upload_blocked() => Interest::READ_CLOSED,
+            State::Writing if self.stalled => Interest::READ_CLOSED,
+            State::Writing => Interest::WRITABLE,
+            _ => Interest::READABLE,
+        }
+    }
+
+    /*
+     graceful 종료: 유휴 keep-alive 연결은 바로 닫고, 처리 중인 연결은 현재 응답까지만 보내고 닫음
+     - 방금 accept해서 아직 요청이 도착하지 않은 연결은 첫 요청까지 응답 (클라이언트는 이미 요청을 보냈을 수 있음)
+    */
+    pub fn drain(&mut self) {
+        self.draining = true;
+
+        if self.state == State::ReadingHeaders && self.read_buf.is_empty() && self.served > 0 {
+            self.state = State::Closed;
+        }
+    }
+
+    //상태별 타임아웃이 지났는지 (keep-alive 유휴 / 요청 수신 중 / 응답 전송 중)
+    pub fn is_expired(&self, now: Instant, http: &HttpConfig) -> bool {
+        let timeout = match self.state {
+            State::ReadingHeaders if self.read_buf.is_empty() && self.served > 0 => {
+                http.keepalive_timeout
+            }
+            //업로드가 막혀 있는 동안은 업스트림 타임아웃(proxy_send_timeout)이 적용됨
+            State::Uploading if self.is_upload_blocked() => return false,
+            State::ReadingHeaders | State::ReadingBody | State::Uploading => http.client_timeout,
+            //업스트림을 기다리는 동안은 업스트림 타임아웃이 적용됨
+            State::Waiting => return false,
+            State::Writing if self.stalled => return false,
+            State::Writing => http.send_timeout,
+            State::Closed => return true,
+        };
+
+        now.duration_since(self.last_active) >= timeout
+    }
+
+    //읽기 이벤트: 소켓에 있는 데이터를 모두 읽고 완성된 요청을 처리
+    pub fn on_readable(&mut self, router: &Router) {
+        if matches!(self.state, State::Waiting | State::Writing | State::Closed)
+            || self.is_upload_blocked()
+        {
+            return;
+        }
+
+        let mut chunk = [0u8; READ_CHUNK];
+
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => {
+                    self.peer_closed = true;
+                    break;
+                }
+                Ok(n) => {
+                    self.read_buf.extend_from_slice(&chunk[..n]);
+                    self.last_active = Instant::now();
+
+                    //덜 채워졌으면 소켓 버퍼를 다 비운 것 (WouldBlock을 보려고 한 번 더 read 하지 않음)
+                    if n < chunk.len() {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => {
+                    self.state = State::Closed;
+                    return;
+                }
+            }
+        }
+
+        self.process(router);
+    }
+
+    //쓰기 이벤트: 남은 응답을 이어서 전송, 끝나면 파이프라이닝된 다음 요청 처리
+    pub fn on_writable(&mut self, router: &Router) {
+        if self.state != State::Writing {
+            return;
+        }
+
+        if self.flush().is_err() {
+            self.state = State::Closed;
+            return;
+        }
+
+        self.process(router);
+    }
+
+    //업스트림 알림: 응답 헤더나 바디 조각이 도착했으면 이어서 전송, 업로드 채널에 자리가 났으면 이어서 넘김
+    pub fn on_upstream(&mut self, router: &Router) {
+        if !matches!(
+            self.state,
+            State::Uploading | State::Waiting | State::Writing
+        ) {
+            return;
+        }
+
+        self.stalled = false;
+        self.process(router);
+    }
+
+    //클라이언트가 연결을 닫음: 업스트림 응답을 기다리는 중이면 업스트림 요청을 취소하고 종료 (nginx 499)
+    pub fn on_read_closed(&mut self) {
+        if self.state == State::Waiting
+            || (self.state == State::Writing && self.stalled)
+            || self.is_upload_blocked()
+        {
+            self.state = State::Closed;
+        }
+    }
+
+    //버퍼에 있는 요청을 하나씩 응답 (응답 전송이 막히면 멈추고 EPOLLOUT을 기다림)
+    fn process(&mut self, router: &Router) {
+        loop {
+            self.dispatch(router);
+
+            if self.state != State::Writing {
+                return;
+            }
+            if self.flush().is_err() {
+                self.state = State::Closed;
+                return;
+            }
+            if self.state == State::Writing {
+                return;
+            }
+        }
+    }
+
+    /*
+     완성된 요청을 파싱해서 응답을 시작 (응답 하나를 시작하거나 데이터가 더 필요하면 멈춤)
+     - 업스트림 응답을 기다리는 중이면 도착했는지 확인해서 응답 시작
+    */
+    fn dispatch(&mut self, router: &Router) {
+        loop {
+            if self.state == State::Uploading {
+                if !self.upload_body() {
+                    return;
+                }
+                continue;
+            }
+
+            if self.state == State::Waiting {
+                let Some(awaiting) = self.upstream.as_mut() else {
+                    return;
+                };
+                let Some(response) = router.resume(&mut awaiting.deferred) else {
+                    return;
+                };
+
+                let Awaiting {
+                    method,
+                    version,
+                    keep_alive,
+                    ..
+                } = self.upstream.take().unwrap();
+                self.respond(response, &method, version, keep_alive);
+                continue;
+            }
+
+            if !matches!(self.state, State::ReadingHeaders | State::ReadingBody) {
+                return;
+            }
+
+            match self.parser.parse(&self.read_buf) {
+                Ok(Some((request, consumed))) => {
+                    self.read_buf.drain(..consumed);
+
+                    let (method, version, keep_alive) = self.accept_request(router, &request);
+
+                    match router.handle(self.listener, request, &self.peer()) {
+                        Reply::Ready(response) => {
+                            self.respond(response, &method, version, keep_alive)
+                        }
+                        Reply::Deferred(deferred) => {
+                            self.upstream = Some(Awaiting {
+                                deferred,
+                                method,
+                                version,
+                                keep_alive,
+                            });
+                            self.state = State::Waiting;
+                        }
+                    }
+                }
+                //헤더를 막 받았고 바디를 받으면서 흘려보낼 요청이면 바로 업스트림으로
+                Ok(None)
+                    if self.state == State::ReadingHeaders
+                        && self
+                            .parser
+                            .head()
+                            .is_some_and(|request| router.streams_body(self.listener, request)) =>
+                {
+                    let (request, body, consumed) = self.parser.stream_body().unwrap();
+                    self.read_buf.drain(..consumed);
+                    self.start_upload(router, request, body);
+                }
+                Ok(None) => {
+                    self.state = if self.parser.is_reading_body() {
+                        State::ReadingBody
+                    } else {
+                        State::ReadingHeaders
+                    };
+
+                    //클라이언트가 닫았는데 완성된 요청이 더 없으면 종료
+                    if self.peer_closed {
+                        self.state = State::Closed;
+                    }
+                    return;
+                }
+                Err(e) => {
+                    //파싱 실패 시 에러 페이지로 응답하고 연결 종료
+                    let response =
+                        Response::error(e.status_code()).with_header("Connection", "close");
+                    self.start_response(response, &Method::Get, Version::Http11);
+                }
+            }
+        }
+    }
+
+    //요청 하나를 받음: (메서드, 버전, 응답 후 keep-alive 여부)
+    fn accept_request(&mut self, router: &Router, request: &Request) -> (Method, Version, bool) {
+        self.served += 1;
+
+        let keep_alive = request.keep_alive()
+            && self.served < router.config().http.keepalive_requests
+            && !self.peer_closed
+            && !self.draining;
+
+        (request.method().clone(), request.version(), keep_alive)
+    }
+
+    fn peer(&self) -> Peer {
+        Peer {
+            token: self.token,
+            addr: self.peer,
+        }
+    }
+
+    /*
+     바디를 다 받기 전에 업스트림 요청 시작 (body: 헤더와 함께 받은 바디 앞부분)
+     - 업스트림에 보낼 수 없으면 바로 에러 응답하고, 남은 바디는 읽지 않고 연결 종료
+    */
+    fn start_upload(&mut self, router: &Router, request: Request, body: Vec<u8>) {
+        let (method, version, keep_alive) = self.accept_request(router, &request);
+
+        match router.handle_upload(self.listener, request, &self.peer()) {
+            (Reply::Deferred(deferred), Some(mut upload)) => {
+                if !body.is_empty() {
+                    upload.push(body);
+                }
+                self.upstream = Some(Awaiting {
+                    deferred,
+                    method,
+                    version,
+                    keep_alive,
+                });
+                self.upload = Some(upload);
+                self.state = State::Uploading;
+            }
+            (Reply::Ready(response), _) => {
+                self.parser.reset();
+                self.respond(response, &method, version, false);
+            }
+            //업로드 채널은 업스트림 요청이 있을 때만 돌려받음
+            (Reply::Deferred(_), None) => unreachable!(),
+        }
+    }
+
+    fn is_upload_blocked(&self) -> bool {
+        self.state == State::Uploading && self.upload.as_ref().is_some_and(Upload::is_blocked)
+    }
+
+    /*
+     받은 요청 바디를 업로드 채널로 넘김 (상태가 바뀌었으면 true)
+     - 채널이 가득 차면 멈추고, 업스트림이 조각을 가져가면 알림으로 깨어나서 이어서 넘김
+     - 바디를 다 넘기면 Waiting (업스트림이 먼저 응답했어도 바디를 끝까지 받은 뒤에 응답)
+     - 바디가 잘못됐으면 업스트림 요청을 취소하고 에러 응답 후 연결 종료
+    */
+    fn upload_body(&mut self) -> bool {
+        let Some(upload) = self.upload.as_mut() else {
+            return false;
+        };
+
+        loop {
+            if !upload.flush() {
+                return false;
+            }
+
+            if !self.parser.is_streaming_body() {
+                self.upload.take().unwrap().finish();
+                self.state = State::Waiting;
+                return true;
+            }
+
+            let mut chunk = Vec::new();
+            match self.parser.decode_body(&self.read_buf, &mut chunk) {
+                Ok(used) => {
+                    self.read_buf.drain(..used);
+
+                    if !chunk.is_empty() {
+                        upload.push(chunk);
+                    } else if self.parser.is_streaming_body() {
+                        //클라이언트가 바디를 다 보내지 않고 닫음
+                        if self.peer_closed {
+                            self.state = State::Closed;
+                            return true;
+                        }
+                        return false;
+                    }
+                }
+                Err(e) => {
+                    self.upload = None;
+                    let Awaiting {
+                        method, version, ..
+                    } = self.upstream.take().unwrap();
+                    self.parser.reset();
+
+                    let response =
+                        Response::error(e.status_code()).with_header("Connection", "close");
+                    self.start_response(response, &method, version);
+                    return true;
+                }
+            }
+        }
+    }
+
+    //keep-alive 여부를 Connection 헤더로 알리고 응답 시작
+    fn respond(
+        &mut self,
+        mut response: Response,
+        method: &Method,
+        version: Version,
+        keep_alive: bool,
+    ) {
+        if !keep_alive {
+            response.headers_mut().insert("Connection", "close");
+        } else if version == Version::Http10 {
+            response.headers_mut().insert("Connection", "keep-alive");
+        }
+
+        self.start_response(response, method, version);
+    }
+
+    fn start_response(&mut self, mut response: Response, method: &Method, version: Version) {
+        response.prepare(method, version);
+        self.close_after_write = response.closes_connection();
+
+        self.write_buf.clear();
+        self.written = 0;
+        self.write_buf.extend_from_slice(&response.encode_head());
+        self.body = Some(response.into_body_writer());
+        self.state = State::Writing;
+    }
+
+    /*
+     쓰기 버퍼 전송
+     - 소켓이 WouldBlock이면 Writing 상태 그대로 리턴 (다음 EPOLLOUT에서 이어서)
+     - 버퍼가 비면 바디의 다음 조각을 보냄: 파일은 sendfile로 직접, 나머지는 버퍼에 채워서
+     - 업스트림 바디가 아직 오지 않았으면 stalled로 리턴 (다음 업스트림 알림에서 이어서)
+     - 응답을 다 보내면 keep-alive면 ReadingHeaders, 아니면 Closed
+    */
+    fn flush(&mut self) -> Result<()> {
+        loop {
+            if self.written < self.write_buf.len() {
+                match self.stream.write(&self.write_buf[self.written..]) {
+                    Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                    Ok(n) => {
+                        self.written += n;
+                        self.last_active = Instant::now();
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                    Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+                continue;
+            }
+
+            if self.sendfile
+                && let Some(body) = self.body.as_mut()
+                && let Some((file, offset, len)) = body.file_part()
+            {
+                match self.stream.send_file(file, offset, len) {
+                    //응답 도중 파일이 잘려 Content-Length만큼 보낼 수 없음
+                    Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                    Ok(n) => {
+                        body.advance(n as u64);
+                        self.last_active = Instant::now();
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(()),
+                    Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                    //이 소켓/파일은 sendfile을 지원하지 않음 → 이후로는 버퍼 복사
+                    Err(e)
+                        if matches!(e.kind(), ErrorKind::Unsupported | ErrorKind::InvalidInput) =>
+                    {
+                        self.sendfile = false;
+                    }
+                    Err(e) => return Err(e),
+                }
+                continue;
+            }
+
+            match self.refill() {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    self.stalled = true;
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.finish_response();
+        Ok(())
+    }
+
+    //보낼 바이트가 남아 있으면 true (쓰기 버퍼를 다 보냈으면 바디에서 다음 조각을 채움)
+    fn refill(&mut self) -> Result<bool> {
+        while self.written == self.write_buf.len() {
+            self.write_buf.clear();
+            self.written = 0;
+
+            match self.body.as_mut() {
+                Some(body) if !body.is_finished() => {
+                    body.fill(&mut self.write_buf, WRITE_CHUNK)?;
+                }
+                _ => return Ok(false),
+            }
+        }
+
+        Ok(true)
+    }
+
+    //응답 전송 완료
+    fn finish_response(&mut self) {
+        self.body = None;
+        self.state = if self.close_after_write || self.draining {
+            State::Closed
+        } else {
+            State::ReadingHeaders
+        };
+    }
+}
+
+// ============= 완료 기반 백엔드(io_uring)용 =============
+// 소켓 I/O는 이벤트 루프가 커널에 제출하고, Connection은 받은 데이터/보낸 바이트 수만 전달받음
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+impl Connection {
+    pub fn is_peer_closed(&self) -> bool {
+        self.peer_closed
+    }
+
+    //강제 종료 (타임아웃, I/O 에러)
+    pub fn close(&mut self) {
+        self.state = State::Closed;
+    }
+
+    //recv 완료: 빈 데이터는 클라이언트가 쓰기 방향을 닫았다는 뜻
+    pub fn on_received(&mut self, data: &[u8], router: &Router) {
+        if self.state == State::Closed {
+            return;
+        }
+
+        if data.is_empty() {
+            self.peer_closed = true;
+            self.on_read_closed();
+        } else {
+            self.read_buf.extend_from_slice(data);
+            self.last_active = Instant::now();
+        }
+
+        //응답 전송 중이면 쌓아 두기만 하고, 전송이 끝난 뒤 poll_output()에서 이어서 처리
+        self.dispatch(router);
+    }
+
+    /*
+     다음에 보낼 데이터 준비
+     - 보낼 바이트가 있으면 true, output()으로 꺼내서 send 제출
+     - 응답을 다 보냈으면 파이프라이닝된 다음 요청을 처리해서 이어서 준비
+    */
+    pub fn poll_output(&mut self, router: &Router) -> bool {
+        while self.state == State::Writing {
+            match self.refill() {
+                Ok(true) => return true,
+                Ok(false) => {
+                    self.finish_response();
+                    self.dispatch(router);
+                }
+                //업스트림 바디가 아직 오지 않음: poll_upstream() 후 다시 호출됨
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    self.stalled = true;
+                    return false;
+                }
+                Err(_) => self.state = State::Closed,
+            }
+        }
+
+        false
+    }
+
+    //업스트림 알림: 응답 헤더가 도착했으면 응답 시작 (전송은 poll_output()에서)
+    pub fn poll_upstream(&mut self, router: &Router) {
+        self.stalled = false;
+        self.dispatch(router);
+    }
+
+    //poll_output()이 준비한 보낼 바이트 (send가 완료될 때까지 내용과 위치가 바뀌지 않음)
+    pub fn output(&self) -> &[u8] {
+        &self.write_buf[self.written..]
+    }
+
+    //send 완료
+    pub fn on_sent(&mut self, n: usize) {
+        self.written += n;
+        self.last_active = Instant::now();
+    }
+}
+
+impl AsRawFd for Connection {
+    fn as_raw_fd(&self) -> RawFd {
+        self.stream.as_raw_fd()
+    }
+}
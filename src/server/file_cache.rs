@@ -0,0 +1,321 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fs::{File, Metadata},
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use Rustify::http::StatusCode;
+
+use crate::config::HttpConfig;
+
+//열린 정적 파일 (캐시에 있으면 여러 응답이 fd와 메타데이터를 공유)
+#[derive(Clone)]
+pub struct OpenFile {
+    pub file: Arc<File>,
+    pub metadata: Metadata,
+    pub path: PathBuf, //심볼릭 링크를 푼 실제 경로 (MIME 타입 판단용)
+}
+
+struct Entry {
+    result: Result<OpenFile, StatusCode>, //에러는 open_file_cache_errors가 켜졌을 때만 저장
+    validated: Instant,                   //마지막으로 디스크에서 다시 연 시각
+    last_used: Instant,
+    generation: u64, //마지막으로 쓰인 순번 (Entries::order의 키)
+}
+
+/*
+ 캐시 항목과 사용 순서
+ - 쓰일 때마다 새 순번을 받아 order의 끝으로 옮김 (O(log n))
+ - order의 앞이 가장 오래 쓰이지 않은 항목이므로 max 초과와 inactive 정리 모두 앞에서부터 꺼냄
+*/
+#[derive(Default)]
+struct Entries {
+    map: HashMap<PathBuf, Entry>,
+    order: BTreeMap<u64, PathBuf>,
+    generation: u64,
+}
+
+impl Entries {
+    //key를 가장 최근에 쓴 항목으로
+    fn touch(&mut self, key: &PathBuf, now: Instant) {
+        let Some(entry) = self.map.get_mut(key) else {
+            return;
+        };
+
+        self.generation += 1;
+        self.order.remove(&entry.generation);
+        self.order.insert(self.generation, key.clone());
+        entry.generation = self.generation;
+        entry.last_used = now;
+    }
+
+    //key가 없을 때만 (있으면 먼저 remove)
+    fn insert(&mut self, key: PathBuf, result: Result<OpenFile, StatusCode>, now: Instant) {
+        self.generation += 1;
+        self.order.insert(self.generation, key.clone());
+        self.map.insert(
+            key,
+            Entry {
+                result,
+                validated: now,
+                last_used: now,
+                generation: self.generation,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: &PathBuf) {
+        if let Some(entry) = self.map.remove(key) {
+            self.order.remove(&entry.generation);
+        }
+    }
+
+    //가장 오래 쓰이지 않은 항목을 keep(항목)이 false인 동안 닫기
+    fn evict_while(&mut self, keep: impl Fn(&Entries, &Entry) -> bool) {
+        while let Some((_, key)) = self.order.first_key_value() {
+            let entry = &self.map[key];
+            if keep(self, entry) {
+                break;
+            }
+            let key = key.clone();
+            self.remove(&key);
+        }
+    }
+}
+
+/*
+ 워커별 열린 파일 캐시 (nginx open_file_cache)
+ - 같은 파일 요청마다 경로 해석(realpath), open, fstat을 반복하지 않고 fd와 메타데이터를 재사용
+ - 워커는 스레드 하나로 모든 연결을 처리하므로 잠금 없이 RefCell로 공유
+ - valid: 이 시간이 지난 항목은 다음 요청에서 다시 열어서 파일 교체/삭제를 반영
+ - inactive: 이 시간 동안 쓰이지 않은 항목은 닫음
+ - max: 가득 차면 가장 오래 쓰이지 않은 항목부터 닫음
+ - errors: 없는 파일 등의 에러도 저장해서 같은 경로의 반복 조회를 막음 (negative caching)
+ - 설정이 off면 매번 새로 염
+*/
+pub struct FileCache {
+    max: usize,
+    inactive: Duration,
+    valid: Duration,
+    errors: bool,
+    entries: RefCell<Entries>,
+}
+
+impl FileCache {
+    pub fn new(http: &HttpConfig) -> FileCache {
+        let (max, inactive) = match http.open_file_cache {
+            Some(cache) => (cache.max, cache.inactive),
+            None => (0, Duration::ZERO),
+        };
+
+        FileCache {
+            max,
+            inactive,
+            valid: http.open_file_cache_valid,
+            errors: http.open_file_cache_errors,
+            entries: RefCell::new(Entries::default()),
+        }
+    }
+
+    //key의 캐시된 결과, 없거나 검증 주기가 지났으면 open()으로 새로 열어서 저장
+    pub fn open(
+        &self,
+        key: PathBuf,
+        open: impl FnOnce() -> Result<OpenFile, StatusCode>,
+    ) -> Result<OpenFile, StatusCode> {
+        self.open_at(key, Instant::now(), open)
+    }
+
+    fn open_at(
+        &self,
+        key: PathBuf,
+        now: Instant,
+        open: impl FnOnce() -> Result<OpenFile, StatusCode>,
+    ) -> Result<OpenFile, StatusCode> {
+        if self.max == 0 {
+            return open();
+        }
+
+        let mut entries = self.entries.borrow_mut();
+
+        //inactive 동안 쓰이지 않은 항목 닫기 (사용 순서의 앞에서부터이므로 닫을 항목 수만큼만 봄)
+        entries.evict_while(|_, entry| now.duration_since(entry.last_used) < self.inactive);
+
+        if let Some(entry) = entries.map.get(&key)
+            && now.duration_since(entry.validated) < self.valid
+        {
+            let result = entry.result.clone();
+            entries.touch(&key, now);
+            return result;
+        }
+
+        let result = open();
+
+        //서버 내부 에러는 일시적일 수 있으므로 저장하지 않음
+        let cacheable = match &result {
+            Ok(_) => true,
+            Err(StatusCode::InternalServerError) => false,
+            Err(_) => self.errors,
+        };
+        if !cacheable {
+            entries.remove(&key);
+            return result;
+        }
+
+        //다시 연 항목은 새로 넣으므로 빼고 셈
+        entries.remove(&key);
+        entries.evict_while(|entries, _| entries.map.len() < self.max);
+        entries.insert(key, result.clone(), now);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn cache(max: usize, inactive: u64, valid: u64, errors: bool) -> FileCache {
+        FileCache {
+            max,
+            inactive: Duration::from_secs(inactive),
+            valid: Duration::from_secs(valid),
+            errors,
+            entries: RefCell::new(Entries::default()),
+        }
+    }
+
+    fn file() -> Result<OpenFile, StatusCode> {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        let file = File::open(&path).unwrap();
+        let metadata = file.metadata().unwrap();
+
+        Ok(OpenFile {
+            file: Arc::new(file),
+            metadata,
+            path,
+        })
+    }
+
+    struct Clock {
+        start: Instant,
+    }
+
+    impl Clock {
+        //start부터 secs초 뒤에 key를 찾아서 디스크에서 새로 열었는지
+        fn opens(
+            &self,
+            cache: &FileCache,
+            key: &str,
+            secs: u64,
+            result: fn() -> Result<OpenFile, StatusCode>,
+        ) -> bool {
+            let opened = Cell::new(false);
+            let now = self.start + Duration::from_secs(secs);
+            let _ = cache.open_at(PathBuf::from(key), now, || {
+                opened.set(true);
+                result()
+            });
+            opened.get()
+        }
+    }
+
+    fn clock() -> Clock {
+        Clock {
+            start: Instant::now(),
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = cache(2, 60, 60, false);
+        let clock = clock();
+
+        assert!(clock.opens(&cache, "/a", 0, file));
+        assert!(clock.opens(&cache, "/b", 1, file));
+        assert!(!clock.opens(&cache, "/a", 2, file));
+
+        //가득 차면 가장 오래 쓰이지 않은 b를 닫음
+        assert!(clock.opens(&cache, "/c", 3, file));
+        assert_eq!(cache.entries.borrow().map.len(), 2);
+        assert!(!clock.opens(&cache, "/a", 4, file));
+        assert!(!clock.opens(&cache, "/c", 5, file));
+        assert!(clock.opens(&cache, "/b", 6, file));
+
+        //이번에는 a가 가장 오래됨
+        assert!(clock.opens(&cache, "/a", 7, file));
+        let entries = cache.entries.borrow();
+        assert_eq!(entries.map.len(), 2);
+        assert_eq!(entries.order.len(), 2);
+    }
+
+    #[test]
+    fn closes_inactive_entries() {
+        let cache = cache(10, 20, 60, false);
+        let clock = clock();
+
+        assert!(clock.opens(&cache, "/a", 0, file));
+        assert!(clock.opens(&cache, "/b", 15, file));
+        assert!(!clock.opens(&cache, "/a", 19, file));
+
+        //a는 19초, b는 15초에 마지막으로 쓰임
+        assert!(!clock.opens(&cache, "/a", 38, file));
+        assert!(clock.opens(&cache, "/b", 38, file));
+        assert!(clock.opens(&cache, "/c", 60, file));
+        assert_eq!(cache.entries.borrow().map.len(), 1);
+    }
+
+    #[test]
+    fn reopens_after_valid() {
+        let cache = cache(10, 60, 10, false);
+        let clock = clock();
+
+        assert!(clock.opens(&cache, "/a", 0, file));
+        assert!(!clock.opens(&cache, "/a", 9, file));
+        //쓰여도 검증 시각은 그대로
+        assert!(clock.opens(&cache, "/a", 10, file));
+        assert!(!clock.opens(&cache, "/a", 19, file));
+        assert_eq!(cache.entries.borrow().map.len(), 1);
+    }
+
+    #[test]
+    fn caches_errors_only_when_enabled() {
+        let not_found = || Err(StatusCode::NotFound);
+        let internal = || Err(StatusCode::InternalServerError);
+        let clock = clock();
+
+        let off = cache(10, 60, 60, false);
+        assert!(clock.opens(&off, "/missing", 0, not_found));
+        assert!(clock.opens(&off, "/missing", 1, not_found));
+
+        //저장하지 않는 결과로 다시 열리면 이전 항목도 버림
+        assert!(clock.opens(&off, "/a", 0, file));
+        assert!(clock.opens(&off, "/a", 70, not_found));
+        assert!(off.entries.borrow().map.is_empty());
+
+        let on = cache(10, 60, 60, true);
+        assert!(clock.opens(&on, "/missing", 0, not_found));
+        assert!(!clock.opens(&on, "/missing", 1, not_found));
+        let cached = on.open_at(PathBuf::from("/missing"), clock.start, file);
+        assert_eq!(cached.err(), Some(StatusCode::NotFound));
+
+        //서버 내부 에러는 일시적일 수 있으므로 저장하지 않음
+        assert!(clock.opens(&on, "/error", 0, internal));
+        assert!(clock.opens(&on, "/error", 1, internal));
+    }
+
+    #[test]
+    fn opens_every_time_when_off() {
+        let cache = cache(0, 60, 60, true);
+        let clock = clock();
+
+        assert!(clock.opens(&cache, "/a", 0, file));
+        assert!(clock.opens(&cache, "/a", 0, file));
+        assert!(cache.entries.borrow().map.is_empty());
+    }
+}
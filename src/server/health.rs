@@ -0,0 +1,476 @@
+use std::{
+    io::{Error, Result},
+    ptr,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use crate::config::{Address, CircuitBreakerConfig, HealthCheckConfig, HttpConfig};
+
+//업스트림 서버 하나의 상태 (공유 메모리 안, 0으로 채워진 상태가 정상)
+#[repr(C)]
+struct Slot {
+    fails: AtomicU32,            //지금 실패 구간 안의 실패 수 (passive)
+    window: AtomicU64,           //실패 구간을 시작한 시각 (ms)
+    down_until: AtomicU64,       //이 시각까지 보내지 않음 (ms, passive)
+    unhealthy: AtomicBool,       //health_check 결과 (active)
+    streak: AtomicU32,           //지금 상태와 반대 결과가 연속으로 나온 횟수 (active)
+    next_check: AtomicU64,       //다음 health_check 시각 (ms, 먼저 가져간 워커만 검사)
+    breaker_failures: AtomicU32, //연속 실패 수 (circuit_breaker의 failures 이상이면 회로가 열림)
+    breaker_until: AtomicU64,    //회로가 열려 있는 시각 (ms), 지나면 half-open
+}
+
+//mmap한 Slot 배열
+struct Mapping {
+    slots: *mut Slot,
+    len: usize,
+}
+
+//프로세스 간 공유 메모리의 Slot은 원자적 필드로만 접근
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            unsafe { libc::munmap(self.slots.cast(), self.len * size_of::<Slot>()) };
+        }
+    }
+}
+
+/*
+ 모든 워커 프로세스가 공유하는 업스트림 서버 상태 표
+ - 마스터가 워커 세대마다 익명 공유 메모리(MAP_SHARED)로 만들고, fork한 워커들이 같은 메모리를 봄
+ - upstream 설정 순서대로 서버마다 Slot 하나 (reload하면 새 세대는 새 표로 시작)
+ - 시각은 CLOCK_MONOTONIC 밀리초 (프로세스끼리 같은 시계)
+*/
+#[derive(Clone)]
+pub struct HealthTable {
+    mapping: Arc<Mapping>,
+}
+
+impl HealthTable {
+    pub fn new(http: &HttpConfig) -> Result<HealthTable> {
+        let len: usize = http
+            .upstreams
+            .iter()
+            .map(|upstream| upstream.servers.len())
+            .sum();
+
+        let slots = if len == 0 {
+            ptr::null_mut()
+        } else {
+            let slots = unsafe {
+                libc::mmap(
+                    ptr::null_mut(),
+                    len * size_of::<Slot>(),
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED | libc::MAP_ANON,
+                    -1,
+                    0,
+                )
+            };
+            if slots == libc::MAP_FAILED {
+                return Err(Error::last_os_error());
+            }
+            slots.cast()
+        };
+
+        Ok(HealthTable {
+            mapping: Arc::new(Mapping { slots, len }),
+        })
+    }
+
+    /*
+     index번째 서버(upstream 설정 순서로 센 전체 인덱스)의 상태
+     - max_fails가 0이면 실패해도 내리지 않음
+    */
+    pub fn get(
+        &self,
+        index: usize,
+        max_fails: u32,
+        fail_timeout: Duration,
+        breaker: Option<CircuitBreakerConfig>,
+    ) -> Health {
+        assert!(index < self.mapping.len, "health slot out of range");
+
+        Health {
+            mapping: self.mapping.clone(),
+            index,
+            max_fails,
+            fail_timeout: fail_timeout.as_millis() as u64,
+            breaker_failures: breaker.map_or(0, |breaker| breaker.failures),
+            breaker_timeout: breaker.map_or(0, |breaker| breaker.timeout.as_millis() as u64),
+        }
+    }
+}
+
+//서버 하나의 공유 상태
+#[derive(Clone)]
+pub struct Health {
+    mapping: Arc<Mapping>,
+    index: usize,
+    max_fails: u32,
+    fail_timeout: u64,     //ms
+    breaker_failures: u32, //0이면 circuit_breaker 없음
+    breaker_timeout: u64,  //ms
+}
+
+impl Health {
+    fn slot(&self) -> &Slot {
+        //new()에서 범위를 확인했고, 매핑은 Arc로 살아 있음
+        unsafe { &*self.mapping.slots.add(self.index) }
+    }
+
+    /*
+     요청을 보내도 되는지 (실패로 내려가 있지도, health_check에서 비정상이지도, 회로가 열려 있지도 않음)
+     - half-open이면 true지만 실제로 보내기 전에 acquire()로 시험 요청 차례를 가져가야 함
+    */
+    pub fn is_available(&self) -> bool {
+        self.is_available_at(now())
+    }
+
+    fn is_available_at(&self, now: u64) -> bool {
+        let slot = self.slot();
+        !slot.unhealthy.load(Ordering::Relaxed)
+            && now >= slot.down_until.load(Ordering::Relaxed)
+            && !(self.is_tripped() && now < slot.breaker_until.load(Ordering::Relaxed))
+    }
+
+    //회로가 열렸거나 half-open인지
+    fn is_tripped(&self) -> bool {
+        self.breaker_failures > 0
+            && self.slot().breaker_failures.load(Ordering::Relaxed) >= self.breaker_failures
+    }
+
+    /*
+     이 서버로 요청을 보내기 직전에 호출
+     - 회로가 half-open이면 모든 워커 중 한 요청만 시험으로 보냄 (가져가지 못하면 false)
+     - 시험 요청의 결과가 오지 않아도(클라이언트가 끊는 등) timeout 뒤에 다시 시험함
+    */
+    pub fn acquire(&self) -> bool {
+        self.acquire_at(now())
+    }
+
+    fn acquire_at(&self, now: u64) -> bool {
+        if !self.is_tripped() {
+            return true;
+        }
+
+        let slot = self.slot();
+        let until = slot.breaker_until.load(Ordering::Relaxed);
+
+        now >= until
+            && slot
+                .breaker_until
+                .compare_exchange(
+                    until,
+                    now + self.breaker_timeout,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+    }
+
+    //업스트림 요청 성공 기록 (회로가 열려 있었으면 닫음)
+    pub fn success(&self, addr: &Address) {
+        if self.breaker_failures == 0 {
+            return;
+        }
+
+        let failures = self.slot().breaker_failures.swap(0, Ordering::Relaxed);
+        if failures >= self.breaker_failures {
+            println!("✅ Upstream {} circuit closed", addr);
+        }
+    }
+
+    /*
+     업스트림 요청 실패 기록
+     - passive: fail_timeout 구간 안에서 max_fails번이 되면 fail_timeout 동안 내림
+     - circuit_breaker: 연속 failures번이면 회로를 열고, half-open 시험 요청이 실패하면 다시 열어 둠
+     - 여러 워커가 동시에 기록해도 한두 번 차이만 나므로 잠그지 않음
+    */
+    pub fn fail(&self, addr: &Address) {
+        self.fail_at(addr, now());
+    }
+
+    fn fail_at(&self, addr: &Address, now: u64) {
+        self.trip(addr, now);
+
+        if self.max_fails == 0 {
+            return;
+        }
+
+        let slot = self.slot();
+
+        let fails = if now >= slot.window.load(Ordering::Relaxed) + self.fail_timeout {
+            slot.window.store(now, Ordering::Relaxed);
+            slot.fails.store(1, Ordering::Relaxed);
+            1
+        } else {
+            slot.fails.fetch_add(1, Ordering::Relaxed) + 1
+        };
+
+        if fails >= self.max_fails {
+            slot.fails.store(0, Ordering::Relaxed);
+            slot.window.store(0, Ordering::Relaxed);
+            slot.down_until
+                .store(now + self.fail_timeout, Ordering::Relaxed);
+            eprintln!(
+                "⚠️ Upstream {} marked down for {}ms after {} failures",
+                addr, self.fail_timeout, fails
+            );
+        }
+    }
+
+    fn trip(&self, addr: &Address, now: u64) {
+        if self.breaker_failures == 0 {
+            return;
+        }
+
+        let slot = self.slot();
+        let failures = slot.breaker_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < self.breaker_failures {
+            return;
+        }
+
+        //계속 더해서 넘치지 않도록 기준값에 묶어 둠
+        slot.breaker_failures
+            .store(self.breaker_failures, Ordering::Relaxed);
+        slot.breaker_until
+            .store(now + self.breaker_timeout, Ordering::Relaxed);
+
+        //이미 열려 있던 회로(half-open 시험 실패, 열리기 전에 보낸 요청의 실패)는 기간만 늘림
+        if failures == self.breaker_failures {
+            eprintln!(
+                "⚠️ Upstream {} circuit opened for {}ms after {} consecutive failures",
+                addr, self.breaker_timeout, failures
+            );
+        }
+    }
+
+    /*
+     health_check 차례를 가져감 (interval마다 모든 워커 중 한 프로세스만 true)
+     - 가져가지 못했으면 다음 차례까지 남은 시간
+    */
+    pub fn claim_check(&self, interval: Duration) -> std::result::Result<(), Duration> {
+        let slot = self.slot();
+        let now = now();
+        let next = slot.next_check.load(Ordering::Relaxed);
+
+        if now >= next
+            && slot
+                .next_check
+                .compare_exchange(
+                    next,
+                    now + interval.as_millis() as u64,
+                    Ordering::Relaxed,
+                    Ordering::Relaxed,
+                )
+                .is_ok()
+        {
+            return Ok(());
+        }
+
+        let next = slot.next_check.load(Ordering::Relaxed);
+        Err(Duration::from_millis(next.saturating_sub(now).max(1)))
+    }
+
+    /*
+     health_check 결과 기록 (active)
+     - 정상 상태에서 fails번 연속 실패하면 비정상, 비정상 상태에서 passes번 연속 성공하면 정상
+     - 검사한 워커만 기록하므로 상태가 바뀔 때 로그는 한 번만 남음
+    */
+    pub fn record_check(
+        &self,
+        check: &HealthCheckConfig,
+        addr: &Address,
+        result: std::result::Result<(), String>,
+    ) {
+        let slot = self.slot();
+        let unhealthy = slot.unhealthy.load(Ordering::Relaxed);
+
+        //지금 상태와 같은 결과면 연속 횟수를 초기화
+        if result.is_err() == unhealthy {
+            slot.streak.store(0, Ordering::Relaxed);
+            return;
+        }
+
+        let streak = slot.streak.fetch_add(1, Ordering::Relaxed) + 1;
+        let needed = if unhealthy { check.passes } else { check.fails };
+        if streak < needed {
+            return;
+        }
+
+        slot.streak.store(0, Ordering::Relaxed);
+        slot.unhealthy.store(!unhealthy, Ordering::Relaxed);
+        match result {
+            Ok(()) => println!("✅ Upstream {} is healthy", addr),
+            Err(reason) => eprintln!("⚠️ Upstream {} is unhealthy: {}", addr, reason),
+        }
+    }
+}
+
+//CLOCK_MONOTONIC 밀리초 (모든 프로세스가 같은 값을 봄)
+fn now() -> u64 {
+    let mut ts: libc::timespec = unsafe { std::mem::zeroed() };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    ts.tv_sec as u64 * 1000 + ts.tv_nsec as u64 / 1_000_000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Balance, UpstreamConfig, UpstreamServer};
+
+    //시각은 CLOCK_MONOTONIC처럼 큰 값에서 시작 (ms)
+    const T: u64 = 1_000_000;
+
+    fn addr() -> Address {
+        Address::Tcp(([127, 0, 0, 1], 8001).into())
+    }
+
+    //서버 하나짜리 표
+    fn table() -> HealthTable {
+        let upstream = UpstreamConfig {
+            name: String::from("app"),
+            balance: Balance::RoundRobin,
+            servers: vec![UpstreamServer {
+                addr: addr(),
+                weight: 1,
+                max_fails: 1,
+                fail_timeout: Duration::from_secs(10),
+            }],
+            health_check: None,
+            circuit_breaker: None,
+            keepalive: 0,
+            keepalive_timeout: Duration::from_secs(60),
+        };
+        HealthTable::new(&HttpConfig {
+            upstreams: vec![upstream],
+            ..HttpConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn check(fails: u32, passes: u32) -> HealthCheckConfig {
+        HealthCheckConfig {
+            interval: Duration::from_secs(5),
+            timeout: Duration::from_secs(1),
+            fails,
+            passes,
+            uri: String::from("/"),
+            status: vec![(200, 399)],
+            body: None,
+        }
+    }
+
+    #[test]
+    fn marks_down_after_max_fails_within_window() {
+        let table = table();
+        let health = table.get(0, 3, Duration::from_secs(10), None);
+
+        health.fail_at(&addr(), T);
+        health.fail_at(&addr(), T + 1_000);
+        assert!(health.is_available_at(T + 1_000));
+
+        //fail_timeout이 지나면 구간을 새로 시작해서 앞의 실패는 세지 않음
+        health.fail_at(&addr(), T + 10_000);
+        health.fail_at(&addr(), T + 11_000);
+        assert!(health.is_available_at(T + 11_000));
+        assert_eq!(health.slot().fails.load(Ordering::Relaxed), 2);
+
+        health.fail_at(&addr(), T + 12_000);
+        assert!(!health.is_available_at(T + 12_000));
+        assert!(!health.is_available_at(T + 21_999));
+        assert!(health.is_available_at(T + 22_000));
+
+        //내린 뒤에는 실패 수를 0부터 다시 셈
+        assert_eq!(health.slot().fails.load(Ordering::Relaxed), 0);
+        health.fail_at(&addr(), T + 22_000);
+        assert!(health.is_available_at(T + 22_000));
+    }
+
+    #[test]
+    fn never_marks_down_without_max_fails() {
+        let table = table();
+        let health = table.get(0, 0, Duration::from_secs(10), None);
+
+        for i in 0..10 {
+            health.fail_at(&addr(), T + i);
+        }
+        assert!(health.is_available_at(T + 10));
+    }
+
+    #[test]
+    fn lets_one_probe_through_half_open_circuit() {
+        let table = table();
+        let breaker = CircuitBreakerConfig {
+            failures: 2,
+            timeout: Duration::from_secs(30),
+        };
+        //같은 서버를 보는 두 워커
+        let health = table.get(0, 0, Duration::from_secs(10), Some(breaker));
+        let other = table.get(0, 0, Duration::from_secs(10), Some(breaker));
+
+        health.fail_at(&addr(), T);
+        assert!(health.acquire_at(T));
+        health.fail_at(&addr(), T + 1);
+
+        //open: timeout 동안 보내지 않음
+        assert!(!health.is_available_at(T + 1));
+        assert!(!other.is_available_at(T + 30_000));
+        assert!(!other.acquire_at(T + 30_000));
+
+        //half-open: 모든 워커 중 한 요청만 시험으로 보냄
+        assert!(health.is_available_at(T + 30_001));
+        assert!(other.acquire_at(T + 30_001));
+        assert!(!health.acquire_at(T + 30_001));
+        assert!(!health.is_available_at(T + 30_002));
+
+        //시험 요청이 실패하면 다시 timeout 동안 열어 둠
+        other.fail_at(&addr(), T + 31_000);
+        assert!(!health.acquire_at(T + 60_001));
+        assert!(health.acquire_at(T + 61_000));
+
+        //시험 요청 결과가 오지 않으면 timeout 뒤에 다시 시험
+        assert!(!other.acquire_at(T + 90_999));
+        assert!(other.acquire_at(T + 91_000));
+
+        //성공하면 닫힘
+        other.success(&addr());
+        assert!(health.is_available_at(T + 91_000));
+        assert!(health.acquire_at(T + 91_000));
+        assert!(other.acquire_at(T + 91_000));
+    }
+
+    #[test]
+    fn records_health_check_streaks() {
+        let table = table();
+        let health = table.get(0, 1, Duration::from_secs(10), None);
+        let check = check(2, 3);
+        let failed = || Err(String::from("status 500"));
+
+        //정상 상태: 연속 2번 실패해야 비정상 (중간에 성공하면 처음부터)
+        health.record_check(&check, &addr(), failed());
+        health.record_check(&check, &addr(), Ok(()));
+        health.record_check(&check, &addr(), failed());
+        assert!(health.is_available_at(T));
+        health.record_check(&check, &addr(), failed());
+        assert!(!health.is_available_at(T));
+
+        //비정상 상태: 연속 3번 성공해야 정상
+        health.record_check(&check, &addr(), Ok(()));
+        health.record_check(&check, &addr(), Ok(()));
+        health.record_check(&check, &addr(), failed());
+        health.record_check(&check, &addr(), Ok(()));
+        health.record_check(&check, &addr(), Ok(()));
+        assert!(!health.is_available_at(T));
+        health.record_check(&check, &addr(), Ok(()));
+        assert!(health.is_available_at(T));
+        assert_eq!(health.slot().streak.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,616 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    io::{Error, ErrorKind, Result},
+    mem::zeroed,
+    net::{SocketAddr, TcpListener},
+    os::{
+        fd::{AsRawFd, FromRawFd, RawFd},
+        unix::{
+            fs::{FileTypeExt, PermissionsExt},
+            net::{UnixListener, UnixStream},
+        },
+    },
+    path::Path,
+    time::Duration,
+};
+
+use libc::{
+    AF_INET, AF_INET6, F_GETFD, IPPROTO_IPV6, IPPROTO_TCP, IPV6_V6ONLY, SO_KEEPALIVE, SO_RCVBUF,
+    SO_REUSEADDR, SO_REUSEPORT, SO_SNDBUF, SOL_SOCKET, TCP_KEEPCNT, TCP_KEEPINTVL, bind, c_int,
+    c_void, sa_family_t, sockaddr, sockaddr_in, sockaddr_in6, sockaddr_storage, socklen_t,
+};
+
+#[cfg(target_os = "linux")]
+use libc::TCP_KEEPIDLE;
+//macOS는 같은 옵션의 이름이 TCP_KEEPALIVE
+#[cfg(target_os = "macos")]
+use libc::TCP_KEEPALIVE as TCP_KEEPIDLE;
+
+use crate::{
+    config::{Address, Config, ListenConfig},
+    server::socket::Listener,
+};
+
+//바이너리 업그레이드 시 새 마스터에 넘기는 리스너 목록 (예: "0.0.0.0:7878=5;unix:/run/rustify.sock=6")
+pub const LISTENERS_ENV: &str = "RUSTIFY_LISTENERS";
+
+//listen 주소 종류에 맞는 소켓 생성
+pub fn create_listener(listen: &ListenConfig) -> Result<Listener> {
+    match &listen.addr {
+        Address::Tcp(addr) => create_reusable_listener(addr, listen).map(Listener::Tcp),
+        Address::Unix(path) => create_unix_listener(path, listen).map(Listener::Unix),
+    }
+}
+
+/*
+  SO_REUSEPORT 소켓 생성
+  - IPv4(sockaddr_in), IPv6(sockaddr_in6) 모두 지원, IPv6는 bind 전에 IPV6_V6ONLY 설정
+*/
+pub fn create_reusable_listener(addr: &SocketAddr, listen: &ListenConfig) -> Result<TcpListener> {
+    let domain = if addr.is_ipv4() { AF_INET } else { AF_INET6 };
+
+    //소켓 생성
+    let sockfd: i32 = unsafe { libc::socket(domain, libc::SOCK_STREAM, 0) };
+
+    if sockfd < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    //에러가 나면 drop되면서 close
+    let socket = unsafe { TcpListener::from_raw_fd(sockfd) };
+
+    //SO_REUSEADDR 설정
+    set_option(sockfd, SOL_SOCKET, SO_REUSEADDR, 1)?;
+
+    //SO_REUSEPORT 설정(여러 소켓이 같은 포트 사용)
+    set_option(sockfd, SOL_SOCKET, SO_REUSEPORT, 1)?;
+
+    //IPV6_V6ONLY 설정 (끄면 IPv4 연결도 ::ffff:a.b.c.d 주소로 받음)
+    if addr.is_ipv6() {
+        set_option(sockfd, IPPROTO_IPV6, IPV6_V6ONLY, listen.ipv6only as c_int)?;
+    }
+
+    //bind
+    let (sockaddr, len) = to_sockaddr(addr);
+    let ret = unsafe {
+        bind(
+            sockfd,
+            &sockaddr as *const sockaddr_storage as *const sockaddr,
+            len,
+        )
+    };
+
+    if ret < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    //listen
+    let ret = unsafe { libc::listen(sockfd, listen.backlog) };
+
+    if ret < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(socket)
+}
+
+/*
+ Unix 도메인 소켓 생성
+ - 이전 프로세스가 지우지 못한 소켓 파일이 남아 있으면 지우고 bind (다른 프로세스가 쓰는 중이면 에러)
+ - mode가 있으면 소켓 파일 권한 변경 (접속하려면 쓰기 권한이 필요)
+*/
+fn create_unix_listener(path: &Path, listen: &ListenConfig) -> Result<UnixListener> {
+    remove_stale_socket(path)?;
+
+    let socket = UnixListener::bind(path)?;
+
+    if let Some(mode) = listen.mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+    }
+
+    //UnixListener::bind는 기본 backlog로 listen하므로 설정값으로 다시 listen
+    if unsafe { libc::listen(socket.as_raw_fd(), listen.backlog) } < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(socket)
+}
+
+//남아 있는 소켓 파일이 아무도 받지 않는(접속이 거부되는) 소켓이면 삭제
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    if !metadata.file_type().is_socket() {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            "file exists and is not a socket",
+        ));
+    }
+
+    match UnixStream::connect(path) {
+        Ok(_) => Err(Error::new(
+            ErrorKind::AddrInUse,
+            "socket is in use by another process",
+        )),
+        Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+            println!("⚠️ Removing stale socket file {}", path.display());
+            fs::remove_file(path)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/*
+ listen 소켓 옵션 설정 (accept한 연결은 리스너의 버퍼 크기와 keepalive 설정을 물려받음)
+ - 설정에 없는 옵션은 건드리지 않음 (커널 기본값 또는 이전에 설정한 값 유지)
+ - deferred는 끄는 것도 반영해야 하므로 항상 설정
+ - 실패하면 옵션 이름과 함께 에러 (시작/reload 실패)
+*/
+fn apply_options(fd: RawFd, listen: &ListenConfig) -> Result<()> {
+    let set = |level: c_int, name: c_int, label: &str, value: c_int| {
+        set_option(fd, level, name, value)
+            .map_err(|e| Error::new(e.kind(), format!("setsockopt({}) failed ({})", label, e)))
+    };
+
+    if let Some(size) = listen.rcvbuf {
+        set(SOL_SOCKET, SO_RCVBUF, "SO_RCVBUF", size)?;
+    }
+    if let Some(size) = listen.sndbuf {
+        set(SOL_SOCKET, SO_SNDBUF, "SO_SNDBUF", size)?;
+    }
+
+    if let Some(keepalive) = &listen.keepalive {
+        set(
+            SOL_SOCKET,
+            SO_KEEPALIVE,
+            "SO_KEEPALIVE",
+            keepalive.enabled as c_int,
+        )?;
+        if let Some(idle) = keepalive.idle {
+            set(IPPROTO_TCP, TCP_KEEPIDLE, "TCP_KEEPIDLE", seconds(idle))?;
+        }
+        if let Some(interval) = keepalive.interval {
+            set(
+                IPPROTO_TCP,
+                TCP_KEEPINTVL,
+                "TCP_KEEPINTVL",
+                seconds(interval),
+            )?;
+        }
+        if let Some(count) = keepalive.count {
+            set(IPPROTO_TCP, TCP_KEEPCNT, "TCP_KEEPCNT", count)?;
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    if listen.addr.tcp().is_some() {
+        //값은 기다릴 최대 초 (1이면 커널이 SYN-ACK 재전송 한 번 동안 데이터를 기다림)
+        set(
+            IPPROTO_TCP,
+            libc::TCP_DEFER_ACCEPT,
+            "TCP_DEFER_ACCEPT",
+            listen.deferred as c_int,
+        )?;
+
+        if let Some(queue) = listen.fastopen {
+            set(IPPROTO_TCP, libc::TCP_FASTOPEN, "TCP_FASTOPEN", queue)?;
+        }
+    }
+
+    Ok(())
+}
+
+//초 단위 소켓 옵션 값 (c_int 범위로 자름)
+fn seconds(duration: Duration) -> c_int {
+    duration.as_secs().min(c_int::MAX as u64) as c_int
+}
+
+fn set_option(fd: RawFd, level: c_int, name: c_int, value: c_int) -> Result<()> {
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            name,
+            &value as *const c_int as *const c_void,
+            size_of::<c_int>() as socklen_t,
+        )
+    };
+
+    if ret < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+fn get_option(fd: RawFd, level: c_int, name: c_int) -> Result<c_int> {
+    let mut value: c_int = 0;
+    let mut len = size_of::<c_int>() as socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            level,
+            name,
+            &mut value as *mut c_int as *mut c_void,
+            &mut len,
+        )
+    };
+
+    if ret < 0 {
+        return Err(Error::last_os_error());
+    }
+
+    Ok(value)
+}
+
+//SocketAddr → sockaddr_in / sockaddr_in6 (sockaddr_storage에 담아서 길이와 함께 리턴)
+fn to_sockaddr(addr: &SocketAddr) -> (sockaddr_storage, socklen_t) {
+    let mut storage: sockaddr_storage = unsafe { zeroed() };
+
+    let len = match addr {
+        SocketAddr::V4(addr) => {
+            let sa = unsafe { &mut *(&mut storage as *mut sockaddr_storage as *mut sockaddr_in) };
+            sa.sin_family = AF_INET as sa_family_t;
+            sa.sin_port = addr.port().to_be();
+            sa.sin_addr.s_addr = u32::from_ne_bytes(addr.ip().octets());
+            size_of::<sockaddr_in>()
+        }
+        SocketAddr::V6(addr) => {
+            let sa = unsafe { &mut *(&mut storage as *mut sockaddr_storage as *mut sockaddr_in6) };
+            sa.sin6_family = AF_INET6 as sa_family_t;
+            sa.sin6_port = addr.port().to_be();
+            sa.sin6_flowinfo = addr.flowinfo();
+            sa.sin6_addr.s6_addr = addr.ip().octets();
+            sa.sin6_scope_id = addr.scope_id();
+            size_of::<sockaddr_in6>()
+        }
+    };
+
+    (storage, len as socklen_t)
+}
+
+/*
+ 마스터가 소유하는 리스너 (config.listeners와 같은 순서)
+ - 마스터가 한 번 만들고 워커는 fork로 물려받음 (워커가 죽거나 교체되어도 소켓은 계속 열려 있음)
+ - 주소마다 소켓 그룹: accept_strategy shared면 1개(모든 워커가 공유), reuseport면 워커 수만큼(워커 i는 i번째 소켓)
+ - reload: 주소가 같은 그룹의 소켓은 순서대로 그대로 쓰고, 모자라면 새로 bind, 남으면 닫음 (빠진 주소도 닫음)
+ - 바이너리 업그레이드: fd를 LISTENERS_ENV로 새 마스터에 넘기고, 새 마스터는 주소가 같은 fd를 그대로 씀
+ - Unix 소켓은 SO_REUSEPORT 분배가 없으므로 accept_strategy와 상관없이 하나를 공유, 소켓 파일은 닫을 때 마스터가 삭제
+*/
+pub struct Listeners {
+    groups: Vec<Group>,
+}
+
+//listen 주소 하나의 소켓들 (reuseport면 커널의 SO_REUSEPORT 그룹과 같은 순서)
+struct Group {
+    addr: Address,
+    sockets: Vec<Listener>,
+}
+
+impl Listeners {
+    //시작할 때: 이전 마스터가 넘긴 리스너가 있으면 쓰고, 나머지는 새로 bind
+    pub fn open(config: &Config) -> Result<Listeners> {
+        let mut inherited = inherited_listeners();
+        let listeners = Self::bind(config, |addr| {
+            Ok(inherited.remove(addr).unwrap_or_default())
+        })?;
+
+        //설정에서 빠진 주소는 닫음 (Unix 소켓은 파일도 삭제)
+        for addr in inherited.keys() {
+            println!(
+                "⚠️ Closing inherited listener {} (not in configuration)",
+                addr
+            );
+            if let Address::Unix(path) = addr {
+                let _ = fs::remove_file(path);
+            }
+        }
+
+        Ok(listeners)
+    }
+
+    //reload: 현재 소켓을 복제해서 재사용하므로 실패해도 기존 리스너는 그대로
+    pub fn reopen(&self, config: &Config) -> Result<Listeners> {
+        Self::bind(config, |addr| match self.group(addr) {
+            Some(group) => group.sockets.iter().map(Listener::try_clone).collect(),
+            None => Ok(Vec::new()),
+        })
+    }
+
+    fn bind<F>(config: &Config, mut reuse: F) -> Result<Listeners>
+    where
+        F: FnMut(&Address) -> Result<Vec<Listener>>,
+    {
+        let mut groups: Vec<Group> = Vec::with_capacity(config.listeners.len());
+
+        for listen in &config.listeners {
+            let count = config
+                .events
+                .accept_strategy
+                .sockets_per_listener(&listen.addr, config.worker_processes);
+            let mut sockets = reuse(&listen.addr)?;
+            //남는 소켓은 뒤에서부터 닫음 (앞쪽 소켓의 SO_REUSEPORT 그룹 내 순서 유지)
+            sockets.truncate(count);
+
+            for socket in &sockets {
+                //IPV6_V6ONLY는 bind 전에만 바꿀 수 있음
+                if listen.addr.tcp().is_some_and(SocketAddr::is_ipv6)
+                    && get_option(socket.as_raw_fd(), IPPROTO_IPV6, IPV6_V6ONLY)?
+                        != listen.ipv6only as c_int
+                {
+                    return Err(Error::other(format!(
+                        "ipv6only of {} can not be changed without restart",
+                        listen.addr
+                    )));
+                }
+
+                //backlog이 바뀌었을 수 있으므로 다시 listen (이미 listen 중인 소켓은 backlog만 바뀜)
+                if unsafe { libc::listen(socket.as_raw_fd(), listen.backlog) } < 0 {
+                    return Err(Error::last_os_error());
+                }
+            }
+
+            while sockets.len() < count {
+                let socket = create_listener(listen).map_err(|e| {
+                    Error::new(
+                        e.kind(),
+                        format!("bind() to {} failed ({})", listen.addr, e),
+                    )
+                })?;
+                sockets.push(socket);
+            }
+
+            for socket in &sockets {
+                //논블로킹 플래그는 fork/dup한 모든 fd가 공유 (epoll, io_uring 워커 모두 논블로킹 소켓 사용)
+                socket.set_nonblocking(true)?;
+
+                //reload로 바뀐 옵션도 반영되도록 재사용한 소켓에도 매번 설정
+                apply_options(socket.as_raw_fd(), listen)
+                    .map_err(|e| Error::new(e.kind(), format!("{}: {}", listen.addr, e)))?;
+            }
+
+            #[cfg(target_os = "linux")]
+            if let Address::Tcp(addr) = &listen.addr {
+                steering::apply(addr, &sockets, config)?;
+            }
+
+            groups.push(Group {
+                addr: listen.addr.clone(),
+                sockets,
+            });
+        }
+
+        Ok(Listeners { groups })
+    }
+
+    fn group(&self, addr: &Address) -> Option<&Group> {
+        self.groups.iter().find(|group| group.addr == *addr)
+    }
+
+    /*
+     Unix 소켓 파일 삭제
+     - reload 후: current에 없는(설정에서 빠진) 주소만
+     - 종료할 때(current = None): 전부 (업그레이드로 새 마스터에 넘긴 경우는 호출하지 않음)
+    */
+    pub fn unlink(&self, current: Option<&Listeners>) {
+        for group in &self.groups {
+            let Address::Unix(path) = &group.addr else {
+                continue;
+            };
+            if current.is_some_and(|current| current.group(&group.addr).is_some()) {
+                continue;
+            }
+
+            if let Err(e) = fs::remove_file(path) {
+                eprintln!("⚠️ unlink() \"{}\" failed ({})", path.display(), e);
+            }
+        }
+    }
+
+    //워커 id가 accept할 소켓 (config.listeners와 같은 순서, fork된 워커 안에서 복제)
+    pub fn worker_sockets(&self, id: usize) -> Result<Vec<Listener>> {
+        self.groups
+            .iter()
+            .map(|group| group.sockets[id % group.sockets.len()].try_clone())
+            .collect()
+    }
+
+    pub fn raw_fds(&self) -> Vec<RawFd> {
+        self.groups
+            .iter()
+            .flat_map(|group| group.sockets.iter().map(AsRawFd::as_raw_fd))
+            .collect()
+    }
+
+    //LISTENERS_ENV 값 (예: "0.0.0.0:7878=5,6,7;0.0.0.0:8080=8,9,10")
+    pub fn to_env(&self) -> String {
+        self.groups
+            .iter()
+            .map(|group| {
+                let fds: Vec<String> = group
+                    .sockets
+                    .iter()
+                    .map(|socket| socket.as_raw_fd().to_string())
+                    .collect();
+                format!("{}={}", group.addr, fds.join(","))
+            })
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+}
+
+//LISTENERS_ENV 파싱 (잘못된 항목은 경고 후 무시)
+fn inherited_listeners() -> HashMap<Address, Vec<Listener>> {
+    let mut inherited: HashMap<Address, Vec<Listener>> = HashMap::new();
+
+    let Ok(value) = env::var(LISTENERS_ENV) else {
+        return inherited;
+    };
+
+    for entry in value.split(';').filter(|entry| !entry.is_empty()) {
+        let parsed = entry.rsplit_once('=').and_then(|(addr, fds)| {
+            let fds = fds
+                .split(',')
+                .map(|fd| fd.parse::<RawFd>().ok())
+                .collect::<Option<Vec<RawFd>>>()?;
+            Some((addr.parse::<Address>().ok()?, fds))
+        });
+
+        let Some((addr, fds)) = parsed else {
+            eprintln!("⚠️ Invalid {} entry \"{}\", ignoring", LISTENERS_ENV, entry);
+            continue;
+        };
+
+        for fd in fds {
+            //열려 있는 fd인지 확인 (없는 fd를 리스너로 감싸면 drop할 때 엉뚱한 fd를 닫음)
+            if unsafe { libc::fcntl(fd, F_GETFD) } < 0 {
+                eprintln!(
+                    "⚠️ Inherited listener {} (fd {}) is not open, ignoring",
+                    addr, fd
+                );
+                continue;
+            }
+
+            println!("🔗 Using inherited listener {} (fd {})", addr, fd);
+            let socket = match addr {
+                Address::Tcp(_) => Listener::Tcp(unsafe { TcpListener::from_raw_fd(fd) }),
+                Address::Unix(_) => Listener::Unix(unsafe { UnixListener::from_raw_fd(fd) }),
+            };
+            inherited.entry(addr.clone()).or_default().push(socket);
+        }
+    }
+
+    inherited
+}
+
+/*
+ reuseport steer=cpu: 연결을 받은 CPU 번호로 SO_REUSEPORT 그룹의 소켓을 고르는 CBPF 프로그램
+ - 프로그램: A = 현재 CPU 번호; A = A % 소켓 수; return A (리턴값이 그룹 안의 소켓 인덱스)
+ - 그룹의 소켓 하나에 붙이면 그룹 전체에 적용, 다시 붙이면 교체 (reload로 워커 수가 바뀐 경우)
+ - 워커 i는 CPU 번호 % 소켓 수 == i 인 CPU에 고정되므로 패킷 수신부터 응답까지 같은 CPU에서 처리
+ - steer=cpu가 아니면 붙어 있던 프로그램을 떼서 커널 기본 해시 분배로 되돌림
+*/
+#[cfg(target_os = "linux")]
+mod steering {
+    use std::{
+        io::{Error, Result},
+        net::SocketAddr,
+        os::fd::AsRawFd,
+    };
+
+    use libc::{
+        BPF_A, BPF_ABS, BPF_ALU, BPF_K, BPF_LD, BPF_MOD, BPF_RET, BPF_W, ENOENT, ENOPROTOOPT,
+        SKF_AD_CPU, SKF_AD_OFF, SO_ATTACH_REUSEPORT_CBPF, SOL_SOCKET, c_int, c_void, sock_filter,
+        sock_fprog, socklen_t,
+    };
+
+    use crate::{
+        config::{AcceptStrategy, Config},
+        server::socket::Listener,
+        utils::system::get_cpu_count,
+    };
+
+    //libc가 musl 타깃에서는 정의하지 않으므로 직접 정의 (sparc를 뺀 모든 아키텍처에서 68)
+    const SO_DETACH_REUSEPORT_BPF: c_int = 68;
+
+    pub fn apply(addr: &SocketAddr, sockets: &[Listener], config: &Config) -> Result<()> {
+        let steer_by_cpu = matches!(
+            config.events.accept_strategy,
+            AcceptStrategy::Reuseport { steer_by_cpu: true }
+        );
+
+        if !steer_by_cpu {
+            return detach(&sockets[0]);
+        }
+
+        let cpus = get_cpu_count();
+        if sockets.len() > cpus {
+            eprintln!(
+                "⚠️ {}: {} sockets but only {} CPUs, sockets {}..{} receive no connections with steer=cpu",
+                addr,
+                sockets.len(),
+                cpus,
+                cpus + 1,
+                sockets.len()
+            );
+        }
+
+        attach(&sockets[0], sockets.len() as u32).map_err(|e| {
+            Error::new(
+                e.kind(),
+                format!("attaching CPU steering program to {} failed ({})", addr, e),
+            )
+        })
+    }
+
+    fn attach(socket: &Listener, count: u32) -> Result<()> {
+        let mut program = [
+            sock_filter {
+                code: (BPF_LD | BPF_W | BPF_ABS) as u16,
+                jt: 0,
+                jf: 0,
+                k: (SKF_AD_OFF + SKF_AD_CPU) as u32,
+            },
+            sock_filter {
+                code: (BPF_ALU | BPF_MOD | BPF_K) as u16,
+                jt: 0,
+                jf: 0,
+                k: count,
+            },
+            sock_filter {
+                code: (BPF_RET | BPF_A) as u16,
+                jt: 0,
+                jf: 0,
+                k: 0,
+            },
+        ];
+        let fprog = sock_fprog {
+            len: program.len() as u16,
+            filter: program.as_mut_ptr(),
+        };
+
+        let ret = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                SOL_SOCKET,
+                SO_ATTACH_REUSEPORT_CBPF,
+                &fprog as *const sock_fprog as *const c_void,
+                size_of::<sock_fprog>() as socklen_t,
+            )
+        };
+
+        if ret < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    //붙어 있는 프로그램이 없으면 ENOENT, 5.3 이전 커널은 ENOPROTOOPT (둘 다 무시)
+    fn detach(socket: &Listener) -> Result<()> {
+        let optval: c_int = 0;
+        let ret = unsafe {
+            libc::setsockopt(
+                socket.as_raw_fd(),
+                SOL_SOCKET,
+                SO_DETACH_REUSEPORT_BPF,
+                &optval as *const c_int as *const c_void,
+                size_of::<c_int>() as socklen_t,
+            )
+        };
+
+        if ret < 0 {
+            let e = Error::last_os_error();
+            if !matches!(e.raw_os_error(), Some(ENOENT | ENOPROTOOPT)) {
+                return Err(e);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,27 +1,879 @@
-use std::{thread, time::Duration};
+use std::{
+    env, fmt, fs,
+    io::{Error, Result},
+    os::unix::process::CommandExt,
+    path::PathBuf,
+    process::{self, Command},
+    time::{Duration, Instant},
+};
 
 use libc::*;
 
-pub fn start_master_process() {
-    println!("마스터 프로세스 모니터링 시작");
+use crate::{
+    config::Config,
+    server::{
+        health::HealthTable,
+        listeners::{LISTENERS_ENV, Listeners},
+        signals::SignalPipe,
+        worker::start_worker_process,
+    },
+};
 
-    loop {
-        let mut status: c_int = 0;
-        let pid = unsafe { libc::waitpid(-1, &mut status, WNOHANG) };
+//마스터가 처리하는 신호 (워커는 fork 직후 기본 동작으로 되돌림)
+const HANDLED_SIGNALS: [c_int; 7] = [SIGTERM, SIGINT, SIGQUIT, SIGHUP, SIGUSR1, SIGUSR2, SIGCHLD];
 
-        if pid > 0 {
-            if unsafe { WIFEXITED(status) } {
-                let exit_code = unsafe { WEXITSTATUS(status) };
-                eprintln!("⚠️ Worker {} exited with status {}", pid, exit_code);
-            } else if unsafe { WIFSIGNALED(status) } {
-                let signal = unsafe { WTERMSIG(status) };
-                eprintln!("⚠️ Worker {} killed by signal {}", pid, signal);
+//바이너리 업그레이드로 실행된 새 마스터에 이전 마스터 pid를 알려주는 환경 변수
+const OLD_MASTER_ENV: &str = "RUSTIFY_OLD_MASTER";
+
+//이보다 빨리 죽으면 크래시 루프로 보고 재시작을 지연
+const MIN_UPTIME: Duration = Duration::from_secs(5);
+
+//재시작 지연: 100ms부터 연속 크래시마다 2배, 최대 30초
+const BACKOFF_BASE: Duration = Duration::from_millis(100);
+const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+//graceful 종료 시 worker_shutdown_timeout이 지나고도 남은 워커를 SIGKILL 하기 전 여유 시간
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
+//워커 슬롯 하나 (슬롯 id는 고정, 재시작하면 pid만 바뀜)
+#[derive(Debug, Clone)]
+pub struct WorkerSlot {
+    id: usize,
+    pid: Option<pid_t>,          //실행 중이면 Some
+    started_at: Option<Instant>, //마지막으로 fork한 시각
+    restarts: u32,               //지금까지 재시작한 횟수
+    crashes: u32,                //MIN_UPTIME 안에 연속으로 죽은 횟수
+    respawn_at: Option<Instant>, //재시작 예정 시각 (백오프 중)
+    last_exit: Option<ExitStatus>,
+}
+
+impl WorkerSlot {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn pid(&self) -> Option<pid_t> {
+        self.pid
+    }
+
+    pub fn restarts(&self) -> u32 {
+        self.restarts
+    }
+
+    pub fn uptime(&self) -> Option<Duration> {
+        self.pid?;
+        self.started_at.map(|started_at| started_at.elapsed())
+    }
+
+    pub fn last_exit(&self) -> Option<ExitStatus> {
+        self.last_exit
+    }
+
+    //연속 크래시 횟수에 따른 재시작 지연
+    fn backoff(&self) -> Duration {
+        if self.crashes == 0 {
+            return Duration::ZERO;
+        }
+        let shift = (self.crashes - 1).min(16);
+        (BACKOFF_BASE * (1 << shift)).min(BACKOFF_MAX)
+    }
+}
+
+//waitpid로 받은 워커 종료 상태
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    Exited(c_int),
+    Signaled(c_int),
+}
+
+impl fmt::Display for ExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExitStatus::Exited(code) => write!(f, "exit {}", code),
+            ExitStatus::Signaled(signal) => write!(f, "signal {}", signal),
+        }
+    }
+}
+
+//reload로 교체되어 graceful 종료 중인 이전 세대 워커
+#[derive(Debug, Clone, Copy)]
+struct Retiring {
+    pid: pid_t,
+    deadline: Instant, //이 시각까지 안 끝나면 SIGKILL
+}
+
+/*
+ 워커 테이블
+ - worker_processes 개수만큼 슬롯을 만들고 슬롯마다 현재 pid를 기록
+ - 워커가 죽으면 같은 슬롯 id로 다시 fork (연속으로 빨리 죽으면 지수 백오프)
+ - reload하면 세대(generation)를 올리고 새 슬롯을 만듦, 이전 세대 워커는 retiring으로 옮겨서 종료를 기다림
+*/
+#[derive(Debug)]
+pub struct WorkerTable {
+    generation: u64,
+    slots: Vec<WorkerSlot>,
+    retiring: Vec<Retiring>,
+}
+
+impl WorkerTable {
+    pub fn new(count: usize) -> WorkerTable {
+        WorkerTable {
+            generation: 1,
+            slots: Self::empty_slots(count),
+            retiring: Vec::new(),
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn slots(&self) -> &[WorkerSlot] {
+        &self.slots
+    }
+
+    pub fn running(&self) -> usize {
+        self.slots.iter().filter(|slot| slot.pid.is_some()).count()
+    }
+
+    pub fn retiring(&self) -> usize {
+        self.retiring.len()
+    }
+
+    //현재 세대와 이전 세대를 합친 살아 있는 워커 pid
+    fn pids(&self) -> Vec<pid_t> {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.pid)
+            .chain(self.retiring.iter().map(|retiring| retiring.pid))
+            .collect()
+    }
+
+    fn empty_slots(count: usize) -> Vec<WorkerSlot> {
+        (0..count)
+            .map(|id| WorkerSlot {
+                id,
+                pid: None,
+                started_at: None,
+                restarts: 0,
+                crashes: 0,
+                respawn_at: None,
+                last_exit: None,
+            })
+            .collect()
+    }
+
+    //다음 세대로 교체: 살아 있는 워커는 retiring으로 옮기고 그 pid를 리턴 (호출한 쪽이 SIGQUIT 전송)
+    fn roll(&mut self, count: usize, deadline: Instant) -> Vec<pid_t> {
+        let old: Vec<pid_t> = self.slots.iter().filter_map(|slot| slot.pid).collect();
+        self.retiring
+            .extend(old.iter().map(|&pid| Retiring { pid, deadline }));
+
+        self.generation += 1;
+        self.slots = Self::empty_slots(count);
+        old
+    }
+
+    //슬롯 id로 fork한 워커 기록
+    fn on_spawn(&mut self, id: usize, pid: pid_t, now: Instant) {
+        let slot = &mut self.slots[id];
+        if slot.started_at.is_some() {
+            slot.restarts += 1;
+        }
+        slot.pid = Some(pid);
+        slot.started_at = Some(now);
+        slot.respawn_at = None;
+    }
+
+    //fork 실패: 크래시와 같이 백오프 후 다시 시도
+    fn on_spawn_failed(&mut self, id: usize, now: Instant) {
+        let slot = &mut self.slots[id];
+        slot.crashes += 1;
+        slot.respawn_at = Some(now + slot.backoff());
+    }
+
+    //종료된 워커를 슬롯에서 지우고 재시작 시각을 정함, 해당 슬롯 id 리턴
+    fn on_exit(&mut self, pid: pid_t, status: ExitStatus, now: Instant) -> Option<usize> {
+        let slot = self.slots.iter_mut().find(|slot| slot.pid == Some(pid))?;
+
+        let quick = slot
+            .started_at
+            .is_some_and(|started_at| now.duration_since(started_at) < MIN_UPTIME);
+        slot.crashes = if quick { slot.crashes + 1 } else { 0 };
+
+        slot.pid = None;
+        slot.last_exit = Some(status);
+        slot.respawn_at = Some(now + slot.backoff());
+
+        Some(slot.id)
+    }
+
+    //이전 세대 워커였으면 목록에서 지우고 true
+    fn on_retired(&mut self, pid: pid_t) -> bool {
+        let before = self.retiring.len();
+        self.retiring.retain(|retiring| retiring.pid != pid);
+        self.retiring.len() != before
+    }
+
+    //다음 할 일(재시작, 이전 세대 종료 기한) 중 가장 이른 시각 (대기 시간 계산용)
+    fn next_deadline(&self) -> Option<Instant> {
+        let respawn = self.slots.iter().filter_map(|slot| slot.respawn_at);
+        let retiring = self.retiring.iter().map(|retiring| retiring.deadline);
+        respawn.chain(retiring).min()
+    }
+
+    //종료 중에는 재시작하지 않음
+    fn cancel_respawns(&mut self) {
+        for slot in &mut self.slots {
+            slot.respawn_at = None;
+        }
+    }
+}
+
+//워커 테이블 출력 (시작할 때, 워커가 바뀔 때)
+impl fmt::Display for WorkerTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "📋 Workers generation {} ({}/{} running",
+            self.generation(),
+            self.running(),
+            self.slots.len()
+        )?;
+        if self.retiring() > 0 {
+            write!(f, ", {} retiring", self.retiring())?;
+        }
+        writeln!(f, ")")?;
+        writeln!(f, "   SLOT   PID      UPTIME         RESTARTS  LAST EXIT")?;
+
+        for slot in &self.slots {
+            let pid = slot
+                .pid()
+                .map(|pid| pid.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            let uptime = match (slot.uptime(), slot.respawn_at) {
+                (Some(uptime), _) => format!("{}s", uptime.as_secs()),
+                (None, Some(_)) => format!("backoff {}ms", slot.backoff().as_millis()),
+                (None, None) => "-".to_string(),
+            };
+            let last_exit = slot
+                .last_exit()
+                .map(|status| status.to_string())
+                .unwrap_or_else(|| "-".to_string());
+
+            writeln!(
+                f,
+                "   {:<6} {:<8} {:<14} {:<9} {}",
+                slot.id() + 1,
+                pid,
+                uptime,
+                slot.restarts(),
+                last_exit
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/*
+ 마스터 프로세스
+ - listen 소켓을 직접 만들어 소유하고, worker_processes 개수만큼 워커를 fork해서 물려줌 (워커 테이블로 관리)
+ - 워커가 종료되거나 죽으면 같은 슬롯으로 다시 fork (크래시 루프면 백오프)
+ - 신호는 self-pipe로 받아서 루프에서 도착 순서대로 처리
+   - stop(SIGTERM/SIGINT): 워커에 SIGTERM을 보내 즉시 종료시키고 pid 파일을 지운 뒤 종료
+   - quit(SIGQUIT): 워커에 SIGQUIT을 보내 처리 중인 연결을 마무리하게 하고, 모두 끝나면 종료
+   - reload(SIGHUP): 설정을 다시 읽어서 새 세대 워커를 띄우고 이전 세대는 graceful 종료
+     (설정에 에러가 있으면 기존 워커를 그대로 유지)
+   - upgrade(SIGUSR2): 새 바이너리를 실행해서 리스너를 넘기고, 새 마스터가 준비되면 이 마스터는 graceful 종료
+   - SIGUSR1: 로그를 표준 출력/에러로만 쓰므로 다시 열 파일이 없음, 무시 (기본 동작인 종료를 막음)
+ - reload: 설정 파일을 다시 읽는 함수 (main에서 명령행 옵션을 적용해서 넘김)
+*/
+pub fn start_master_process<F>(config: Config, reload: F) -> Result<()>
+where
+    F: Fn() -> std::result::Result<Config, String>,
+{
+    let listeners = Listeners::open(&config)?;
+    let health = HealthTable::new(&config.http)?;
+    let signals = SignalPipe::install(&HANDLED_SIGNALS)?;
+
+    let mut master = Master {
+        table: WorkerTable::new(config.worker_processes),
+        pid_path: config.pid.clone(),
+        config,
+        reload,
+        listeners,
+        health,
+        signals,
+        quit_deadline: None,
+        upgrade: None,
+    };
+
+    for id in 0..master.config.worker_processes {
+        master.spawn(id)?;
+    }
+
+    master.retire_old_master();
+
+    println!(
+        "마스터 프로세스 모니터링 시작 (workers: {}, listen: {})",
+        master.config.worker_processes,
+        master.listen_addrs()
+    );
+    print!("{}", master.table);
+
+    master.run()
+}
+
+struct Master<F> {
+    config: Config,
+    reload: F,
+    listeners: Listeners,
+    health: HealthTable, //현재 세대 워커들이 공유하는 업스트림 서버 상태
+    signals: SignalPipe,
+    table: WorkerTable,
+    pid_path: PathBuf, //이 마스터의 pid 파일 (업그레이드 중이면 .oldbin)
+    quit_deadline: Option<Instant>, //graceful 종료 중이면 남은 워커를 SIGKILL 할 시각
+    upgrade: Option<pid_t>, //업그레이드로 실행한 새 마스터
+}
+
+impl<F> Master<F>
+where
+    F: Fn() -> std::result::Result<Config, String>,
+{
+    fn run(&mut self) -> Result<()> {
+        loop {
+            //다음 할 일까지 최대 1초 대기, 신호(SIGCHLD 포함)가 오면 바로 깸
+            let now = Instant::now();
+            let timeout = self
+                .table
+                .next_deadline()
+                .into_iter()
+                .chain(self.quit_deadline)
+                .min()
+                .map(|at| at.saturating_duration_since(now))
+                .unwrap_or(Duration::from_secs(1))
+                .min(Duration::from_secs(1));
+            let signals = self.signals.wait(timeout);
+
+            //SIGCHLD는 여러 개가 하나로 합쳐질 수 있으므로 매번 모두 수거
+            let mut changed = self.reap();
+
+            for signal in signals {
+                match signal {
+                    SIGCHLD => {}
+                    SIGTERM | SIGINT => {
+                        println!("🛑 Master shutting down");
+                        self.stop_workers();
+                        self.cleanup();
+                        return Ok(());
+                    }
+                    SIGQUIT => {
+                        if self.quit_deadline.is_none() {
+                            println!("🛑 Master shutting down gracefully");
+                            self.quit_deadline = Some(
+                                Instant::now()
+                                    + self.config.worker_shutdown_timeout
+                                    + SHUTDOWN_GRACE,
+                            );
+                            self.table.cancel_respawns();
+                            self.signal_all(SIGQUIT);
+                        }
+                    }
+                    SIGHUP => {
+                        if self.quit_deadline.is_none() {
+                            changed |= self.reload_config();
+                        }
+                    }
+                    SIGUSR2 => {
+                        if self.quit_deadline.is_none() {
+                            self.upgrade_binary();
+                        }
+                    }
+                    signal => {
+                        eprintln!("⚠️ Signal {} is not supported yet, ignoring", signal);
+                    }
+                }
             }
 
-            //워커 재시작
+            if let Some(deadline) = self.quit_deadline {
+                if self.table.pids().is_empty() {
+                    println!("🛑 All workers exited, master exiting");
+                    self.cleanup();
+                    return Ok(());
+                }
+                if Instant::now() >= deadline {
+                    eprintln!(
+                        "⚠️ {} workers did not exit in time, killing",
+                        self.table.pids().len()
+                    );
+                    self.signal_all(SIGKILL);
+                    self.wait_all();
+                    self.cleanup();
+                    return Ok(());
+                }
+            }
+
+            changed |= self.kill_overdue_retiring();
+            changed |= self.respawn_due();
+
+            if changed {
+                print!("{}", self.table);
+            }
         }
+    }
+
+    //종료된 워커 모두 수거, 테이블이 바뀌었으면 true
+    fn reap(&mut self) -> bool {
+        let now = Instant::now();
+        let mut changed = false;
+
+        loop {
+            let mut status: c_int = 0;
+            let pid = unsafe { libc::waitpid(-1, &mut status, WNOHANG) };
+            if pid <= 0 {
+                break;
+            }
+
+            let status = if WIFSIGNALED(status) {
+                ExitStatus::Signaled(WTERMSIG(status))
+            } else {
+                ExitStatus::Exited(WEXITSTATUS(status))
+            };
+
+            if self.upgrade == Some(pid) {
+                self.on_upgrade_failed(status);
+                continue;
+            }
+
+            if self.table.on_retired(pid) {
+                println!("👋 Old worker (PID: {}) exited with {}", pid, status);
+                changed = true;
+                continue;
+            }
+
+            if let Some(id) = self.table.on_exit(pid, status, now) {
+                if self.quit_deadline.is_some() {
+                    println!("👋 Worker {} (PID: {}) exited with {}", id + 1, pid, status);
+                    self.table.slots[id].respawn_at = None;
+                } else {
+                    eprintln!(
+                        "⚠️ Worker {} (PID: {}) exited with {}, respawning in {}ms",
+                        id + 1,
+                        pid,
+                        status,
+                        self.table.slots[id].backoff().as_millis()
+                    );
+                }
+                changed = true;
+            }
+        }
+
+        changed
+    }
+
+    //재시작 시각이 된 슬롯 다시 fork
+    fn respawn_due(&mut self) -> bool {
+        let now = Instant::now();
+        let due: Vec<usize> = self
+            .table
+            .slots()
+            .iter()
+            .filter(|slot| slot.respawn_at.is_some_and(|at| at <= now))
+            .map(|slot| slot.id())
+            .collect();
+
+        for &id in &due {
+            if let Err(e) = self.spawn(id) {
+                eprintln!("❌ Respawn of worker {} failed: {}", id + 1, e);
+                self.table.on_spawn_failed(id, Instant::now());
+            }
+        }
+
+        !due.is_empty()
+    }
+
+    /*
+     SIGHUP: 설정 다시 읽기
+     - 새 설정으로 새 세대 워커를 먼저 띄운 뒤 이전 세대에 SIGQUIT
+       (새 연결은 새 워커가 받고, 이전 워커는 처리 중인 연결만 마무리)
+     - 설정에 에러가 있으면 기존 워커를 그대로 유지
+    */
+    fn reload_config(&mut self) -> bool {
+        println!("🔄 Reloading configuration");
+
+        let config = match (self.reload)() {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                eprintln!("❌ Reload failed, keeping current configuration");
+                return false;
+            }
+        };
+
+        //새 주소만 bind (실패하면 기존 리스너와 워커를 그대로 유지)
+        let listeners = match self.listeners.reopen(&config) {
+            Ok(listeners) => listeners,
+            Err(e) => {
+                eprintln!("❌ {}", e);
+                eprintln!("❌ Reload failed, keeping current configuration");
+                return false;
+            }
+        };
+
+        //새 세대는 업스트림 서버 상태를 새로 시작 (upstream 구성이 바뀌었을 수 있음)
+        let health = match HealthTable::new(&config.http) {
+            Ok(health) => health,
+            Err(e) => {
+                eprintln!("❌ Failed to create upstream health table: {}", e);
+                eprintln!("❌ Reload failed, keeping current configuration");
+                return false;
+            }
+        };
+
+        let deadline = Instant::now() + self.config.worker_shutdown_timeout + SHUTDOWN_GRACE;
+        let old = self.table.roll(config.worker_processes, deadline);
+        self.config = config;
+        self.health = health;
+        let closed = std::mem::replace(&mut self.listeners, listeners);
+        closed.unlink(Some(&self.listeners));
+
+        for id in 0..self.config.worker_processes {
+            if let Err(e) = self.spawn(id) {
+                eprintln!("❌ Spawn of worker {} failed: {}", id + 1, e);
+                self.table.on_spawn_failed(id, Instant::now());
+            }
+        }
+
+        for pid in old {
+            unsafe { libc::kill(pid, SIGQUIT) };
+        }
+
+        println!(
+            "🔄 Generation {} started (workers: {}, listen: {})",
+            self.table.generation(),
+            self.config.worker_processes,
+            self.listen_addrs()
+        );
+        true
+    }
+
+    /*
+     SIGUSR2: 바이너리 업그레이드
+     - pid 파일을 .oldbin으로 바꾸고, 같은 명령행으로 새 바이너리를 실행 (리스너 fd는 LISTENERS_ENV로 넘김)
+     - 새 마스터는 물려받은 소켓으로 워커를 띄운 뒤 이 마스터에 SIGQUIT을 보냄
+       → 두 세대가 같은 소켓에서 함께 accept하다가 이전 세대 워커가 처리 중인 연결을 마치고 종료
+     - 새 마스터가 바로 죽으면 pid 파일을 되돌리고 계속 실행
+    */
+    fn upgrade_binary(&mut self) {
+        if let Some(pid) = self.upgrade {
+            eprintln!(
+                "⚠️ Binary upgrade already in progress (new master PID: {}), ignoring",
+                pid
+            );
+            return;
+        }
+
+        let oldbin = oldbin_path(&self.pid_path);
+        if let Err(e) = fs::rename(&self.pid_path, &oldbin) {
+            eprintln!(
+                "❌ rename() \"{}\" to \"{}\" failed ({}), binary upgrade canceled",
+                self.pid_path.display(),
+                oldbin.display(),
+                e
+            );
+            return;
+        }
+
+        //바이너리가 교체되었을 수 있으므로 /proc/self/exe가 아니라 실행할 때의 경로(argv[0])로 실행
+        let mut args = env::args_os();
+        let program = args.next().unwrap_or_else(|| "rustify".into());
+
+        let mut command = Command::new(&program);
+        command
+            .args(args)
+            .env(LISTENERS_ENV, self.listeners.to_env())
+            .env(OLD_MASTER_ENV, process::id().to_string());
+
+        //리스너 fd가 exec 후에도 열려 있도록 자식에서만 FD_CLOEXEC 해제
+        let fds = self.listeners.raw_fds();
+        unsafe {
+            command.pre_exec(move || {
+                for &fd in &fds {
+                    if libc::fcntl(fd, F_SETFD, 0) < 0 {
+                        return Err(Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+
+        match command.spawn() {
+            Ok(child) => {
+                let pid = child.id() as pid_t;
+                println!("🔄 Binary upgrade: started new master (PID: {})", pid);
+                self.upgrade = Some(pid);
+                self.pid_path = oldbin;
+            }
+            Err(e) => {
+                eprintln!("❌ Binary upgrade: failed to execute {:?} ({})", program, e);
+                let _ = fs::rename(&oldbin, &self.pid_path);
+            }
+        }
+    }
+
+    //업그레이드로 실행한 새 마스터가 종료됨: pid 파일을 되돌리고 이 마스터가 계속 서비스
+    fn on_upgrade_failed(&mut self, status: ExitStatus) {
+        eprintln!(
+            "❌ New master (PID: {}) exited with {}, binary upgrade failed",
+            self.upgrade.unwrap_or_default(),
+            status
+        );
+        self.upgrade = None;
+
+        let pid_path = self.config.pid.clone();
+        if fs::rename(&self.pid_path, &pid_path).is_ok() {
+            self.pid_path = pid_path;
+        }
+    }
+
+    //업그레이드로 실행된 새 마스터: 워커를 띄웠으면 이전 마스터에 graceful 종료 요청
+    fn retire_old_master(&self) {
+        let Some(old) = env::var(OLD_MASTER_ENV)
+            .ok()
+            .and_then(|pid| pid.parse::<pid_t>().ok())
+        else {
+            return;
+        };
+
+        //이전 마스터가 이미 없으면(부모가 바뀜) 엉뚱한 프로세스에 신호를 보내지 않음
+        if unsafe { getppid() } != old {
+            return;
+        }
+
+        println!(
+            "🔄 Binary upgrade: new master ready, asking old master (PID: {}) to quit",
+            old
+        );
+        unsafe { libc::kill(old, SIGQUIT) };
+    }
+
+    //기한 안에 끝나지 않은 이전 세대 워커 강제 종료 (수거는 reap에서)
+    fn kill_overdue_retiring(&mut self) -> bool {
+        let now = Instant::now();
+        let mut killed = false;
+
+        for retiring in &mut self.table.retiring {
+            if retiring.deadline <= now {
+                eprintln!(
+                    "⚠️ Old worker (PID: {}) did not exit in time, killing",
+                    retiring.pid
+                );
+                unsafe { libc::kill(retiring.pid, SIGKILL) };
+                //수거될 때까지 다시 죽이지 않도록 기한을 미룸
+                retiring.deadline = now + BACKOFF_MAX;
+                killed = true;
+            }
+        }
+
+        killed
+    }
+
+    /*
+     슬롯 id로 워커 fork
+     - 자식: 마스터의 시그널 핸들러를 기본 동작으로 되돌리고 워커 이벤트 루프 실행 (리턴하지 않음)
+     - 부모: 슬롯에 pid 기록
+    */
+    fn spawn(&mut self, id: usize) -> Result<()> {
+        match unsafe { libc::fork() } {
+            //자식 프로세스(Worker)
+            0 => {
+                self.signals.reset_in_child();
+
+                let parent_pid = unsafe { getppid() };
+
+                //워커들 무한루프로 계속 실행(블로킹), 에러가 나면 종료해서 마스터가 재시작하게 함
+                let code = match self.listeners.worker_sockets(id).and_then(|sockets| {
+                    start_worker_process(id, parent_pid, &self.config, &self.health, sockets)
+                }) {
+                    Ok(()) => 0,
+                    Err(e) => {
+                        eprintln!("❌ Worker {} failed: {}", id + 1, e);
+                        1
+                    }
+                };
+                process::exit(code);
+            }
+            pid if pid > 0 => {
+                //부모 프로세스(Master)
+                self.table.on_spawn(id, pid, Instant::now());
+                Ok(())
+            }
+            _ => {
+                eprintln!("❌ Fork failed");
+                Err(Error::last_os_error())
+            }
+        }
+    }
+
+    fn signal_all(&self, signal: c_int) {
+        for pid in self.table.pids() {
+            unsafe { libc::kill(pid, signal) };
+        }
+    }
+
+    //워커에 SIGTERM을 보내고 모두 종료될 때까지 대기
+    fn stop_workers(&self) {
+        self.signal_all(SIGTERM);
+        self.wait_all();
+    }
+
+    fn wait_all(&self) {
+        for pid in self.table.pids() {
+            let mut status: c_int = 0;
+            unsafe { libc::waitpid(pid, &mut status, 0) };
+        }
+    }
+
+    //종료 직전: pid 파일과 Unix 소켓 파일 삭제 (업그레이드로 넘긴 소켓은 새 마스터가 계속 사용)
+    fn cleanup(&self) {
+        let _ = fs::remove_file(&self.pid_path);
+        if self.upgrade.is_none() {
+            self.listeners.unlink(None);
+        }
+    }
+
+    fn listen_addrs(&self) -> String {
+        self.config
+            .listeners
+            .iter()
+            .map(|listen| listen.addr.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+//업그레이드 중 이전 마스터의 pid 파일 (nginx와 같은 .oldbin)
+fn oldbin_path(pid_path: &std::path::Path) -> PathBuf {
+    let mut path = pid_path.as_os_str().to_owned();
+    path.push(".oldbin");
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIGKILL_STATUS: ExitStatus = ExitStatus::Signaled(SIGKILL);
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn doubles_backoff_up_to_limit() {
+        let mut table = WorkerTable::new(1);
+        let slot = &mut table.slots[0];
+
+        let expected = [0, 100, 200, 400, 800, 1600, 3200, 6400, 12800, 25600, 30000];
+        for (crashes, millis) in expected.into_iter().enumerate() {
+            slot.crashes = crashes as u32;
+            assert_eq!(slot.backoff(), ms(millis), "{}", crashes);
+        }
+
+        //큰 값에서도 넘치지 않음
+        slot.crashes = u32::MAX;
+        assert_eq!(slot.backoff(), BACKOFF_MAX);
+    }
+
+    #[test]
+    fn delays_respawn_of_crash_loop() {
+        let start = Instant::now();
+        let mut table = WorkerTable::new(2);
+        table.on_spawn(0, 100, start);
+        table.on_spawn(1, 101, start);
+        assert_eq!(table.running(), 2);
+        assert_eq!(table.next_deadline(), None);
+
+        //MIN_UPTIME 안에 연속으로 죽을 때마다 2배
+        let mut now = start;
+        for (pid, next, backoff) in [(100, 102, 100), (102, 103, 200), (103, 104, 400)] {
+            now += ms(10);
+            assert_eq!(table.on_exit(pid, SIGKILL_STATUS, now), Some(0));
+            let slot = &table.slots[0];
+            assert_eq!(slot.pid(), None);
+            assert_eq!(slot.last_exit(), Some(SIGKILL_STATUS));
+            assert_eq!(slot.respawn_at, Some(now + ms(backoff)));
+            assert_eq!(table.next_deadline(), slot.respawn_at);
+            assert_eq!(table.running(), 1);
+
+            now += ms(backoff);
+            table.on_spawn(0, next, now);
+        }
+        assert_eq!(table.slots[0].restarts(), 3);
+        assert_eq!(table.slots[0].respawn_at, None);
+
+        //MIN_UPTIME 넘게 돌다가 죽으면 바로 재시작하고 횟수도 처음부터
+        now += MIN_UPTIME;
+        assert_eq!(table.on_exit(104, ExitStatus::Exited(1), now), Some(0));
+        assert_eq!(table.slots[0].respawn_at, Some(now));
+        now += ms(10);
+        table.on_spawn(0, 105, now);
+        assert_eq!(table.on_exit(105, SIGKILL_STATUS, now + ms(10)), Some(0));
+        assert_eq!(table.slots[0].backoff(), ms(100));
+
+        //다른 슬롯과 모르는 pid는 그대로
+        assert_eq!(table.slots[1].pid(), Some(101));
+        assert_eq!(table.slots[1].restarts(), 0);
+        assert_eq!(table.on_exit(999, SIGKILL_STATUS, now), None);
+    }
+
+    #[test]
+    fn backs_off_failed_spawns() {
+        let now = Instant::now();
+        let mut table = WorkerTable::new(1);
+
+        table.on_spawn_failed(0, now);
+        assert_eq!(table.slots[0].respawn_at, Some(now + ms(100)));
+        table.on_spawn_failed(0, now);
+        assert_eq!(table.slots[0].respawn_at, Some(now + ms(200)));
+
+        //처음 띄운 것이므로 재시작 횟수는 그대로
+        table.on_spawn(0, 100, now);
+        assert_eq!(table.slots[0].restarts(), 0);
+        assert_eq!(table.slots[0].respawn_at, None);
+    }
+
+    #[test]
+    fn retires_previous_generation() {
+        let now = Instant::now();
+        let deadline = now + Duration::from_secs(10);
+        let mut table = WorkerTable::new(2);
+        table.on_spawn(0, 100, now);
+        table.on_spawn(1, 101, now);
+
+        let old = table.roll(3, deadline);
+        assert_eq!(old, [100, 101]);
+        assert_eq!(table.generation(), 2);
+        assert_eq!(table.slots().len(), 3);
+        assert_eq!(table.running(), 0);
+        assert_eq!(table.retiring(), 2);
+        assert_eq!(table.next_deadline(), Some(deadline));
+
+        //새 슬롯은 id와 기록이 처음부터
+        table.on_spawn(0, 200, now);
+        assert_eq!(table.slots[0].id(), 0);
+        assert_eq!(table.slots[0].restarts(), 0);
+        assert_eq!(table.pids(), [200, 100, 101]);
+
+        //이전 세대 워커는 슬롯이 아니라 retiring에서 지움
+        assert_eq!(table.on_exit(100, SIGKILL_STATUS, now), None);
+        assert!(table.on_retired(100));
+        assert!(!table.on_retired(100));
+        assert_eq!(table.retiring(), 1);
 
-        //CPU 과사용 방지
-        thread::sleep(Duration::from_secs(1));
+        assert_eq!(table.on_exit(200, SIGKILL_STATUS, now), Some(0));
+        table.cancel_respawns();
+        assert_eq!(table.next_deadline(), Some(deadline));
     }
 }
@@ -1,3 +1,18 @@
+pub mod balancer;
+pub mod connection;
+pub mod file_cache;
+pub mod health;
+pub mod listeners;
 pub mod master;
+pub mod notify;
+pub mod proxy_body;
 pub mod reverse_proxy;
+pub mod router;
+pub mod signals;
+pub mod socket;
+pub mod static_files;
+pub mod upstream;
+pub mod uri;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+pub mod uring;
 pub mod worker;
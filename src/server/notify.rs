@@ -0,0 +1,102 @@
+use std::{
+    io::{ErrorKind, Read, Result, Write},
+    mem,
+    os::{
+        fd::{AsRawFd, RawFd},
+        unix::net::UnixStream,
+    },
+    sync::{Arc, Mutex},
+};
+
+use Rustify::Token;
+
+//이벤트 루프가 알림 소켓을 등록할 때 쓰는 토큰 (리스너/연결 토큰과 겹치지 않음, io_uring user_data에 들어가는 62비트 안)
+pub const NOTIFY_TOKEN: Token = Token(u64::MAX >> 2);
+
+struct Shared {
+    tokens: Mutex<Vec<Token>>, //깨워야 할 연결 토큰
+    writer: UnixStream,
+}
+
+/*
+ 다른 스레드(업스트림 작업)가 워커 이벤트 루프를 깨우는 채널
+ - 소켓 쌍의 읽기 쪽을 Poller/io_uring에 등록하고, 작업 스레드는 연결 토큰을 큐에 넣은 뒤 1바이트를 씀
+ - 큐가 비어 있을 때만 쓰므로 이벤트 루프가 한 번 깨어날 때까지 알림 여러 개가 바이트 하나로 합쳐짐
+*/
+#[derive(Clone)]
+pub struct Notifier {
+    shared: Arc<Shared>,
+}
+
+//이벤트 루프 쪽 (읽기 소켓)
+pub struct Notifications {
+    shared: Arc<Shared>,
+    reader: UnixStream,
+}
+
+pub fn channel() -> Result<(Notifier, Notifications)> {
+    let (reader, writer) = UnixStream::pair()?;
+    reader.set_nonblocking(true)?;
+    writer.set_nonblocking(true)?;
+
+    let shared = Arc::new(Shared {
+        tokens: Mutex::new(Vec::new()),
+        writer,
+    });
+
+    Ok((
+        Notifier {
+            shared: shared.clone(),
+        },
+        Notifications { shared, reader },
+    ))
+}
+
+impl Notifier {
+    //token 연결을 다음 루프에서 처리하도록 깨움
+    pub fn notify(&self, token: Token) {
+        let mut tokens = self.shared.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        let wake = tokens.is_empty();
+        tokens.push(token);
+        drop(tokens);
+
+        //소켓 버퍼가 가득 차서 실패해도 이미 깨울 바이트가 쌓여 있으므로 무시
+        if wake {
+            let _ = (&self.shared.writer).write(&[1]);
+        }
+    }
+}
+
+impl Notifications {
+    //깨우기 바이트를 비우고 그동안 쌓인 연결 토큰을 중복 없이 꺼냄
+    pub fn drain(&self) -> Vec<Token> {
+        let mut buf = [0u8; 64];
+        loop {
+            match (&self.reader).read(&mut buf) {
+                Ok(n) if n == buf.len() => continue,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                _ => break,
+            }
+        }
+
+        self.take()
+    }
+
+    /*
+     쌓인 연결 토큰만 꺼냄 (io_uring처럼 깨우기 바이트를 커널이 이미 읽어 준 경우)
+     - 바이트를 먼저 읽고 큐를 비우므로, 그 사이에 들어온 알림은 다음 바이트로 다시 깨어남
+    */
+    pub fn take(&self) -> Vec<Token> {
+        let mut tokens =
+            mem::take(&mut *self.shared.tokens.lock().unwrap_or_else(|e| e.into_inner()));
+        tokens.sort_unstable();
+        tokens.dedup();
+        tokens
+    }
+}
+
+impl AsRawFd for Notifications {
+    fn as_raw_fd(&self) -> RawFd {
+        self.reader.as_raw_fd()
+    }
+}
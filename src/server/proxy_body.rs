@@ -0,0 +1,490 @@
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{Error, ErrorKind, Result},
+    os::unix::fs::FileExt,
+    path::{Path, PathBuf},
+    pin::Pin,
+    process,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    time::Instant,
+};
+
+use Rustify::Token;
+
+use crate::{config::ProxyBuffering, server::notify::Notifier};
+
+//버퍼링한 요청 바디를 업스트림 연결에 한 번에 넘기는 최대 크기
+const SEND_CHUNK: usize = 64 * 1024;
+
+//흘려보내는 요청 바디 조각을 쌓아 둘 수 있는 최대 개수 (가득 차면 클라이언트 읽기를 멈춤)
+const UPLOAD_CHANNEL_CAPACITY: usize = 8;
+
+//요청 바디를 넘기지 않는 동안(응답 대기 등) 멈춤 검사를 다시 하는 간격
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+//임시 파일 이름이 겹치지 않도록 붙이는 번호
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/*
+ 요청 바디 전송 진행 상황 (proxy_send_timeout)
+ - 업스트림 연결이 바디 조각을 가져갈 때마다 시각을 기록하고, 다음 조각을 가져가지 않은 채 timeout이 지나면 멈춘 것
+ - 클라이언트에서 다음 조각을 기다리는 중이거나 마지막 조각을 넘긴 뒤에는 세지 않음
+*/
+pub struct SendProgress {
+    started: Instant,
+    last: AtomicU64, //마지막으로 조각을 넘긴 시각 (started 기준 ms + 1, 0이면 세지 않음)
+}
+
+impl SendProgress {
+    fn touch(&self) {
+        let elapsed = self.started.elapsed().as_millis() as u64;
+        self.last.store(elapsed + 1, Ordering::Relaxed);
+    }
+
+    fn idle(&self) {
+        self.last.store(0, Ordering::Relaxed);
+    }
+
+    //업스트림이 바디를 timeout 동안 가져가지 않으면 끝남
+    pub async fn stalled(&self, timeout: Duration) {
+        loop {
+            let last = self.last.load(Ordering::Relaxed);
+            if last == 0 {
+                tokio::time::sleep(timeout.min(IDLE_CHECK_INTERVAL)).await;
+                continue;
+            }
+
+            let deadline = self.started + Duration::from_millis(last - 1) + timeout;
+            if Instant::now() >= deadline {
+                return;
+            }
+            tokio::time::sleep_until(deadline).await;
+        }
+    }
+}
+
+/*
+ 업스트림에 보내는 요청 바디 (reqwest 바디 스트림)
+ - Buffered: 클라이언트에게 다 받은 바디를 SEND_CHUNK씩 나눠서 넘김
+ - Streamed: 연결이 받는 대로 채널에 넣어 주는 조각 (proxy_request_buffering off)
+   연결이 finish() 없이 채널을 닫으면 바디가 잘린 것이므로 에러 (업스트림 요청도 실패)
+*/
+pub struct RequestBody {
+    source: Source,
+    progress: Arc<SendProgress>,
+}
+
+enum Source {
+    Buffered(Bytes),
+    Streamed {
+        receiver: mpsc::Receiver<Bytes>,
+        shared: Arc<UploadShared>,
+    },
+}
+
+impl RequestBody {
+    pub fn buffered(body: Vec<u8>) -> RequestBody {
+        RequestBody::new(Source::Buffered(Bytes::from(body)))
+    }
+
+    fn new(source: Source) -> RequestBody {
+        RequestBody {
+            source,
+            progress: Arc::new(SendProgress {
+                started: Instant::now(),
+                last: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    //바디 길이 (흘려보내는 바디는 모름)
+    pub fn len(&self) -> Option<usize> {
+        match &self.source {
+            Source::Buffered(body) => Some(body.len()),
+            Source::Streamed { .. } => None,
+        }
+    }
+
+    pub fn progress(&self) -> Arc<SendProgress> {
+        self.progress.clone()
+    }
+}
+
+impl Stream for RequestBody {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        match &mut this.source {
+            Source::Buffered(body) => {
+                if body.is_empty() {
+                    this.progress.idle();
+                    return Poll::Ready(None);
+                }
+
+                let chunk = body.split_to(body.len().min(SEND_CHUNK));
+                if body.is_empty() {
+                    this.progress.idle();
+                } else {
+                    this.progress.touch();
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Source::Streamed { receiver, shared } => match receiver.poll_recv(cx) {
+                Poll::Ready(Some(chunk)) => {
+                    this.progress.touch();
+                    shared.wake();
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+                Poll::Ready(None) => {
+                    this.progress.idle();
+                    if shared.finished.load(Ordering::Acquire) {
+                        Poll::Ready(None)
+                    } else {
+                        Poll::Ready(Some(Err(Error::new(
+                            ErrorKind::UnexpectedEof,
+                            "client request body was cut off",
+                        ))))
+                    }
+                }
+                Poll::Pending => {
+                    this.progress.idle();
+                    Poll::Pending
+                }
+            },
+        }
+    }
+}
+
+//연결과 업스트림 작업이 함께 보는 업로드 상태
+struct UploadShared {
+    finished: AtomicBool, //연결이 바디를 끝까지 넘김
+    blocked: AtomicBool,  //채널이 가득 차서 연결이 읽기를 멈춤 (조각을 가져가면 깨움)
+    notifier: Notifier,
+    token: Token,
+}
+
+impl UploadShared {
+    fn wake(&self) {
+        if self.blocked.swap(false, Ordering::Relaxed) {
+            self.notifier.notify(self.token);
+        }
+    }
+}
+
+/*
+ 클라이언트 요청 바디를 받는 대로 업스트림에 흘려보내는 연결 쪽 (proxy_request_buffering off)
+ - 채널이 가득 차면 조각 하나를 들고 멈추고, 업스트림이 조각을 가져가면 notifier로 연결을 깨움
+ - 업스트림 요청이 끝나서(응답, 실패) 채널이 닫히면 나머지 조각은 버림
+*/
+pub struct Upload {
+    sender: mpsc::Sender<Bytes>,
+    shared: Arc<UploadShared>,
+    pending: Option<Bytes>, //채널이 가득 차서 아직 넣지 못한 조각
+}
+
+//token 연결이 쓰는 업로드 채널 (업스트림 쪽 바디는 RequestBody로)
+pub fn upload(notifier: Notifier, token: Token) -> (Upload, RequestBody) {
+    let (sender, receiver) = mpsc::channel(UPLOAD_CHANNEL_CAPACITY);
+    let shared = Arc::new(UploadShared {
+        finished: AtomicBool::new(false),
+        blocked: AtomicBool::new(false),
+        notifier,
+        token,
+    });
+
+    (
+        Upload {
+            sender,
+            shared: shared.clone(),
+            pending: None,
+        },
+        RequestBody::new(Source::Streamed { receiver, shared }),
+    )
+}
+
+impl Upload {
+    //보낼 조각 (앞 조각을 flush()로 다 넣은 뒤에만)
+    pub fn push(&mut self, chunk: Vec<u8>) {
+        debug_assert!(self.pending.is_none());
+        self.pending = Some(Bytes::from(chunk));
+    }
+
+    //들고 있는 조각을 채널에 넣음 (채널이 가득 찼으면 false, 자리가 나면 알림으로 깨어남)
+    pub fn flush(&mut self) -> bool {
+        let Some(chunk) = self.pending.take() else {
+            return true;
+        };
+
+        let chunk = match self.sender.try_send(chunk) {
+            Ok(()) | Err(TrySendError::Closed(_)) => return true,
+            Err(TrySendError::Full(chunk)) => chunk,
+        };
+
+        //표시하기 전에 업스트림이 자리를 비웠을 수 있으므로 한 번 더
+        self.shared.blocked.store(true, Ordering::Relaxed);
+        match self.sender.try_send(chunk) {
+            Ok(()) | Err(TrySendError::Closed(_)) => true,
+            Err(TrySendError::Full(chunk)) => {
+                self.pending = Some(chunk);
+                false
+            }
+        }
+    }
+
+    //채널이 가득 차서 멈춰 있는지
+    pub fn is_blocked(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    //바디를 끝까지 넘김 (업스트림에 바디 끝을 알림)
+    pub fn finish(self) {
+        self.shared.finished.store(true, Ordering::Release);
+    }
+}
+
+/*
+ 업스트림 응답 바디 버퍼 (proxy_buffering on)
+ - 클라이언트가 아직 가져가지 않은 조각을 메모리에 buffers * buffer_size까지 쌓고, 넘으면 임시 파일에 이어 씀
+ - 임시 파일은 만들자마자 지워서(unlink) 작업이 끝나거나 워커가 죽어도 남지 않음, 다 읽으면 비우고 다시 씀
+ - 임시 파일에 남은 데이터가 max_temp_file_size에 닿으면 is_full(): 업스트림 읽기를 멈춤
+   (임시 파일을 쓰지 않으면 메모리가 찼을 때, 이미 읽은 조각이 들어갈 자리가 없으면 그 조각 하나만 들고 멈춤)
+ - 임시 파일 I/O는 페이지 캐시를 거치므로 작업 스레드에서 바로 함
+*/
+pub struct Spool {
+    memory: VecDeque<Bytes>,
+    memory_size: usize,
+    memory_limit: usize,
+    buffer_size: usize,
+    file: Option<File>,
+    read: u64,  //임시 파일에서 다음에 읽을 위치
+    write: u64, //임시 파일에서 다음에 쓸 위치
+    max_file_size: u64,
+    temp_path: PathBuf,
+    held: Option<Bytes>, //임시 파일에 넣으면 max_file_size를 넘는 조각 (파일을 다 꺼낸 뒤 꺼냄)
+    end: Option<Result<Bytes>>, //바디의 끝(빈 조각) 또는 에러, 쌓인 조각을 다 꺼낸 뒤 꺼냄
+}
+
+impl Spool {
+    pub fn new(buffering: &ProxyBuffering) -> Spool {
+        Spool {
+            memory: VecDeque::new(),
+            memory_size: 0,
+            memory_limit: buffering.buffers * buffering.buffer_size,
+            buffer_size: buffering.buffer_size,
+            file: None,
+            read: 0,
+            write: 0,
+            max_file_size: buffering.max_temp_file_size,
+            temp_path: buffering.temp_path.clone(),
+            held: None,
+            end: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+            && self.read == self.write
+            && self.held.is_none()
+            && self.end.is_none()
+    }
+
+    pub fn is_full(&self) -> bool {
+        if self.held.is_some() {
+            return true;
+        }
+
+        if self.read < self.write {
+            //임시 파일을 쓰는 중에는 새 조각이 모두 파일 뒤로 감
+            self.write - self.read >= self.max_file_size
+        } else {
+            self.memory_size >= self.memory_limit && self.max_file_size == 0
+        }
+    }
+
+    /*
+     조각 추가 (is_full()이 아닐 때만, 꺼내는 순서가 바뀌지 않도록 임시 파일에 남은 데이터가 있으면 파일 뒤에)
+     - 임시 파일이 max_file_size를 넘게 되면 파일에 쓰지 않고 들고 있음 (파일을 다 꺼낼 때까지 is_full())
+     - 임시 파일을 만들지 못하면 이 응답은 메모리 버퍼만 씀
+    */
+    pub fn push(&mut self, chunk: Bytes) -> Result<()> {
+        debug_assert!(self.held.is_none());
+
+        let fits = self.memory_size + chunk.len() <= self.memory_limit;
+        if self.read == self.write && (fits || self.max_file_size == 0) {
+            self.memory_size += chunk.len();
+            self.memory.push_back(chunk);
+            return Ok(());
+        }
+
+        if self.write - self.read + chunk.len() as u64 > self.max_file_size {
+            self.held = Some(chunk);
+            return Ok(());
+        }
+
+        let file = match &mut self.file {
+            Some(file) => file,
+            None => match create_temp_file(&self.temp_path) {
+                Ok(file) => self.file.insert(file),
+                Err(e) => {
+                    eprintln!(
+                        "❌ Failed to create proxy temp file in {}: {}",
+                        self.temp_path.display(),
+                        e
+                    );
+                    self.max_file_size = 0;
+                    return self.push(chunk);
+                }
+            },
+        };
+
+        file.write_all_at(&chunk, self.write)?;
+        self.write += chunk.len() as u64;
+        Ok(())
+    }
+
+    //업스트림 응답이 끝남 (빈 조각) 또는 실패
+    pub fn finish(&mut self, end: Result<Bytes>) {
+        self.end = Some(end);
+    }
+
+    //가장 먼저 쌓인 조각 (메모리 → 임시 파일 → 들고 있는 조각 → 끝 표시 순)
+    pub fn pop(&mut self) -> Option<Result<Bytes>> {
+        if let Some(chunk) = self.memory.pop_front() {
+            self.memory_size -= chunk.len();
+            return Some(Ok(chunk));
+        }
+
+        if self.read < self.write
+            && let Some(file) = &self.file
+        {
+            let len = (self.write - self.read).min(self.buffer_size as u64) as usize;
+            let mut chunk = vec![0; len];
+            if let Err(e) = file.read_exact_at(&mut chunk, self.read) {
+                self.read = self.write;
+                return Some(Err(e));
+            }
+
+            self.read += len as u64;
+            if self.read == self.write {
+                self.read = 0;
+                self.write = 0;
+                let _ = file.set_len(0);
+            }
+            return Some(Ok(Bytes::from(chunk)));
+        }
+
+        if let Some(chunk) = self.held.take() {
+            return Some(Ok(chunk));
+        }
+
+        self.end.take()
+    }
+}
+
+//dir 아래 임시 파일을 만들고 바로 지움 (열린 파일로만 남음)
+fn create_temp_file(dir: &Path) -> Result<File> {
+    let path = dir.join(format!(
+        "rustify-proxy-{}-{}",
+        process::id(),
+        TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    fs::remove_file(&path)?;
+
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spool(buffers: usize, buffer_size: usize, max_temp_file_size: u64) -> Spool {
+        Spool::new(&ProxyBuffering {
+            buffers,
+            buffer_size,
+            max_temp_file_size,
+            ..ProxyBuffering::default()
+        })
+    }
+
+    fn drain(spool: &mut Spool) -> Vec<u8> {
+        let mut out = Vec::new();
+        while let Some(chunk) = spool.pop() {
+            out.extend_from_slice(&chunk.unwrap());
+        }
+        out
+    }
+
+    #[test]
+    fn spills_to_temp_file_in_order() {
+        let mut spool = spool(1, 4, 1024);
+
+        spool.push(Bytes::from_static(b"abcd")).unwrap();
+        spool.push(Bytes::from_static(b"efgh")).unwrap();
+        spool.push(Bytes::from_static(b"ij")).unwrap();
+        assert!(spool.file.is_some());
+        assert!(!spool.is_full());
+
+        spool.finish(Ok(Bytes::new()));
+        assert_eq!(drain(&mut spool), b"abcdefghij");
+        assert!(spool.is_empty());
+    }
+
+    #[test]
+    fn stops_at_temp_file_cap() {
+        let mut spool = spool(1, 4, 8);
+
+        spool.push(Bytes::from_static(b"abcd")).unwrap();
+        spool.push(Bytes::from_static(b"efgh")).unwrap();
+        assert!(!spool.is_full());
+        spool.push(Bytes::from_static(b"ijkl")).unwrap();
+        assert!(spool.is_full());
+        assert_eq!(spool.write - spool.read, 8);
+
+        //메모리를 비워도 임시 파일이 차 있으면 계속 멈춤
+        assert_eq!(&spool.pop().unwrap().unwrap()[..], b"abcd");
+        assert!(spool.is_full());
+        assert_eq!(&spool.pop().unwrap().unwrap()[..], b"efgh");
+        assert!(!spool.is_full());
+
+        //파일에 넣으면 상한을 넘는 조각은 들고 멈춤
+        spool.push(Bytes::from_static(b"mnopq")).unwrap();
+        assert!(spool.is_full());
+        assert_eq!(spool.write - spool.read, 4);
+
+        assert_eq!(drain(&mut spool), b"ijklmnopq");
+        assert!(!spool.is_full());
+    }
+
+    #[test]
+    fn keeps_in_memory_without_temp_file() {
+        let mut spool = spool(2, 4, 0);
+
+        spool.push(Bytes::from_static(b"abcd")).unwrap();
+        assert!(!spool.is_full());
+        spool.push(Bytes::from_static(b"efghij")).unwrap();
+        assert!(spool.is_full());
+        assert!(spool.file.is_none());
+
+        assert_eq!(drain(&mut spool), b"abcdefghij");
+    }
+}
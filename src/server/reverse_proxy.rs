@@ -1,19 +1,636 @@
-use std::{ptr::null, time::Duration};
+use std::{
+    future,
+    io::{Error, ErrorKind, Read, Result},
+    net::IpAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
 
-use reqwest::Client;
+use bytes::{Buf, Bytes};
+use reqwest::{
+    Client,
+    header::{CONTENT_LENGTH, HeaderName, HeaderValue},
+};
+use tokio::{
+    runtime::Handle,
+    sync::{
+        mpsc::{self, error::TryRecvError},
+        oneshot,
+    },
+    time::Instant,
+};
+
+use Rustify::{
+    Token,
+    http::{Body, HeaderMap, Request, Response, StatusCode},
+};
+
+use crate::{
+    config::{Address, HealthCheckConfig, ProxyBuffering, ProxyTimeouts, UpstreamConfig},
+    server::{
+        health::Health,
+        notify::Notifier,
+        proxy_body::{RequestBody, SendProgress, Spool},
+    },
+};
+
+//업스트림 응답 바디 조각을 이벤트 루프 쪽에 쌓아 둘 수 있는 최대 개수 (가득 차면 업스트림 읽기를 멈춤)
+const BODY_CHANNEL_CAPACITY: usize = 8;
+
+//다음 홉으로 넘기지 않는 헤더 (RFC 9110 7.6.1), Connection 헤더에 나열된 이름도 함께 제외
+const HOP_BY_HOP: &[&str] = &[
+    "Connection",
+    "Keep-Alive",
+    "Proxy-Connection",
+    "Proxy-Authenticate",
+    "Proxy-Authorization",
+    "TE",
+    "Trailer",
+    "Transfer-Encoding",
+    "Upgrade",
+];
 
 pub struct ReverseProxy {
-    client: Client,
+    clients: Vec<(ClientTimeouts, Client)>, //location의 (연결, 읽기) 타임아웃 조합마다 클라이언트 (커넥션 풀)
+    upstream: Address,
+    base_url: String,         //업스트림 요청 URL의 scheme://host[:port] 부분
+    active: Arc<AtomicUsize>, //진행 중인 요청 수 (least_conn 등 부하 분산에 사용)
+    health: Health,           //모든 워커가 공유하는 서버 상태
+}
+
+//reqwest 클라이언트 단위로만 정할 수 있는 타임아웃 (연결, 읽기)
+type ClientTimeouts = (Duration, Duration);
+
+//업스트림 요청마다 필요한 전달 정보 (다른 서버로 다시 보낼 때도 그대로 씀)
+#[derive(Clone)]
+pub struct ForwardContext {
+    pub uri: String, //업스트림에 보낼 경로 (쿼리 포함)
+    pub client_addr: Option<IpAddr>,
+    pub notifier: Notifier,
+    pub token: Token, //업스트림이 진행되면 깨울 연결
+    pub timeouts: ProxyTimeouts,
+    pub buffering: ProxyBuffering,
+}
+
+//진행 중인 요청 하나 (업스트림 작업이 끝나거나 취소되어 drop되면 요청 수를 줄임)
+struct Active(Arc<AtomicUsize>);
+
+impl Active {
+    fn new(count: &Arc<AtomicUsize>) -> Active {
+        count.fetch_add(1, Ordering::Relaxed);
+        Active(count.clone())
+    }
+}
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
 }
 
+/*
+ 업스트림 서버로 요청을 전달하는 HTTP 클라이언트
+ - TCP 업스트림: http://주소:포트
+ - Unix 소켓 업스트림: 모든 연결을 소켓 파일로 보내고, URL의 호스트는 Host 헤더로만 쓰임 (localhost)
+ - 연결/읽기 타임아웃은 클라이언트 설정이므로 이 그룹을 쓰는 location의 조합마다 클라이언트를 만듦
+ - 보내기/전체 타임아웃은 요청마다 작업 안에서 잼
+ - 커넥션 풀 크기와 유휴 시간은 upstream의 keepalive, keepalive_timeout
+*/
 impl ReverseProxy {
-    fn new() -> Self {
-        let client: Client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .pool_max_idle_per_host(100) //커넥션 풀
-            .build()
-            .unwrap();
+    //timeouts: 이 서버의 그룹으로 보내는 location들의 타임아웃 (없으면 기본값)
+    pub fn new(
+        upstream: &Address,
+        health: Health,
+        config: &UpstreamConfig,
+        timeouts: &[ProxyTimeouts],
+    ) -> Result<Self> {
+        let mut keys: Vec<ClientTimeouts> = timeouts
+            .iter()
+            .map(|timeouts| (timeouts.connect, timeouts.read))
+            .collect();
+        if keys.is_empty() {
+            let timeouts = ProxyTimeouts::default();
+            keys.push((timeouts.connect, timeouts.read));
+        }
+        keys.sort_unstable();
+        keys.dedup();
+
+        let clients = keys
+            .into_iter()
+            .map(|(connect, read)| {
+                let builder = Client::builder()
+                    .connect_timeout(connect)
+                    .read_timeout(read)
+                    .pool_max_idle_per_host(config.keepalive)
+                    .pool_idle_timeout(config.keepalive_timeout);
+                let builder = match upstream {
+                    Address::Tcp(_) => builder,
+                    Address::Unix(path) => builder.unix_socket(path.as_path()),
+                };
+                let client = builder.build().map_err(Error::other)?;
+                Ok(((connect, read), client))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let base_url = match upstream {
+            Address::Tcp(addr) => format!("http://{}", addr),
+            Address::Unix(_) => String::from("http://localhost"),
+        };
+
+        Ok(ReverseProxy {
+            clients,
+            upstream: upstream.clone(),
+            base_url,
+            active: Arc::new(AtomicUsize::new(0)),
+            health,
+        })
+    }
+
+    pub fn upstream(&self) -> &Address {
+        &self.upstream
+    }
+
+    //응답 바디까지 다 받지 못한 요청 수
+    pub fn active(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    #[cfg(test)]
+    pub fn set_active(&self, count: usize) {
+        self.active.store(count, Ordering::Relaxed);
+    }
+
+    pub fn health(&self) -> &Health {
+        &self.health
+    }
 
-        ReverseProxy { client }
+    //timeouts에 맞는 클라이언트 (new()에서 모든 location의 조합을 만들어 둠)
+    fn client(&self, timeouts: &ProxyTimeouts) -> &Client {
+        let key = (timeouts.connect, timeouts.read);
+        self.clients
+            .iter()
+            .find(|(timeouts, _)| *timeouts == key)
+            .map_or(&self.clients[0].1, |(_, client)| client)
     }
+
+    /*
+     active health check 작업을 runtime에 띄움
+     - interval마다 차례를 가져간 워커 하나만 GET uri를 보내고 결과를 공유 상태에 기록
+     - 프록시 요청과 같은 클라이언트(커넥션 풀)를 씀
+    */
+    pub fn spawn_health_check(&self, runtime: &Handle, check: &HealthCheckConfig) {
+        let client = self.clients[0].1.clone();
+        let upstream = self.upstream.clone();
+        let health = self.health.clone();
+        let url = self.url(&check.uri);
+        let check = check.clone();
+
+        runtime.spawn(async move {
+            loop {
+                if let Err(wait) = health.claim_check(check.interval) {
+                    tokio::time::sleep(wait).await;
+                    continue;
+                }
+
+                let result = probe(&client, &url, &check).await;
+                health.record_check(&check, &upstream, result);
+            }
+        });
+    }
+
+    //업스트림에 보낼 전체 URL (path는 쿼리 포함, /로 시작)
+    fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    /*
+     요청을 업스트림으로 보내는 작업을 runtime에 띄움
+     - 응답 헤더가 오거나 실패하면, 이후에는 바디 조각이 올 때마다 notifier로 token 연결을 깨움
+     - proxy_buffering off: 바디를 채널로 조금씩 넘기고, 이벤트 루프가 읽어 가지 않으면 채널이 차서 업스트림 읽기도 멈춤
+       on: 클라이언트가 느려도 Spool(메모리, 임시 파일)에 쌓으며 계속 읽음
+     - body: 흘려보내는 요청 바디 (None이면 request의 바디)
+     - 리턴된 Pending(응답 헤더 전) 또는 응답 바디(헤더 후)를 drop하면 업스트림 요청을 취소함
+    */
+    pub fn forward(
+        &self,
+        runtime: &Handle,
+        request: Request,
+        body: Option<RequestBody>,
+        context: &ForwardContext,
+    ) -> Pending {
+        let (head_tx, head_rx) = oneshot::channel();
+        let pending = Pending { receiver: head_rx };
+
+        let (upstream_request, progress) = match self.build_request(request, body, context) {
+            Ok(built) => built,
+            Err(e) => {
+                eprintln!("❌ Upstream {} request error: {}", self.upstream, e);
+                let _ = head_tx.send(Err(StatusCode::InternalServerError));
+                return pending;
+            }
+        };
+
+        let client = self.client(&context.timeouts).clone();
+        let upstream = self.upstream.clone();
+        let active = Active::new(&self.active);
+        let timeouts = context.timeouts;
+        let buffering = context
+            .buffering
+            .response
+            .then(|| Spool::new(&context.buffering));
+        let notifier = context.notifier.clone();
+        let token = context.token;
+
+        runtime.spawn(async move {
+            let _active = active;
+            let mut head_tx = head_tx;
+            let deadline = (!timeouts.total.is_zero()).then(|| Instant::now() + timeouts.total);
+
+            let result = tokio::select! {
+                result = client.execute(upstream_request) => result.map_err(|e| {
+                    eprintln!("❌ Upstream {} error: {}", upstream, e);
+                    if e.is_timeout() {
+                        StatusCode::GatewayTimeout
+                    } else {
+                        StatusCode::BadGateway
+                    }
+                }),
+                _ = stalled(progress, timeouts.send) => {
+                    eprintln!("❌ Upstream {} timed out while sending request body", upstream);
+                    Err(StatusCode::GatewayTimeout)
+                }
+                _ = until(deadline) => {
+                    eprintln!("❌ Upstream {} timed out waiting for response", upstream);
+                    Err(StatusCode::GatewayTimeout)
+                }
+                _ = head_tx.closed() => return, //응답 헤더 전에 클라이언트 연결이 닫힘
+            };
+
+            let mut response = match result {
+                Ok(response) => response,
+                Err(status) => {
+                    let _ = head_tx.send(Err(status));
+                    notifier.notify(token);
+                    return;
+                }
+            };
+
+            let (body_tx, body_rx) = mpsc::channel(BODY_CHANNEL_CAPACITY);
+            let mut head = Response::new(StatusCode::from_u16(response.status().as_u16()))
+                .with_body(Body::Stream(Box::new(UpstreamBody {
+                    receiver: body_rx,
+                    chunk: Bytes::new(),
+                    finished: false,
+                })));
+            *head.headers_mut() = response_headers(response.headers());
+
+            if head_tx.send(Ok(head)).is_err() {
+                return;
+            }
+            notifier.notify(token);
+
+            let relay = Relay {
+                body_tx,
+                deadline,
+                upstream,
+                notifier,
+                token,
+            };
+            match buffering {
+                Some(spool) => relay.buffered(&mut response, spool).await,
+                None => relay.unbuffered(&mut response).await,
+            }
+        });
+
+        pending
+    }
+
+    /*
+     클라이언트 요청을 업스트림 요청으로 (메서드, URI, 전달할 헤더, 바디)
+     - 바디가 있으면 업스트림이 가져가는 진행 상황(proxy_send_timeout)도 함께 리턴
+     - 버퍼링한 바디는 Content-Length, 흘려보내는 바디는 chunked로 보냄
+    */
+    fn build_request(
+        &self,
+        request: Request,
+        body: Option<RequestBody>,
+        context: &ForwardContext,
+    ) -> std::result::Result<(reqwest::Request, Option<Arc<SendProgress>>), reqwest::Error> {
+        let method = reqwest::Method::from_bytes(request.method().as_str().as_bytes())
+            .unwrap_or(reqwest::Method::GET);
+
+        //바디가 없어도 길이(0)를 알렸던 요청은 업스트림에도 Content-Length를 보냄
+        let has_body = request.headers().contains("Content-Length")
+            || request.headers().contains("Transfer-Encoding");
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in request_headers(request.headers(), context.client_addr).iter() {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::from_bytes(name.as_bytes()),
+                HeaderValue::from_bytes(value.as_bytes()),
+            ) {
+                headers.append(name, value);
+            }
+        }
+
+        let body = match body {
+            Some(body) => Some(body),
+            None if has_body => Some(RequestBody::buffered(request.into_body())),
+            None => None,
+        };
+        let progress = body.as_ref().map(RequestBody::progress);
+        if let Some(len) = body.as_ref().and_then(RequestBody::len) {
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(len));
+        }
+
+        let mut builder = self
+            .client(&context.timeouts)
+            .request(method, self.url(&context.uri))
+            .headers(headers);
+        if let Some(body) = body {
+            builder = builder.body(reqwest::Body::wrap_stream(body));
+        }
+
+        Ok((builder.build()?, progress))
+    }
+}
+
+//deadline이 지나면 끝남 (None이면 끝나지 않음)
+async fn until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => future::pending().await,
+    }
+}
+
+//업스트림이 요청 바디를 timeout 동안 가져가지 않으면 끝남 (바디가 없으면 끝나지 않음)
+async fn stalled(progress: Option<Arc<SendProgress>>, timeout: Duration) {
+    match progress {
+        Some(progress) => progress.stalled(timeout).await,
+        None => future::pending().await,
+    }
+}
+
+//업스트림 응답 바디를 이벤트 루프 쪽 채널로 넘기는 작업 (빈 조각은 바디의 끝 표시)
+struct Relay {
+    body_tx: mpsc::Sender<Result<Bytes>>,
+    deadline: Option<Instant>, //proxy_total_timeout
+    upstream: Address,
+    notifier: Notifier,
+    token: Token,
+}
+
+impl Relay {
+    //proxy_buffering off: 클라이언트가 읽어 간 만큼만 업스트림에서 읽음
+    async fn unbuffered(&self, response: &mut reqwest::Response) {
+        loop {
+            let message = tokio::select! {
+                message = self.read(response) => message,
+                _ = self.body_tx.closed() => return, //클라이언트 연결이 닫힘
+            };
+
+            let last = !matches!(&message, Ok(chunk) if !chunk.is_empty());
+            if self.body_tx.send(message).await.is_err() {
+                return;
+            }
+            self.notifier.notify(self.token);
+
+            if last {
+                return;
+            }
+        }
+    }
+
+    //proxy_buffering on: 클라이언트가 가져가지 않은 조각은 spool에 쌓으며 업스트림을 끝까지 읽음
+    async fn buffered(&self, response: &mut reqwest::Response, mut spool: Spool) {
+        let mut reading = true;
+
+        while reading || !spool.is_empty() {
+            tokio::select! {
+                message = self.read(response), if reading && !spool.is_full() => {
+                    match message {
+                        Ok(chunk) if !chunk.is_empty() => {
+                            if let Err(e) = spool.push(chunk) {
+                                eprintln!("❌ Upstream {} failed to buffer body: {}", self.upstream, e);
+                                spool.finish(Err(e));
+                                reading = false;
+                            }
+                        }
+                        end => {
+                            spool.finish(end);
+                            reading = false;
+                        }
+                    }
+                }
+                permit = self.body_tx.reserve(), if !spool.is_empty() => {
+                    let (Ok(permit), Some(message)) = (permit, spool.pop()) else {
+                        return;
+                    };
+                    permit.send(message);
+                    self.notifier.notify(self.token);
+                }
+                _ = self.body_tx.closed(), if spool.is_empty() => return, //클라이언트 연결이 닫힘
+            }
+        }
+    }
+
+    //업스트림 응답 바디의 다음 조각 (끝이면 빈 조각)
+    async fn read(&self, response: &mut reqwest::Response) -> Result<Bytes> {
+        loop {
+            let chunk = tokio::select! {
+                chunk = response.chunk() => chunk,
+                _ = until(self.deadline) => {
+                    eprintln!("❌ Upstream {} timed out while reading body", self.upstream);
+                    return Err(ErrorKind::TimedOut.into());
+                }
+            };
+
+            match chunk {
+                Ok(Some(chunk)) if chunk.is_empty() => continue,
+                Ok(Some(chunk)) => return Ok(chunk),
+                Ok(None) => return Ok(Bytes::new()),
+                Err(e) => {
+                    eprintln!(
+                        "❌ Upstream {} error while reading body: {}",
+                        self.upstream, e
+                    );
+                    return Err(Error::other(e));
+                }
+            }
+        }
+    }
+}
+
+//health_check 요청 하나 (상태 코드와 바디가 기대와 다르면 이유)
+async fn probe(
+    client: &Client,
+    url: &str,
+    check: &HealthCheckConfig,
+) -> std::result::Result<(), String> {
+    let response = client
+        .get(url)
+        .timeout(check.timeout)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let status = response.status().as_u16();
+    if !check
+        .status
+        .iter()
+        .any(|&(low, high)| (low..=high).contains(&status))
+    {
+        return Err(format!("unexpected status {}", status));
+    }
+
+    if let Some(expected) = &check.body {
+        let body = response.bytes().await.map_err(|e| e.to_string())?;
+        if !body
+            .windows(expected.len())
+            .any(|window| window == expected.as_bytes())
+        {
+            return Err(format!("body does not contain \"{}\"", expected));
+        }
+    }
+
+    Ok(())
+}
+
+//업스트림 응답 헤더를 기다리는 요청
+pub struct Pending {
+    receiver: oneshot::Receiver<std::result::Result<Response, StatusCode>>,
+}
+
+impl Pending {
+    //응답 헤더가 도착했으면 Some (실패했으면 클라이언트에 보낼 에러 상태)
+    pub fn poll(&mut self) -> Option<std::result::Result<Response, StatusCode>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(oneshot::error::TryRecvError::Empty) => None,
+            Err(oneshot::error::TryRecvError::Closed) => Some(Err(StatusCode::BadGateway)),
+        }
+    }
+}
+
+/*
+ 업스트림 응답 바디 (Body::Stream)
+ - 아직 도착한 조각이 없으면 WouldBlock: 연결은 쓰기를 멈추고 다음 알림을 기다림
+ - 끝 표시 없이 채널이 닫히면 (작업이 취소됨) 바디가 잘렸으므로 에러
+*/
+struct UpstreamBody {
+    receiver: mpsc::Receiver<Result<Bytes>>,
+    chunk: Bytes, //받았지만 아직 읽어 가지 않은 나머지
+    finished: bool,
+}
+
+impl Read for UpstreamBody {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        while self.chunk.is_empty() && !self.finished {
+            match self.receiver.try_recv() {
+                Ok(Ok(chunk)) if chunk.is_empty() => self.finished = true,
+                Ok(Ok(chunk)) => self.chunk = chunk,
+                Ok(Err(e)) => return Err(e),
+                Err(TryRecvError::Empty) => return Err(ErrorKind::WouldBlock.into()),
+                Err(TryRecvError::Disconnected) => return Err(ErrorKind::UnexpectedEof.into()),
+            }
+        }
+
+        let n = buf.len().min(self.chunk.len());
+        buf[..n].copy_from_slice(&self.chunk[..n]);
+        self.chunk.advance(n);
+        Ok(n)
+    }
+}
+
+//Connection 헤더에 나열된 이름을 포함한 hop-by-hop 헤더인지
+fn is_hop_by_hop(headers: &HeaderMap, name: &str) -> bool {
+    HOP_BY_HOP.iter().any(|hop| hop.eq_ignore_ascii_case(name))
+        || headers.has_token("Connection", name)
+}
+
+/*
+ 업스트림에 보낼 요청 헤더
+ - hop-by-hop 헤더 제외, Content-Length는 클라이언트가 사용한 전송 방식과 상관없이 디코딩한 바디 길이로 다시 붙임
+ - Expect: 100-continue는 바디를 이미 다 받았으므로 제외
+ - X-Forwarded-For, Forwarded: 기존 값 뒤에 클라이언트 주소를 덧붙임 (Unix 소켓 클라이언트는 주소를 모름)
+ - X-Forwarded-Proto: 클라이언트가 사용한 scheme
+*/
+fn request_headers(headers: &HeaderMap, client_addr: Option<IpAddr>) -> HeaderMap {
+    let mut outgoing = HeaderMap::with_capacity(headers.len() + 3);
+    for (name, value) in headers.iter() {
+        if is_hop_by_hop(headers, name)
+            || name.eq_ignore_ascii_case("Content-Length")
+            || name.eq_ignore_ascii_case("Expect")
+            || name.eq_ignore_ascii_case("X-Forwarded-For")
+            || name.eq_ignore_ascii_case("X-Forwarded-Proto")
+            || name.eq_ignore_ascii_case("Forwarded")
+        {
+            continue;
+        }
+        outgoing.append(name, value);
+    }
+
+    let client = match client_addr {
+        Some(addr) => addr.to_string(),
+        None => String::from("unix:"),
+    };
+    let x_forwarded_for = headers
+        .get_all("X-Forwarded-For")
+        .chain(std::iter::once(client.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    outgoing.insert("X-Forwarded-For", x_forwarded_for);
+    outgoing.insert("X-Forwarded-Proto", "http");
+
+    //RFC 7239: IPv6 주소는 따옴표와 대괄호로 감싸고, 주소를 모르면 unknown
+    let mut element = match client_addr {
+        Some(IpAddr::V4(addr)) => format!("for={}", addr),
+        Some(IpAddr::V6(addr)) => format!("for=\"[{}]\"", addr),
+        None => String::from("for=unknown"),
+    };
+    if let Some(host) = headers.get("Host") {
+        element.push_str(&format!(
+            ";host=\"{}\"",
+            host.replace('\\', "\\\\").replace('"', "\\\"")
+        ));
+    }
+    element.push_str(";proto=http");
+
+    let forwarded_value = headers
+        .get_all("Forwarded")
+        .chain(std::iter::once(element.as_str()))
+        .collect::<Vec<_>>()
+        .join(", ");
+    outgoing.insert("Forwarded", forwarded_value);
+
+    outgoing
+}
+
+/*
+ 클라이언트에 보낼 응답 헤더
+ - hop-by-hop 헤더 제외 (바디 프레이밍은 응답을 보낼 때 다시 정함)
+ - Date, Server는 이 서버 값으로 (nginx와 같음)
+*/
+fn response_headers(upstream: &reqwest::header::HeaderMap) -> HeaderMap {
+    let mut connection = HeaderMap::new();
+    for value in upstream.get_all(reqwest::header::CONNECTION) {
+        connection.append("Connection", String::from_utf8_lossy(value.as_bytes()));
+    }
+
+    let mut headers = HeaderMap::with_capacity(upstream.len());
+    for (name, value) in upstream {
+        let name = name.as_str();
+        if is_hop_by_hop(&connection, name)
+            || name.eq_ignore_ascii_case("Date")
+            || name.eq_ignore_ascii_case("Server")
+        {
+            continue;
+        }
+        headers.append(name, String::from_utf8_lossy(value.as_bytes()));
+    }
+
+    headers
 }
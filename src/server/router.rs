@@ -0,0 +1,350 @@
+use std::{io, net::IpAddr};
+
+use tokio::runtime::{Builder, Runtime};
+
+use Rustify::{
+    Token,
+    http::{Request, Response, StatusCode},
+};
+
+use crate::{
+    config::{Config, LocationConfig, ProxyConfig, ReturnConfig, ServerConfig},
+    server::{
+        file_cache::FileCache,
+        health::HealthTable,
+        notify::Notifier,
+        proxy_body::{self, RequestBody, Upload},
+        reverse_proxy::ForwardContext,
+        static_files,
+        upstream::{Forward, Upstream},
+        uri,
+    },
+};
+
+//요청을 보낸 연결 (프록시 헤더와 업스트림 완료 알림에 사용)
+pub struct Peer {
+    pub token: Token,
+    pub addr: Option<IpAddr>, //클라이언트 IP (Unix 소켓이면 None)
+}
+
+//요청 처리 결과
+pub enum Reply {
+    Ready(Response),
+    Deferred(Deferred), //proxy_pass: 업스트림 응답 헤더가 오면 Router::resume()으로 꺼냄
+}
+
+//업스트림 응답을 기다리는 요청 (drop하면 업스트림 요청도 취소됨)
+pub struct Deferred {
+    server: usize,         //에러 응답에 쓸 error_page의 server 인덱스
+    upstream: usize,       //config.http.upstreams 인덱스
+    forward: Box<Forward>, //다시 보낼 원본 요청을 들고 있어 큼
+}
+
+/*
+ 요청 라우팅
+ - 요청이 들어온 listen 주소의 server들 중 Host 헤더와 server_name이 맞는 server 선택 (없으면 기본 server)
+ - server 안에서 location = 정확히 일치 → 가장 긴 접두사 순으로 location 선택
+ - location의 return → proxy_pass → root 아래 정적 파일 순으로 처리, 맞는 location이 없으면 server의 root
+ - 처리할 지시어가 없으면 404, 에러 응답에는 server의 error_page 적용
+ - proxy_pass: upstream 그룹에서 부하 분산 방식으로 서버를 고름
+   업스트림 I/O는 워커의 tokio 런타임 스레드에서 하고, 진행되면 notifier로 이벤트 루프를 깨움
+*/
+pub struct Router {
+    config: Config,
+    file_cache: FileCache,    //워커의 모든 연결이 공유하는 열린 파일 캐시
+    runtime: Option<Runtime>, //proxy_pass가 있을 때만 만듦 (thread_pool_size개 스레드)
+    upstreams: Vec<Upstream>, //config.http.upstreams와 같은 순서
+    notifier: Notifier,
+}
+
+impl Router {
+    //health: 마스터가 이 세대 워커들에게 나눠 준 업스트림 서버 상태 표
+    pub fn new(config: Config, notifier: Notifier, health: &HealthTable) -> io::Result<Router> {
+        let file_cache = FileCache::new(&config.http);
+
+        let mut upstreams = Vec::new();
+        let mut first = 0;
+        for (i, upstream) in config.http.upstreams.iter().enumerate() {
+            //이 그룹으로 보내는 location들의 타임아웃
+            let timeouts: Vec<_> = config
+                .http
+                .servers
+                .iter()
+                .flat_map(|server| &server.locations)
+                .filter_map(|location| location.proxy.as_ref())
+                .filter(|proxy| proxy.upstream == i)
+                .map(|proxy| proxy.timeouts)
+                .collect();
+
+            upstreams.push(Upstream::new(upstream, health, first, &timeouts)?);
+            first += upstream.servers.len();
+        }
+
+        let runtime = if upstreams.is_empty() {
+            None
+        } else {
+            Some(
+                Builder::new_multi_thread()
+                    .worker_threads(config.thread_pool_size)
+                    .thread_name("rustify-upstream")
+                    .enable_all()
+                    .build()?,
+            )
+        };
+
+        if let Some(runtime) = &runtime {
+            for (upstream, upstream_config) in upstreams.iter().zip(&config.http.upstreams) {
+                upstream.spawn_health_checks(runtime.handle(), upstream_config);
+            }
+        }
+
+        Ok(Router {
+            config,
+            file_cache,
+            runtime,
+            upstreams,
+            notifier,
+        })
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    //listener: 요청이 들어온 config.listeners 인덱스
+    pub fn handle(&self, listener: usize, request: Request, peer: &Peer) -> Reply {
+        self.route(listener, request, peer, None)
+    }
+
+    //요청 바디를 다 받기 전에 업스트림으로 흘려보낼 요청인지 (proxy_request_buffering off)
+    pub fn streams_body(&self, listener: usize, request: &Request) -> bool {
+        let index = self.find_server(listener, request.header("Host"));
+        let server = &self.config.http.servers[index];
+
+        let Ok(path) = uri::normalize(request.path()) else {
+            return false;
+        };
+
+        matches!(
+            find_location(server, &path),
+            Some(LocationConfig {
+                ret: None,
+                proxy: Some(proxy),
+                ..
+            }) if !proxy.buffering.request
+        )
+    }
+
+    /*
+     바디를 받으면서 업스트림으로 보내는 요청 처리 (streams_body()가 true인 요청)
+     - 받은 바디는 Upload에 넣고, 업스트림으로 보내지 못하면 None (바디는 읽어서 버림)
+    */
+    pub fn handle_upload(
+        &self,
+        listener: usize,
+        request: Request,
+        peer: &Peer,
+    ) -> (Reply, Option<Upload>) {
+        let (upload, body) = proxy_body::upload(self.notifier.clone(), peer.token);
+        let reply = self.route(listener, request, peer, Some(body));
+        match reply {
+            Reply::Deferred(_) => (reply, Some(upload)),
+            Reply::Ready(_) => (reply, None),
+        }
+    }
+
+    fn route(
+        &self,
+        listener: usize,
+        request: Request,
+        peer: &Peer,
+        body: Option<RequestBody>,
+    ) -> Reply {
+        let index = self.find_server(listener, request.header("Host"));
+        let server = &self.config.http.servers[index];
+
+        //location 규칙을 %XX나 .. 으로 피해 가지 못하도록 정규화한 경로로 고름
+        let path = match uri::normalize(request.path()) {
+            Ok(path) => path,
+            Err(status) => return Reply::Ready(self.error_response(server, status)),
+        };
+
+        let result = match find_location(server, &path) {
+            Some(LocationConfig { ret: Some(ret), .. }) => return_response(ret),
+            Some(
+                location @ LocationConfig {
+                    proxy: Some(proxy), ..
+                },
+            ) => match self.forward(location, proxy, request, &path, peer, body) {
+                Some(forward) => {
+                    return Reply::Deferred(Deferred {
+                        server: index,
+                        upstream: proxy.upstream,
+                        forward: Box::new(forward),
+                    });
+                }
+                None => Err(StatusCode::BadGateway),
+            },
+            Some(location) => {
+                static_files::serve(&location.files, &request, &path, &self.file_cache)
+            }
+            None => static_files::serve(&server.files, &request, &path, &self.file_cache),
+        };
+
+        Reply::Ready(result.unwrap_or_else(|status| self.error_response(server, status)))
+    }
+
+    /*
+     업스트림 응답 헤더가 도착했으면 클라이언트에 보낼 응답 (실패하면 에러 응답)
+     - 실패가 proxy_next_upstream 조건에 맞으면 다음 서버로 다시 보내고 계속 기다림
+    */
+    pub fn resume(&self, deferred: &mut Deferred) -> Option<Response> {
+        //Deferred는 런타임이 있을 때만 만들어짐
+        let runtime = self.runtime.as_ref()?;
+        let upstream = &self.upstreams[deferred.upstream];
+        let result = upstream.poll(runtime.handle(), &mut deferred.forward)?;
+        let server = &self.config.http.servers[deferred.server];
+
+        Some(result.unwrap_or_else(|status| self.error_response(server, status)))
+    }
+
+    /*
+     proxy_pass로 요청 전달 (upstream 그룹에 보낼 서버가 없으면 None)
+     - uri가 없으면 퍼센트 디코딩 전 원본 요청 URI 그대로 보냄
+     - 있으면 정규화한 경로(path)에서 location 경로에 해당하는 앞부분을 uri로 바꾸고 나머지는 다시 인코딩
+     - body: 받으면서 흘려보낼 요청 바디 (None이면 request의 바디를 보냄)
+    */
+    fn forward(
+        &self,
+        location: &LocationConfig,
+        proxy: &ProxyConfig,
+        request: Request,
+        path: &[u8],
+        peer: &Peer,
+        body: Option<RequestBody>,
+    ) -> Option<Forward> {
+        let path = match &proxy.uri {
+            Some(prefix) if location.exact => prefix.clone(),
+            Some(prefix) => format!(
+                "{}{}",
+                prefix,
+                uri::encode_path(&path[location.path.len()..])
+            ),
+            None => request.path().to_string(),
+        };
+        let uri = match request.query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path,
+        };
+
+        //new()에서 모든 upstream 그룹과 런타임을 만들어 둠
+        let upstream = &self.upstreams[proxy.upstream];
+        let runtime = self
+            .runtime
+            .as_ref()
+            .expect("runtime exists with upstreams");
+
+        let context = ForwardContext {
+            uri,
+            client_addr: peer.addr,
+            notifier: self.notifier.clone(),
+            token: peer.token,
+            timeouts: proxy.timeouts,
+            buffering: proxy.buffering.clone(),
+        };
+
+        upstream.forward(
+            runtime.handle(),
+            request,
+            body,
+            context,
+            &proxy.next_upstream,
+        )
+    }
+
+    /*
+     에러 응답: error_page가 있으면 그 URI를 맡는 location(없으면 server)의 root에서 읽음
+     - 페이지를 읽지 못하면 기본 에러 페이지
+    */
+    fn error_response(&self, server: &ServerConfig, status: StatusCode) -> Response {
+        let page = server.error_pages.get(&status.as_u16()).and_then(|uri| {
+            let files = match find_location(server, uri.as_bytes()) {
+                Some(location) => &location.files,
+                None => &server.files,
+            };
+            static_files::error_page(files.root.as_ref()?, uri, status, &self.file_cache)
+        });
+
+        page.unwrap_or_else(|| Response::error(status))
+    }
+
+    //config.http.servers 인덱스
+    fn find_server(&self, listener: usize, host: Option<&str>) -> usize {
+        let servers = &self.config.listeners[listener].servers;
+        let default = servers[0];
+
+        let Some(host) = host else {
+            return default;
+        };
+
+        //포트와 끝의 점 제거, 대소문자 무시 (IPv6 리터럴 [::1]:80 고려)
+        let host = match host.rfind(':') {
+            Some(i) if !host[i..].contains(']') => &host[..i],
+            _ => host,
+        };
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+
+        servers
+            .iter()
+            .copied()
+            .find(|&index| {
+                let server = &self.config.http.servers[index];
+                server.names.iter().any(|name| name_matches(name, &host))
+            })
+            .unwrap_or(default)
+    }
+}
+
+//server_name 매칭: 정확히 일치, *.example.com (서브도메인), .example.com (자기 자신 + 서브도메인)
+fn name_matches(name: &str, host: &str) -> bool {
+    if let Some(suffix) = name.strip_prefix('*') {
+        host.len() > suffix.len() && host.ends_with(suffix)
+    } else if let Some(domain) = name.strip_prefix('.') {
+        host == domain || host.ends_with(name)
+    } else {
+        name == host
+    }
+}
+
+//path: 정규화한 요청 경로 (uri::normalize)
+fn find_location<'a>(server: &'a ServerConfig, path: &[u8]) -> Option<&'a LocationConfig> {
+    if let Some(exact) = server
+        .locations
+        .iter()
+        .find(|location| location.exact && location.path.as_bytes() == path)
+    {
+        return Some(exact);
+    }
+
+    server
+        .locations
+        .iter()
+        .filter(|location| !location.exact && path.starts_with(location.path.as_bytes()))
+        .max_by_key(|location| location.path.len())
+}
+
+//return 지시어 응답 (3xx는 Location 헤더, 나머지는 텍스트 바디, 텍스트 없는 에러 코드는 에러 페이지)
+fn return_response(ret: &ReturnConfig) -> Result<Response, StatusCode> {
+    let code = ret.status.as_u16();
+
+    match &ret.text {
+        Some(url) if (301..=308).contains(&code) && code != 304 => {
+            Ok(Response::error(ret.status).with_header("Location", url.as_str()))
+        }
+        Some(text) => Ok(Response::new(ret.status)
+            .with_header("Content-Type", "text/plain")
+            .with_body(text.as_str())),
+        None if ret.status.is_success() => Ok(Response::new(ret.status)),
+        None => Err(ret.status),
+    }
+}
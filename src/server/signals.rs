@@ -0,0 +1,106 @@
+use std::{
+    io::{Error, Result},
+    sync::atomic::{AtomicI32, Ordering},
+    time::Duration,
+};
+
+use libc::{
+    F_GETFL, F_SETFD, F_SETFL, FD_CLOEXEC, O_NONBLOCK, POLLIN, SIG_DFL, c_int, c_void, pollfd,
+    sighandler_t,
+};
+
+//시그널 핸들러가 쓰는 파이프 쓰기 쪽 fd (-1이면 설치 전)
+static PIPE_WRITE_FD: AtomicI32 = AtomicI32::new(-1);
+
+//핸들러에서는 신호 번호 1바이트를 파이프에 쓰기만 함 (write는 async-signal-safe)
+extern "C" fn forward_signal(signal: c_int) {
+    let fd = PIPE_WRITE_FD.load(Ordering::Relaxed);
+    if fd >= 0 {
+        let byte = signal as u8;
+        //파이프가 가득 차서 실패해도 이미 깨울 바이트가 쌓여 있으므로 무시
+        unsafe { libc::write(fd, &byte as *const u8 as *const c_void, 1) };
+    }
+}
+
+/*
+ self-pipe 신호 처리
+ - 핸들러는 신호 번호를 파이프에 쓰고, 마스터 루프는 poll로 파이프를 기다리다가 읽어서 처리
+ - 신호 여러 개가 연달아 와도 순서대로 모두 전달됨 (마지막 신호 하나만 남기지 않음)
+ - 대기 중인 poll은 신호가 오면 바로 깨어나므로 SIGCHLD도 즉시 처리됨
+*/
+pub struct SignalPipe {
+    read_fd: c_int,
+    write_fd: c_int,
+    signals: Vec<c_int>,
+}
+
+impl SignalPipe {
+    //파이프를 만들고 signals에 핸들러 설치
+    pub fn install(signals: &[c_int]) -> Result<SignalPipe> {
+        let mut fds: [c_int; 2] = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } < 0 {
+            return Err(Error::last_os_error());
+        }
+
+        //양쪽 모두 논블로킹 + exec 시 닫힘 (핸들러가 write에서 멈추면 안 됨)
+        for fd in fds {
+            unsafe {
+                let flags = libc::fcntl(fd, F_GETFL);
+                libc::fcntl(fd, F_SETFL, flags | O_NONBLOCK);
+                libc::fcntl(fd, F_SETFD, FD_CLOEXEC);
+            }
+        }
+
+        PIPE_WRITE_FD.store(fds[1], Ordering::Relaxed);
+
+        for &signal in signals {
+            unsafe { libc::signal(signal, forward_signal as *const () as sighandler_t) };
+        }
+
+        Ok(SignalPipe {
+            read_fd: fds[0],
+            write_fd: fds[1],
+            signals: signals.to_vec(),
+        })
+    }
+
+    /*
+     신호가 올 때까지 최대 timeout만큼 대기하고 받은 신호를 도착 순서대로 리턴
+     - 타임아웃이거나 다른 이유로 깨어나면 빈 Vec
+    */
+    pub fn wait(&self, timeout: Duration) -> Vec<c_int> {
+        let mut fds = [pollfd {
+            fd: self.read_fd,
+            events: POLLIN,
+            revents: 0,
+        }];
+        let timeout_ms = timeout.as_millis().min(c_int::MAX as u128) as c_int;
+        unsafe { libc::poll(fds.as_mut_ptr(), 1, timeout_ms) };
+
+        let mut received: Vec<c_int> = Vec::new();
+        let mut buf = [0u8; 64];
+
+        loop {
+            let n = unsafe { libc::read(self.read_fd, buf.as_mut_ptr() as *mut c_void, buf.len()) };
+            if n <= 0 {
+                break;
+            }
+            received.extend(buf[..n as usize].iter().map(|&byte| byte as c_int));
+        }
+
+        received
+    }
+
+    //fork된 자식(워커)에서 호출: 핸들러를 기본 동작으로 되돌리고 파이프를 닫음
+    pub fn reset_in_child(&self) {
+        for &signal in &self.signals {
+            unsafe { libc::signal(signal, SIG_DFL) };
+        }
+
+        PIPE_WRITE_FD.store(-1, Ordering::Relaxed);
+        unsafe {
+            libc::close(self.read_fd);
+            libc::close(self.write_fd);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    fs::File,
+    io::{Error, Read, Result, Write},
+    net::{IpAddr, TcpListener, TcpStream},
+    os::{
+        fd::{AsRawFd, RawFd},
+        unix::net::{UnixListener, UnixStream},
+    },
+};
+
+/*
+ 수신 소켓 (TCP 또는 Unix 도메인 소켓)
+ - 이벤트 루프는 fd만 다루므로 종류와 상관없이 같은 경로로 accept
+*/
+pub enum Listener {
+    Tcp(TcpListener),
+    Unix(UnixListener),
+}
+
+impl Listener {
+    pub fn accept(&self) -> Result<Stream> {
+        match self {
+            Listener::Tcp(listener) => listener.accept().map(|(stream, _)| Stream::Tcp(stream)),
+            Listener::Unix(listener) => listener.accept().map(|(stream, _)| Stream::Unix(stream)),
+        }
+    }
+
+    /*
+     accept4(SOCK_NONBLOCK | SOCK_CLOEXEC): 받은 연결의 논블로킹 설정까지 시스템 콜 하나로 처리
+     - accept()는 논블로킹 설정에 fcntl이 한 번 더 필요
+    */
+    #[cfg(target_os = "linux")]
+    pub fn accept4(&self) -> Result<Stream> {
+        use std::os::fd::FromRawFd;
+
+        let fd = unsafe {
+            libc::accept4(
+                self.as_raw_fd(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            )
+        };
+
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(match self {
+            Listener::Tcp(_) => Stream::Tcp(unsafe { TcpStream::from_raw_fd(fd) }),
+            Listener::Unix(_) => Stream::Unix(unsafe { UnixStream::from_raw_fd(fd) }),
+        })
+    }
+
+    pub fn try_clone(&self) -> Result<Listener> {
+        match self {
+            Listener::Tcp(listener) => listener.try_clone().map(Listener::Tcp),
+            Listener::Unix(listener) => listener.try_clone().map(Listener::Unix),
+        }
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        match self {
+            Listener::Tcp(listener) => listener.set_nonblocking(nonblocking),
+            Listener::Unix(listener) => listener.set_nonblocking(nonblocking),
+        }
+    }
+
+    /*
+     이 리스너에서 accept된 fd를 Stream으로 감쌈 (io_uring accept 완료 결과)
+     - 안전성: fd는 이 리스너에서 받은 열린 소켓이어야 하고, 소유권이 Stream으로 넘어감
+    */
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    pub unsafe fn stream_from_raw_fd(&self, fd: RawFd) -> Stream {
+        use std::os::fd::FromRawFd;
+
+        match self {
+            Listener::Tcp(_) => Stream::Tcp(unsafe { TcpStream::from_raw_fd(fd) }),
+            Listener::Unix(_) => Stream::Unix(unsafe { UnixStream::from_raw_fd(fd) }),
+        }
+    }
+}
+
+impl AsRawFd for Listener {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            Listener::Tcp(listener) => listener.as_raw_fd(),
+            Listener::Unix(listener) => listener.as_raw_fd(),
+        }
+    }
+}
+
+//클라이언트 연결 (TCP 또는 Unix 도메인 소켓)
+pub enum Stream {
+    Tcp(TcpStream),
+    Unix(UnixStream),
+}
+
+impl Stream {
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.set_nonblocking(nonblocking),
+            Stream::Unix(stream) => stream.set_nonblocking(nonblocking),
+        }
+    }
+
+    //클라이언트 IP (Unix 소켓이면 None, IPv4-mapped IPv6 주소는 IPv4로)
+    pub fn peer_ip(&self) -> Option<IpAddr> {
+        match self {
+            Stream::Tcp(stream) => stream.peer_addr().ok().map(|addr| addr.ip().to_canonical()),
+            Stream::Unix(_) => None,
+        }
+    }
+
+    //Nagle 알고리즘 끄기 (Unix 소켓에는 해당 없음)
+    pub fn set_nodelay(&self, nodelay: bool) -> Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.set_nodelay(nodelay),
+            Stream::Unix(_) => Ok(()),
+        }
+    }
+
+    /*
+     파일의 offset부터 최대 len 바이트를 커널 안에서 바로 소켓으로 복사 (sendfile, 유저 공간 버퍼를 거치지 않음)
+     - 소켓 버퍼가 가득 차면 WouldBlock, 일부만 보냈으면 보낸 바이트 수
+     - offset이 파일 끝이면 0 (응답 도중 파일이 잘림)
+    */
+    #[cfg(target_os = "linux")]
+    pub fn send_file(&self, file: &File, offset: u64, len: u64) -> Result<usize> {
+        //한 번에 보낼 수 있는 최대 크기 (Linux 제한 0x7ffff000)
+        const MAX_SENDFILE: u64 = 0x7fff_f000;
+
+        let mut offset = offset as libc::off_t;
+        let n = unsafe {
+            libc::sendfile(
+                self.as_raw_fd(),
+                file.as_raw_fd(),
+                &mut offset,
+                len.min(MAX_SENDFILE) as usize,
+            )
+        };
+
+        if n < 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(n as usize)
+    }
+
+    //macOS sendfile: 보낸 바이트 수를 len 인자로 돌려받음 (EAGAIN이어도 일부를 보냈을 수 있음)
+    #[cfg(target_os = "macos")]
+    pub fn send_file(&self, file: &File, offset: u64, len: u64) -> Result<usize> {
+        let mut sent = len as libc::off_t;
+        let result = unsafe {
+            libc::sendfile(
+                file.as_raw_fd(),
+                self.as_raw_fd(),
+                offset as libc::off_t,
+                &mut sent,
+                std::ptr::null_mut(),
+                0,
+            )
+        };
+
+        if result < 0 {
+            let e = Error::last_os_error();
+            let partial = matches!(e.raw_os_error(), Some(libc::EAGAIN | libc::EINTR));
+            if !(partial && sent > 0) {
+                return Err(e);
+            }
+        }
+        Ok(sent as usize)
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            Stream::Unix(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            Stream::Unix(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            Stream::Unix(stream) => stream.flush(),
+        }
+    }
+}
+
+impl AsRawFd for Stream {
+    fn as_raw_fd(&self) -> RawFd {
+        match self {
+            Stream::Tcp(stream) => stream.as_raw_fd(),
+            Stream::Unix(stream) => stream.as_raw_fd(),
+        }
+    }
+}
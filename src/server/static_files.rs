@@ -0,0 +1,714 @@
+use std::{
+    ffi::OsStr,
+    fs::{self, Metadata, OpenOptions},
+    io::{self, ErrorKind},
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{MetadataExt, OpenOptionsExt},
+    },
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use Rustify::http::{
+    Body, Method, Request, Response, StatusCode,
+    date::{format_http_date, parse_http_date},
+};
+
+use crate::{
+    config::{Expires, FilesConfig},
+    server::{
+        file_cache::{FileCache, OpenFile},
+        uri,
+    },
+};
+
+//확장자별 Content-Type (모르는 확장자는 application/octet-stream)
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("html", "text/html; charset=utf-8"),
+    ("htm", "text/html; charset=utf-8"),
+    ("css", "text/css; charset=utf-8"),
+    ("js", "text/javascript; charset=utf-8"),
+    ("mjs", "text/javascript; charset=utf-8"),
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("xml", "application/xml"),
+    ("txt", "text/plain; charset=utf-8"),
+    ("md", "text/markdown; charset=utf-8"),
+    ("csv", "text/csv; charset=utf-8"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("wasm", "application/wasm"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+];
+
+const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+//Range 헤더 하나에 허용하는 최대 구간 수 (넘으면 Range를 무시하고 전체 응답)
+const MAX_RANGES: usize = 16;
+
+//expires epoch / max 가 만드는 Expires 헤더 (nginx와 같은 값)
+const EXPIRES_EPOCH: &str = "Thu, 01 Jan 1970 00:00:01 GMT";
+const EXPIRES_MAX: &str = "Thu, 31 Dec 2037 23:55:55 GMT";
+const MAX_AGE_MAX: u64 = 10 * 365 * 24 * 60 * 60;
+
+//multipart/byteranges 경계 문자열이 응답마다 달라지도록 섞는 값
+static BOUNDARY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/*
+ 정적 파일 응답 (GET, HEAD)
+ - path: 라우터가 정규화한 요청 경로 (uri::normalize), root 아래 파일로 매핑
+ - /로 끝나면 index 파일을 순서대로 찾음 (없으면 403)
+ - 디렉터리를 /없이 요청하면 /를 붙인 주소로 301
+ - 심볼릭 링크를 따라간 실제 경로가 root 밖이면 403
+ - 조건부 요청(If-Match 등)을 검사한 뒤 GET의 Range 요청은 206 (구간이 여러 개면 multipart/byteranges)
+ - root가 없으면 404, 에러는 상태 코드로 돌려줘서 라우터가 error_page를 적용
+ - 파일 열기와 메타데이터는 워커의 열린 파일 캐시를 거침
+*/
+pub fn serve(
+    files: &FilesConfig,
+    request: &Request,
+    path: &[u8],
+    cache: &FileCache,
+) -> Result<Response, StatusCode> {
+    let Some(root) = &files.root else {
+        return Err(StatusCode::NotFound);
+    };
+    if !matches!(request.method(), Method::Get | Method::Head) {
+        return Err(StatusCode::MethodNotAllowed);
+    }
+
+    let (relative, directory) = relative_path(path);
+
+    if directory {
+        for name in &files.index {
+            match lookup(cache, root, &relative.join(name)) {
+                Ok(open) if open.metadata.is_file() => return respond(files, request, &open),
+                Ok(_) | Err(StatusCode::NotFound) => continue,
+                Err(status) => return Err(status),
+            }
+        }
+
+        //디렉터리는 있지만 index 파일이 없음 (디렉터리 목록은 보여주지 않음)
+        return match lookup(cache, root, &relative) {
+            Ok(open) if open.metadata.is_dir() => Err(StatusCode::Forbidden),
+            Ok(_) => Err(StatusCode::NotFound),
+            Err(status) => Err(status),
+        };
+    }
+
+    let open = lookup(cache, root, &relative)?;
+
+    if open.metadata.is_dir() {
+        let location = match request.query() {
+            Some(query) => format!("{}/?{}", request.path(), query),
+            None => format!("{}/", request.path()),
+        };
+        return Ok(Response::error(StatusCode::MovedPermanently).with_header("Location", location));
+    }
+    if !open.metadata.is_file() {
+        return Err(StatusCode::Forbidden);
+    }
+
+    respond(files, request, &open)
+}
+
+//error_page URI를 디스크에서 읽어서 원래 상태 코드로 응답
+pub fn error_page(
+    root: &Path,
+    uri: &str,
+    status: StatusCode,
+    cache: &FileCache,
+) -> Option<Response> {
+    let (relative, _) = relative_path(&uri::normalize(uri).ok()?);
+    let open = lookup(cache, root, &relative).ok()?;
+
+    if !open.metadata.is_file() {
+        return None;
+    }
+
+    let mut response = file_response(&open);
+    response.set_status(status);
+    Some(response)
+}
+
+/*
+ 파일 응답: 조건부 요청 → Range 순으로 처리하고 검증자(ETag, Last-Modified)와 캐시 헤더를 붙임
+ - 조건이 맞지 않으면 304 (If-None-Match, If-Modified-Since) 또는 412 (If-Match, If-Unmodified-Since)
+*/
+fn respond(
+    files: &FilesConfig,
+    request: &Request,
+    open: &OpenFile,
+) -> Result<Response, StatusCode> {
+    let validators = Validators::new(&open.metadata);
+
+    let response = match evaluate_preconditions(request, &validators) {
+        Some(StatusCode::NotModified) => Response::new(StatusCode::NotModified),
+        Some(status) => return Err(status),
+        None => range_response(request, open, &validators),
+    };
+
+    if response.status() == StatusCode::RangeNotSatisfiable {
+        return Ok(response);
+    }
+
+    let mut response = response.with_header("ETag", validators.etag.as_str());
+    if let Some(modified) = validators.modified {
+        response = response.with_header("Last-Modified", format_http_date(modified));
+    }
+
+    Ok(cache_headers(response, files))
+}
+
+/*
+ 파일의 검증자
+ - ETag: 수정 시각(나노초까지)과 크기로 만든 강한 검증자 "초+나노초-크기" (16진수)
+ - Last-Modified: 수정 시각 (HTTP-date는 초 단위이므로 초 아래는 버림)
+*/
+struct Validators {
+    etag: String,
+    modified: Option<SystemTime>,
+}
+
+impl Validators {
+    fn new(metadata: &Metadata) -> Validators {
+        let etag = format!(
+            "\"{:x}{:08x}-{:x}\"",
+            metadata.mtime(),
+            metadata.mtime_nsec(),
+            metadata.len()
+        );
+        let modified = u64::try_from(metadata.mtime())
+            .ok()
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+
+        Validators { etag, modified }
+    }
+}
+
+/*
+ 조건부 요청 평가 (RFC 9110 13.2.2 순서)
+ 1. If-Match가 있으면 강한 비교로 하나도 맞지 않을 때 412
+ 2. 없으면 If-Unmodified-Since 이후에 수정됐을 때 412
+ 3. If-None-Match가 있으면 약한 비교로 하나라도 맞을 때 304 (GET/HEAD가 아니면 412)
+ 4. 없으면 If-Modified-Since 이후에 수정되지 않았을 때 304 (GET/HEAD에만 적용)
+ - 날짜 형식이 틀린 헤더는 무시
+*/
+fn evaluate_preconditions(request: &Request, validators: &Validators) -> Option<StatusCode> {
+    let headers = request.headers();
+
+    if headers.contains("If-Match") {
+        if !etag_matches(headers.get_all("If-Match"), &validators.etag, false) {
+            return Some(StatusCode::PreconditionFailed);
+        }
+    } else if let Some(since) = headers.get("If-Unmodified-Since").and_then(parse_http_date)
+        && let Some(modified) = validators.modified
+        && modified > since
+    {
+        return Some(StatusCode::PreconditionFailed);
+    }
+
+    let read = matches!(request.method(), Method::Get | Method::Head);
+    if headers.contains("If-None-Match") {
+        if etag_matches(headers.get_all("If-None-Match"), &validators.etag, true) {
+            return Some(if read {
+                StatusCode::NotModified
+            } else {
+                StatusCode::PreconditionFailed
+            });
+        }
+    } else if read
+        && let Some(since) = headers.get("If-Modified-Since").and_then(parse_http_date)
+        && let Some(modified) = validators.modified
+        && modified <= since
+    {
+        return Some(StatusCode::NotModified);
+    }
+
+    None
+}
+
+//ETag 목록("*" 또는 "a", W/"b", ...) 중 하나라도 맞는지 (weak: W/ 접두사를 무시하는 약한 비교)
+fn etag_matches<'a>(values: impl Iterator<Item = &'a str>, etag: &str, weak: bool) -> bool {
+    values
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .any(|tag| {
+            if tag == "*" {
+                return true;
+            }
+            match tag.strip_prefix("W/") {
+                Some(opaque) => weak && opaque == etag,
+                None => tag == etag,
+            }
+        })
+}
+
+//expires → Expires와 Cache-Control: max-age, cache_control이 있으면 Cache-Control을 덮어씀
+fn cache_headers(mut response: Response, files: &FilesConfig) -> Response {
+    match files.expires {
+        Some(Expires::Epoch) => {
+            response = response
+                .with_header("Expires", EXPIRES_EPOCH)
+                .with_header("Cache-Control", "no-cache");
+        }
+        Some(Expires::Max) => {
+            response = response
+                .with_header("Expires", EXPIRES_MAX)
+                .with_header("Cache-Control", format!("max-age={}", MAX_AGE_MAX));
+        }
+        Some(Expires::After(duration)) => {
+            response = response
+                .with_header("Expires", format_http_date(SystemTime::now() + duration))
+                .with_header("Cache-Control", format!("max-age={}", duration.as_secs()));
+        }
+        Some(Expires::Off) | None => {}
+    }
+
+    match &files.cache_control {
+        Some(cache_control) => response.with_header("Cache-Control", cache_control.as_str()),
+        None => response,
+    }
+}
+
+fn file_response(open: &OpenFile) -> Response {
+    Response::new(StatusCode::Ok)
+        .with_header("Content-Type", mime_type(&open.path))
+        .with_header("Accept-Ranges", "bytes")
+        .with_body(Body::File {
+            file: Arc::clone(&open.file),
+            offset: 0,
+            len: open.metadata.len(),
+        })
+}
+
+/*
+ Range 요청 처리 (RFC 9110 14)
+ - GET만 적용, 문법이 틀린 Range나 bytes 이외의 단위는 무시하고 전체 응답
+ - 만족하는 구간이 없으면 416 (Content-Range에 전체 크기만 알려줌)
+ - 겹치거나 붙어 있는 구간은 합침: 하나면 206 + Content-Range, 여러 개면 multipart/byteranges
+*/
+fn range_response(request: &Request, open: &OpenFile, validators: &Validators) -> Response {
+    let size = open.metadata.len();
+
+    let Some(range) = request.header("Range") else {
+        return file_response(open);
+    };
+    if *request.method() != Method::Get {
+        return file_response(open);
+    }
+    if let Some(if_range) = request.header("If-Range")
+        && !if_range_matches(if_range, validators)
+    {
+        return file_response(open);
+    }
+    let Some(ranges) = parse_range(range, size) else {
+        return file_response(open);
+    };
+
+    let content_type = mime_type(&open.path);
+
+    match ranges.as_slice() {
+        [] => Response::error(StatusCode::RangeNotSatisfiable)
+            .with_header("Content-Range", format!("bytes */{}", size)),
+        [(start, end)] => Response::new(StatusCode::PartialContent)
+            .with_header("Content-Type", content_type)
+            .with_header("Accept-Ranges", "bytes")
+            .with_header("Content-Range", format!("bytes {}-{}/{}", start, end, size))
+            .with_body(Body::File {
+                file: Arc::clone(&open.file),
+                offset: *start,
+                len: end - start + 1,
+            }),
+        ranges => {
+            let boundary = boundary();
+            let mut parts: Vec<Body> = Vec::with_capacity(ranges.len() * 2 + 1);
+
+            for (start, end) in ranges {
+                let head = format!(
+                    "\r\n--{}\r\nContent-Type: {}\r\nContent-Range: bytes {}-{}/{}\r\n\r\n",
+                    boundary, content_type, start, end, size
+                );
+                parts.push(Body::from(head));
+                parts.push(Body::File {
+                    file: Arc::clone(&open.file),
+                    offset: *start,
+                    len: end - start + 1,
+                });
+            }
+            parts.push(Body::from(format!("\r\n--{}--\r\n", boundary)));
+
+            Response::new(StatusCode::PartialContent)
+                .with_header(
+                    "Content-Type",
+                    format!("multipart/byteranges; boundary={}", boundary),
+                )
+                .with_header("Accept-Ranges", "bytes")
+                .with_body(Body::Parts(parts))
+        }
+    }
+}
+
+/*
+ Range: bytes=0-499, 500-, -200
+ - 구간은 (시작, 끝) 포함 범위로, 파일 크기에 맞춰 자르고 정렬해서 합침
+ - 문법이 틀렸거나 구간이 너무 많으면 None (Range 무시), 만족하는 구간이 없으면 빈 Vec
+*/
+fn parse_range(header: &str, size: u64) -> Option<Vec<(u64, u64)>> {
+    let (unit, specs) = header.split_once('=')?;
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+
+    let mut ranges: Vec<(u64, u64)> = Vec::new();
+    let mut count = 0;
+
+    for spec in specs
+        .split(',')
+        .map(str::trim)
+        .filter(|spec| !spec.is_empty())
+    {
+        count += 1;
+        if count > MAX_RANGES {
+            return None;
+        }
+
+        let (first, last) = spec.split_once('-')?;
+        let (first, last) = (first.trim(), last.trim());
+
+        if first.is_empty() {
+            //-N: 마지막 N 바이트
+            let suffix: u64 = last.parse().ok()?;
+            if suffix > 0 && size > 0 {
+                ranges.push((size.saturating_sub(suffix), size - 1));
+            }
+            continue;
+        }
+
+        let start: u64 = first.parse().ok()?;
+        let end: u64 = match last {
+            "" => u64::MAX,
+            last => last.parse().ok()?,
+        };
+        if end < start {
+            return None;
+        }
+        if start < size {
+            ranges.push((start, end.min(size - 1)));
+        }
+    }
+
+    if count == 0 {
+        return None;
+    }
+
+    ranges.sort_unstable();
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    Some(merged)
+}
+
+//If-Range: ETag(강한 비교)나 날짜가 지금 파일과 정확히 같을 때만 Range 적용
+fn if_range_matches(value: &str, validators: &Validators) -> bool {
+    let value = value.trim();
+    if value.starts_with('"') || value.starts_with("W/") {
+        return value == validators.etag;
+    }
+
+    validators
+        .modified
+        .is_some_and(|modified| format_http_date(modified) == value)
+}
+
+fn boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    let counter = BOUNDARY_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{:016x}{:08x}", nanos, counter as u32)
+}
+
+//열린 파일 캐시에서 찾고, 없거나 검증 주기가 지났으면 새로 열기
+fn lookup(cache: &FileCache, root: &Path, relative: &Path) -> Result<OpenFile, StatusCode> {
+    cache.open(root.join(relative), || open(root, relative))
+}
+
+/*
+ root 아래 relative 경로를 열기
+ - 심볼릭 링크를 모두 풀어낸 실제 경로가 root(역시 실제 경로) 안에 있는지 확인한 뒤 그 경로를 염
+ - 확인과 열기 사이에 마지막 구성 요소가 링크로 바뀌는 경우는 O_NOFOLLOW로 막음
+*/
+fn open(root: &Path, relative: &Path) -> Result<OpenFile, StatusCode> {
+    let root = fs::canonicalize(root).map_err(|e| io_status(&e))?;
+    let path = fs::canonicalize(root.join(relative)).map_err(|e| io_status(&e))?;
+
+    if !path.starts_with(&root) {
+        return Err(StatusCode::Forbidden);
+    }
+
+    let file = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(&path)
+        .map_err(|e| io_status(&e))?;
+    let metadata = file.metadata().map_err(|e| io_status(&e))?;
+
+    Ok(OpenFile {
+        file: Arc::new(file),
+        metadata,
+        path,
+    })
+}
+
+fn io_status(e: &io::Error) -> StatusCode {
+    match e.kind() {
+        ErrorKind::NotFound | ErrorKind::NotADirectory | ErrorKind::InvalidFilename => {
+            StatusCode::NotFound
+        }
+        ErrorKind::PermissionDenied => StatusCode::Forbidden,
+        //O_NOFOLLOW: 마지막 구성 요소가 심볼릭 링크로 바뀜
+        _ if e.raw_os_error() == Some(libc::ELOOP) => StatusCode::Forbidden,
+        _ => StatusCode::InternalServerError,
+    }
+}
+
+//정규화한 요청 경로(uri::normalize) → root 기준 상대 경로 (/로 끝나는지 함께 리턴)
+fn relative_path(path: &[u8]) -> (PathBuf, bool) {
+    let relative: PathBuf = path
+        .split(|&b| b == b'/')
+        .filter(|segment| !segment.is_empty())
+        .map(OsStr::from_bytes)
+        .collect();
+
+    (relative, path.ends_with(b"/"))
+}
+
+fn mime_type(path: &Path) -> &'static str {
+    let Some(extension) = path.extension().and_then(OsStr::to_str) else {
+        return DEFAULT_MIME_TYPE;
+    };
+
+    MIME_TYPES
+        .iter()
+        .find(|(ext, _)| ext.eq_ignore_ascii_case(extension))
+        .map_or(DEFAULT_MIME_TYPE, |(_, mime)| mime)
+}
+
+#[cfg(test)]
+mod tests {
+    use Rustify::http::{HeaderMap, Version};
+
+    use super::*;
+
+    #[test]
+    fn parses_single_ranges() {
+        assert_eq!(parse_range("bytes=0-499", 1000), Some(vec![(0, 499)]));
+        assert_eq!(parse_range("bytes=500-", 1000), Some(vec![(500, 999)]));
+        assert_eq!(parse_range("bytes=-200", 1000), Some(vec![(800, 999)]));
+        assert_eq!(parse_range("BYTES = 0-0", 1000), Some(vec![(0, 0)]));
+    }
+
+    #[test]
+    fn clamps_ranges_to_file_size() {
+        assert_eq!(parse_range("bytes=900-2000", 1000), Some(vec![(900, 999)]));
+        assert_eq!(parse_range("bytes=-5000", 1000), Some(vec![(0, 999)]));
+    }
+
+    #[test]
+    fn sorts_and_merges_ranges() {
+        assert_eq!(
+            parse_range("bytes=20-30, 0-9, 10-15, 50-", 100),
+            Some(vec![(0, 15), (20, 30), (50, 99)])
+        );
+        assert_eq!(parse_range("bytes=0-50,25-75", 100), Some(vec![(0, 75)]));
+    }
+
+    #[test]
+    fn returns_empty_when_unsatisfiable() {
+        assert_eq!(parse_range("bytes=1000-", 1000), Some(vec![]));
+        assert_eq!(parse_range("bytes=-0", 1000), Some(vec![]));
+        assert_eq!(parse_range("bytes=0-", 0), Some(vec![]));
+    }
+
+    #[test]
+    fn ignores_invalid_ranges() {
+        for header in [
+            "items=0-1",
+            "bytes=",
+            "bytes=5",
+            "bytes=5-1",
+            "bytes=a-b",
+            "bytes=--5",
+            "0-1",
+        ] {
+            assert_eq!(parse_range(header, 1000), None, "{}", header);
+        }
+
+        let too_many = format!("bytes={}", vec!["0-0"; MAX_RANGES + 1].join(","));
+        assert_eq!(parse_range(&too_many, 1000), None);
+    }
+
+    const ETAG: &str = "\"5f5e100-2a\"";
+
+    //2020-09-13 12:26:40 GMT에 수정된 파일
+    fn validators() -> Validators {
+        Validators {
+            etag: String::from(ETAG),
+            modified: Some(UNIX_EPOCH + Duration::from_secs(1_600_000_000)),
+        }
+    }
+
+    type Headers<'a> = &'a [(&'a str, &'a str)];
+
+    fn preconditions(method: Method, headers: Headers) -> Option<StatusCode> {
+        let mut map = HeaderMap::new();
+        for &(name, value) in headers {
+            map.append(name, value);
+        }
+        let request = Request::new(method, "/", Version::Http11, map);
+        evaluate_preconditions(&request, &validators())
+    }
+
+    #[test]
+    fn evaluates_preconditions_in_rfc_order() {
+        const BEFORE: &str = "Sun, 13 Sep 2020 12:26:39 GMT";
+        const AT: &str = "Sun, 13 Sep 2020 12:26:40 GMT";
+        const OTHER: &str = "\"other\"";
+        const WEAK: &str = "W/\"5f5e100-2a\"";
+
+        let not_modified = Some(StatusCode::NotModified);
+        let failed = Some(StatusCode::PreconditionFailed);
+        let cases: [(Headers, Option<StatusCode>); 17] = [
+            (&[], None),
+            //If-Match: 강한 비교
+            (&[("If-Match", ETAG)], None),
+            (&[("If-Match", "*")], None),
+            (&[("If-Match", "\"a\", \"5f5e100-2a\"")], None),
+            (&[("If-Match", OTHER)], failed),
+            (&[("If-Match", WEAK)], failed),
+            //If-Unmodified-Since
+            (&[("If-Unmodified-Since", AT)], None),
+            (&[("If-Unmodified-Since", BEFORE)], failed),
+            (&[("If-Unmodified-Since", "yesterday")], None),
+            //If-Match가 있으면 If-Unmodified-Since는 보지 않음
+            (&[("If-Match", ETAG), ("If-Unmodified-Since", BEFORE)], None),
+            //If-None-Match: 약한 비교
+            (&[("If-None-Match", WEAK)], not_modified),
+            (&[("If-None-Match", "\"a\", *")], not_modified),
+            (&[("If-None-Match", OTHER)], None),
+            //If-Modified-Since
+            (&[("If-Modified-Since", AT)], not_modified),
+            (&[("If-Modified-Since", BEFORE)], None),
+            //If-None-Match가 있으면 If-Modified-Since는 보지 않음
+            (&[("If-None-Match", OTHER), ("If-Modified-Since", AT)], None),
+            //412가 304보다 먼저
+            (&[("If-Match", OTHER), ("If-None-Match", ETAG)], failed),
+        ];
+
+        for (headers, expected) in cases {
+            assert_eq!(
+                preconditions(Method::Get, headers),
+                expected,
+                "{:?}",
+                headers
+            );
+        }
+    }
+
+    #[test]
+    fn fails_if_none_match_for_other_methods() {
+        let headers = [("If-None-Match", ETAG)];
+        assert_eq!(
+            preconditions(Method::Head, &headers),
+            Some(StatusCode::NotModified)
+        );
+        assert_eq!(
+            preconditions(Method::Put, &headers),
+            Some(StatusCode::PreconditionFailed)
+        );
+
+        //If-Modified-Since는 GET/HEAD에만
+        let headers = [("If-Modified-Since", "Sun, 13 Sep 2020 12:26:40 GMT")];
+        assert_eq!(preconditions(Method::Put, &headers), None);
+    }
+
+    #[test]
+    fn compares_etags() {
+        let cases = [
+            (ETAG, false, true),
+            (ETAG, true, true),
+            ("W/\"5f5e100-2a\"", false, false),
+            ("W/\"5f5e100-2a\"", true, true),
+            ("\"a\" , \"5f5e100-2a\"", false, true),
+            ("*", false, true),
+            ("\"5f5e100\"", true, false),
+            ("5f5e100-2a", true, false),
+        ];
+
+        for (value, weak, expected) in cases {
+            assert_eq!(
+                etag_matches([value].into_iter(), ETAG, weak),
+                expected,
+                "{} weak={}",
+                value,
+                weak
+            );
+        }
+        assert!(etag_matches(["\"a\"", ETAG].into_iter(), ETAG, false));
+    }
+
+    #[test]
+    fn matches_if_range_exactly() {
+        let validators = validators();
+        let cases = [
+            (ETAG, true),
+            ("W/\"5f5e100-2a\"", false),
+            ("\"other\"", false),
+            ("Sun, 13 Sep 2020 12:26:40 GMT", true),
+            (" Sun, 13 Sep 2020 12:26:40 GMT ", true),
+            ("Sun, 13 Sep 2020 12:26:41 GMT", false),
+            //다른 날짜 형식은 같은 시각이어도 정확히 같지 않음
+            ("Sunday, 13-Sep-20 12:26:40 GMT", false),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(if_range_matches(value, &validators), expected, "{}", value);
+        }
+
+        let unknown = Validators {
+            modified: None,
+            ..validators
+        };
+        assert!(!if_range_matches("Sun, 13 Sep 2020 12:26:40 GMT", &unknown));
+    }
+}
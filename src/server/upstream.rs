@@ -0,0 +1,440 @@
+use std::{
+    io::Result,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use tokio::runtime::Handle;
+
+use Rustify::http::{Request, Response, StatusCode};
+
+use crate::{
+    config::{Address, NextUpstream, ProxyTimeouts, UpstreamConfig, UpstreamServer},
+    server::{
+        balancer::{self, Context, LoadBalancer},
+        health::{Health, HealthTable},
+        proxy_body::RequestBody,
+        reverse_proxy::{ForwardContext, Pending, ReverseProxy},
+    },
+};
+
+//upstream 그룹의 서버 하나
+pub struct Backend {
+    proxy: ReverseProxy, //서버별 클라이언트 (커넥션 풀)
+    weight: u32,
+}
+
+impl Backend {
+    //health: 이 서버의 공유 상태, timeouts: 이 서버의 그룹으로 보내는 location들의 타임아웃
+    pub fn new(
+        server: &UpstreamServer,
+        health: Health,
+        config: &UpstreamConfig,
+        timeouts: &[ProxyTimeouts],
+    ) -> Result<Backend> {
+        Ok(Backend {
+            proxy: ReverseProxy::new(&server.addr, health, config, timeouts)?,
+            weight: server.weight,
+        })
+    }
+
+    pub fn addr(&self) -> &Address {
+        self.proxy.upstream()
+    }
+
+    pub fn weight(&self) -> u32 {
+        self.weight
+    }
+
+    //진행 중인 요청 수 (이 워커에서 보낸 것만)
+    pub fn active(&self) -> usize {
+        self.proxy.active()
+    }
+
+    //부하 분산 테스트에서 진행 중인 요청 수를 정해 둠
+    #[cfg(test)]
+    pub fn set_active(&self, count: usize) {
+        self.proxy.set_active(count);
+    }
+
+    //실패로 내려가 있거나 health_check에서 비정상이거나 회로가 열려 있으면 false (모든 워커가 같은 값을 봄)
+    pub fn is_available(&self) -> bool {
+        self.proxy.health().is_available()
+    }
+}
+
+/*
+ upstream 그룹으로 보낸 요청 하나
+ - 실패하면 proxy_next_upstream 조건에 따라 같은 그룹의 다른 서버로 다시 보냄
+ - drop하면 진행 중인 업스트림 요청도 취소됨
+*/
+pub struct Forward {
+    backend: usize,           //지금 요청을 보낸 서버
+    pending: Option<Pending>, //send()가 성공하면 항상 있음
+    request: Option<Request>, //다시 보낼 때 쓸 원본 (다시 보낼 수 없는 요청이면 None)
+    context: ForwardContext,
+    next_upstream: NextUpstream,
+    tried: Vec<usize>, //고른 적 있는 서버 (다시 고르지 않음)
+    tries: usize,      //실제로 보낸 횟수
+    started: Instant,
+}
+
+/*
+ upstream 그룹: 서버 목록 + 부하 분산 방식
+ - proxy_pass에 주소를 직접 쓴 location도 서버 하나(또는 호스트 이름이 풀린 주소들)짜리 그룹
+ - 워커마다 따로 가지므로 라운드 로빈 순서, 요청 수는 워커별
+ - 서버 상태(실패, health_check 결과)는 HealthTable로 모든 워커가 공유
+ - 서버가 하나뿐이면 실패해도 내리지 않음 (내리면 보낼 곳이 없음)
+*/
+pub struct Upstream {
+    name: String,
+    backends: Vec<Backend>,
+    balancer: Box<dyn LoadBalancer>,
+}
+
+impl Upstream {
+    /*
+     first: 이 그룹의 첫 서버가 health에서 차지하는 인덱스 (upstream 설정 순서로 센 전체 인덱스)
+     timeouts: 이 그룹으로 보내는 location들의 타임아웃 (서버마다 조합별 클라이언트를 만듦)
+    */
+    pub fn new(
+        config: &UpstreamConfig,
+        health: &HealthTable,
+        first: usize,
+        timeouts: &[ProxyTimeouts],
+    ) -> Result<Upstream> {
+        let single = config.servers.len() == 1;
+        let backends = config
+            .servers
+            .iter()
+            .enumerate()
+            .map(|(index, server)| {
+                let max_fails = if single { 0 } else { server.max_fails };
+                let health = health.get(
+                    first + index,
+                    max_fails,
+                    server.fail_timeout,
+                    config.circuit_breaker,
+                );
+                Backend::new(server, health, config, timeouts)
+            })
+            .collect::<Result<Vec<Backend>>>()?;
+        let balancer = balancer::new(&config.balance, &backends);
+
+        Ok(Upstream {
+            name: config.name.clone(),
+            backends,
+            balancer,
+        })
+    }
+
+    //health_check 작업 시작 (설정에 없으면 아무것도 안 함)
+    pub fn spawn_health_checks(&self, runtime: &Handle, config: &UpstreamConfig) {
+        if let Some(check) = &config.health_check {
+            for backend in &self.backends {
+                backend.proxy.spawn_health_check(runtime, check);
+            }
+        }
+    }
+
+    /*
+     부하 분산 방식으로 고른 서버에 요청 전달 (보낼 서버가 없으면 None)
+     - 다른 서버로 다시 보낼 수 있는 요청(can_retry)이면 원본을 보관
+    */
+    pub fn forward(
+        &self,
+        runtime: &Handle,
+        request: Request,
+        body: Option<RequestBody>,
+        context: ForwardContext,
+        next_upstream: &NextUpstream,
+    ) -> Option<Forward> {
+        let retry = can_retry(next_upstream, &request, body.is_some(), self.backends.len());
+
+        let mut forward = Forward {
+            backend: 0,
+            pending: None,
+            request: retry.then(|| request.clone()),
+            context,
+            next_upstream: next_upstream.clone(),
+            tried: Vec::new(),
+            tries: 0,
+            started: Instant::now(),
+        };
+
+        if !self.send(runtime, &mut forward, request, body) {
+            eprintln!("❌ No live upstreams in \"{}\"", self.name);
+            return None;
+        }
+
+        Some(forward)
+    }
+
+    /*
+     업스트림 응답 헤더가 도착했으면 결과 (아직이면 None)
+     - 연결 실패(502), 타임아웃(504)은 서버 실패로 세고, proxy_next_upstream의 상태 코드 응답도 실패로 셈
+     - 다시 보낼 조건에 맞고 횟수/시간이 남았으면 다음 서버로 보내고 계속 기다림
+     - 더 보낼 수 없으면 마지막 결과를 그대로 (상태 코드 응답이면 업스트림의 응답)
+    */
+    pub fn poll(
+        &self,
+        runtime: &Handle,
+        forward: &mut Forward,
+    ) -> Option<std::result::Result<Response, StatusCode>> {
+        loop {
+            let result = forward.pending.as_mut()?.poll()?;
+            let backend = &self.backends[forward.backend];
+            let policy = &forward.next_upstream;
+
+            //(서버 실패인지, 다시 보낼 조건인지)
+            let (failed, retryable) = match &result {
+                Ok(response) => {
+                    let listed = policy.statuses.contains(&response.status().as_u16());
+                    (listed, listed)
+                }
+                Err(StatusCode::BadGateway) => (true, policy.error),
+                Err(StatusCode::GatewayTimeout) => (true, policy.timeout),
+                Err(_) => return Some(result), //요청을 만들지 못함 (서버 탓이 아님)
+            };
+
+            let health = backend.proxy.health();
+            if !failed {
+                health.success(backend.addr());
+                return Some(result);
+            }
+            health.fail(backend.addr());
+
+            let Some(request) = retryable.then(|| self.next_request(forward)).flatten() else {
+                return Some(result);
+            };
+
+            let status = match &result {
+                Ok(response) => response.status(),
+                Err(status) => *status,
+            };
+            let addr = backend.addr().clone();
+            if !self.send(runtime, forward, request, None) {
+                return Some(result);
+            }
+            eprintln!(
+                "⚠️ Upstream {} failed ({}), retried on {}",
+                addr,
+                status.as_u16(),
+                self.backends[forward.backend].addr()
+            );
+        }
+    }
+
+    //다음 서버로 다시 보낼 요청 (보관한 원본이 없거나 횟수/시간을 다 썼으면 None)
+    fn next_request(&self, forward: &Forward) -> Option<Request> {
+        let request = forward.request.as_ref()?;
+        let elapsed = forward.started.elapsed();
+
+        has_budget(&forward.next_upstream, forward.tries, elapsed).then(|| request.clone())
+    }
+
+    /*
+     아직 고르지 않은 서버 중 하나를 고름 (고른 서버는 tried에 추가)
+     - 회로가 half-open인 서버는 다른 워커가 시험 요청 차례를 가져갔으면 건너뜀
+    */
+    fn select(
+        &self,
+        request: &Request,
+        client: Option<IpAddr>,
+        tried: &mut Vec<usize>,
+    ) -> Option<usize> {
+        loop {
+            let context = Context {
+                request,
+                client,
+                tried,
+            };
+            let index = self.balancer.select(&self.backends, &context)?;
+
+            tried.push(index);
+            if self.backends[index].proxy.health().acquire() {
+                return Some(index);
+            }
+        }
+    }
+
+    //아직 고르지 않은 서버 중 하나를 골라 보냄 (없으면 false)
+    fn send(
+        &self,
+        runtime: &Handle,
+        forward: &mut Forward,
+        request: Request,
+        body: Option<RequestBody>,
+    ) -> bool {
+        let client = forward.context.client_addr;
+        let Some(index) = self.select(&request, client, &mut forward.tried) else {
+            return false;
+        };
+
+        forward.backend = index;
+        forward.tries += 1;
+        forward.pending = Some(self.backends[index].proxy.forward(
+            runtime,
+            request,
+            body,
+            &forward.context,
+        ));
+        true
+    }
+}
+
+/*
+ 실패하면 다른 서버로 다시 보낼 수 있는 요청인지 (원본을 보관할지)
+ - proxy_next_upstream 조건이 하나라도 있고, 멱등 메서드이거나 non_idempotent
+ - 흘려보내는 요청 바디(streamed)는 보관할 수 없고, 서버가 하나면 다시 보낼 곳이 없음
+*/
+fn can_retry(policy: &NextUpstream, request: &Request, streamed: bool, backends: usize) -> bool {
+    (policy.error || policy.timeout || !policy.statuses.is_empty())
+        && (policy.non_idempotent || request.method().is_idempotent())
+        && !streamed
+        && backends > 1
+}
+
+//proxy_next_upstream_tries, proxy_next_upstream_timeout이 남았는지 (tries: 지금까지 보낸 횟수)
+fn has_budget(policy: &NextUpstream, tries: usize, elapsed: Duration) -> bool {
+    (policy.tries == 0 || tries < policy.tries)
+        && (policy.timeout_total.is_zero() || elapsed < policy.timeout_total)
+}
+
+#[cfg(test)]
+mod tests {
+    use Rustify::http::{HeaderMap, Method, Version};
+
+    use super::*;
+    use crate::config::{Balance, CircuitBreakerConfig, HttpConfig};
+
+    //서버 세 개 (127.0.0.1:8001부터), 요청은 보내지 않으므로 소켓을 열지 않음
+    fn upstream(circuit_breaker: Option<CircuitBreakerConfig>) -> Upstream {
+        let config = UpstreamConfig {
+            name: String::from("app"),
+            balance: Balance::RoundRobin,
+            servers: (0..3)
+                .map(|index| UpstreamServer {
+                    addr: Address::Tcp(([127, 0, 0, 1], 8001 + index).into()),
+                    weight: 1,
+                    max_fails: 0,
+                    fail_timeout: Duration::from_secs(10),
+                })
+                .collect(),
+            health_check: None,
+            circuit_breaker,
+            keepalive: 0,
+            keepalive_timeout: Duration::from_secs(60),
+        };
+        let http = HttpConfig {
+            upstreams: vec![config.clone()],
+            ..HttpConfig::default()
+        };
+
+        Upstream::new(&config, &HealthTable::new(&http).unwrap(), 0, &[]).unwrap()
+    }
+
+    fn request(method: Method) -> Request {
+        Request::new(method, "/", Version::Http11, HeaderMap::new())
+    }
+
+    #[test]
+    fn retries_only_idempotent_requests_by_default() {
+        let policy = NextUpstream::default();
+
+        for method in [Method::Get, Method::Head, Method::Put, Method::Delete] {
+            assert!(can_retry(&policy, &request(method), false, 3));
+        }
+        for method in [Method::Post, Method::Patch] {
+            assert!(!can_retry(&policy, &request(method), false, 3));
+        }
+
+        let policy = NextUpstream {
+            non_idempotent: true,
+            ..NextUpstream::default()
+        };
+        assert!(can_retry(&policy, &request(Method::Post), false, 3));
+        assert!(can_retry(&policy, &request(Method::Patch), false, 3));
+    }
+
+    #[test]
+    fn never_retries_streamed_bodies_or_single_servers() {
+        let policy = NextUpstream {
+            non_idempotent: true,
+            ..NextUpstream::default()
+        };
+        assert!(!can_retry(&policy, &request(Method::Post), true, 3));
+        assert!(!can_retry(&policy, &request(Method::Get), false, 1));
+
+        //proxy_next_upstream off
+        let off = NextUpstream {
+            error: false,
+            timeout: false,
+            ..NextUpstream::default()
+        };
+        assert!(!can_retry(&off, &request(Method::Get), false, 3));
+
+        let statuses = NextUpstream {
+            statuses: vec![502],
+            ..off
+        };
+        assert!(can_retry(&statuses, &request(Method::Get), false, 3));
+    }
+
+    #[test]
+    fn limits_tries_and_total_time() {
+        //0이면 제한 없음
+        let unlimited = NextUpstream::default();
+        assert!(has_budget(&unlimited, 100, Duration::from_secs(3600)));
+
+        let tries = NextUpstream {
+            tries: 2,
+            ..NextUpstream::default()
+        };
+        assert!(has_budget(&tries, 1, Duration::ZERO));
+        assert!(!has_budget(&tries, 2, Duration::ZERO));
+
+        let timeout = NextUpstream {
+            timeout_total: Duration::from_secs(5),
+            ..NextUpstream::default()
+        };
+        assert!(has_budget(&timeout, 10, Duration::from_millis(4999)));
+        assert!(!has_budget(&timeout, 10, Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn never_selects_a_tried_server_twice() {
+        let upstream = upstream(None);
+        let request = request(Method::Get);
+        let mut tried = Vec::new();
+
+        let mut selected: Vec<usize> = (0..3)
+            .map(|_| upstream.select(&request, None, &mut tried).unwrap())
+            .collect();
+        assert_eq!(upstream.select(&request, None, &mut tried), None);
+
+        selected.sort();
+        assert_eq!(selected, [0, 1, 2]);
+        assert_eq!(tried.len(), 3);
+    }
+
+    #[test]
+    fn skips_servers_with_open_circuit() {
+        let upstream = upstream(Some(CircuitBreakerConfig {
+            failures: 1,
+            timeout: Duration::from_secs(30),
+        }));
+        let request = request(Method::Get);
+        let backend = &upstream.backends[1];
+        backend.proxy.health().fail(backend.addr());
+
+        let mut tried = Vec::new();
+        let mut selected: Vec<usize> = (0..2)
+            .map(|_| upstream.select(&request, None, &mut tried).unwrap())
+            .collect();
+        assert_eq!(upstream.select(&request, None, &mut tried), None);
+
+        selected.sort();
+        assert_eq!(selected, [0, 2]);
+    }
+}
@@ -0,0 +1,128 @@
+use Rustify::http::StatusCode;
+
+/*
+ 요청 경로 정규화 (location 선택, 정적 파일, proxy_pass URI 치환에 모두 이 경로를 씀)
+ - %XX 디코딩 (잘못된 인코딩, NUL은 400)
+ - 빈 구성 요소와 . 은 건너뛰고 .. 은 앞 구성 요소를 지움 (root 위로 올라가면 400)
+ - /로 끝나는 경로(/. 과 /.. 으로 끝나는 경로 포함)는 정규화한 뒤에도 /로 끝남
+ - 디코딩한 바이트가 UTF-8이 아닐 수 있으므로 바이트로 돌려줌
+*/
+pub fn normalize(path: &str) -> Result<Vec<u8>, StatusCode> {
+    let decoded = percent_decode(path.as_bytes()).ok_or(StatusCode::BadRequest)?;
+    if !decoded.starts_with(b"/") || decoded.contains(&0) {
+        return Err(StatusCode::BadRequest);
+    }
+
+    let mut segments: Vec<&[u8]> = Vec::new();
+    for segment in decoded.split(|&b| b == b'/') {
+        match segment {
+            b"" | b"." => {}
+            b".." => {
+                segments.pop().ok_or(StatusCode::BadRequest)?;
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    let mut normalized = Vec::with_capacity(decoded.len());
+    for segment in &segments {
+        normalized.push(b'/');
+        normalized.extend_from_slice(segment);
+    }
+
+    let directory =
+        decoded.ends_with(b"/") || decoded.ends_with(b"/.") || decoded.ends_with(b"/..");
+    if directory || segments.is_empty() {
+        normalized.push(b'/');
+    }
+
+    Ok(normalized)
+}
+
+//업스트림에 보낼 경로로 다시 인코딩 (unreserved, sub-delims, : @ / 외에는 %XX)
+pub fn encode_path(path: &[u8]) -> String {
+    let mut out = String::with_capacity(path.len());
+
+    for &b in path {
+        if b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@/".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+
+    out
+}
+
+fn percent_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+
+    while i < input.len() {
+        if input[i] == b'%' {
+            let hex = input.get(i + 1..i + 3)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            out.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(input[i]);
+            i += 1;
+        }
+    }
+
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalized(path: &str) -> Result<String, StatusCode> {
+        normalize(path).map(|path| String::from_utf8(path).unwrap())
+    }
+
+    #[test]
+    fn decodes_and_removes_dot_segments() {
+        assert_eq!(normalized("/").as_deref(), Ok("/"));
+        assert_eq!(normalized("/a//b/./c").as_deref(), Ok("/a/b/c"));
+        assert_eq!(normalized("/%70rivate/x").as_deref(), Ok("/private/x"));
+        assert_eq!(normalized("/pub/../private/x").as_deref(), Ok("/private/x"));
+        assert_eq!(
+            normalized("/pub/%2e%2e/private/x").as_deref(),
+            Ok("/private/x")
+        );
+        assert_eq!(normalized("/a%20b").as_deref(), Ok("/a b"));
+    }
+
+    #[test]
+    fn keeps_trailing_slash() {
+        assert_eq!(normalized("/dir/").as_deref(), Ok("/dir/"));
+        assert_eq!(normalized("/dir/.").as_deref(), Ok("/dir/"));
+        assert_eq!(normalized("/dir/sub/..").as_deref(), Ok("/dir/"));
+        assert_eq!(normalized("/dir/..").as_deref(), Ok("/"));
+    }
+
+    #[test]
+    fn rejects_escaping_and_invalid_paths() {
+        for path in [
+            "/..",
+            "/a/../..",
+            "/%2e%2e/x",
+            "/%zz",
+            "/%4",
+            "/a%00b",
+            "a/b",
+        ] {
+            assert_eq!(normalize(path), Err(StatusCode::BadRequest), "{}", path);
+        }
+    }
+
+    #[test]
+    fn encodes_path_for_upstream() {
+        assert_eq!(encode_path(b"/a b/%/c"), "/a%20b/%25/c");
+        assert_eq!(encode_path("/h\u{e9}?#".as_bytes()), "/h%C3%A9%3F%23");
+        assert_eq!(encode_path(b"/a-b_c~d.e:f@g"), "/a-b_c~d.e:f@g");
+    }
+}
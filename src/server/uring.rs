@@ -0,0 +1,306 @@
+use std::{
+    collections::HashMap,
+    io::{ErrorKind, Result},
+    os::fd::{AsRawFd, RawFd},
+    time::{Duration, Instant},
+};
+
+use Rustify::{Completion, IoUring, Op, Token};
+
+use crate::server::{
+    connection::Connection,
+    notify::{NOTIFY_TOKEN, Notifications},
+    router::Router,
+    socket::Listener,
+    worker::quit_requested,
+};
+
+const RING_ENTRIES: u32 = 4096;
+
+//recv 버퍼 풀 (워커당 2048 * 4KB = 8MB)
+const BUFFER_COUNT: u16 = 2048;
+const BUFFER_SIZE: usize = 4 * 1024;
+
+pub fn create_ring() -> Result<IoUring> {
+    IoUring::new(RING_ENTRIES, BUFFER_COUNT, BUFFER_SIZE)
+}
+
+//연결 하나와 커널에 제출해 둔 작업 상태
+struct Slot {
+    connection: Connection,
+    recv_armed: bool, //multishot recv가 살아 있음
+    sending: bool,    //send 완료 대기 중 (그동안 쓰기 버퍼를 건드리면 안 됨)
+    closing: bool,    //shutdown 후 남은 작업이 끝나기를 기다리는 중
+}
+
+/*
+ io_uring 워커 이벤트 루프
+ - 리스너마다 multishot accept 하나(토큰 = 리스너 인덱스), 연결마다 multishot recv 하나를 걸어 두고 완료 이벤트로 구동
+ - 응답은 연결당 send 하나씩만 진행, 일부만 보내졌으면 완료 후 나머지를 다시 제출
+ - 알림 소켓에도 multishot recv를 걸어 두고, 완료되면 업스트림 응답이 진행된 연결들을 이어서 처리
+ - 연결 종료: shutdown으로 진행 중인 recv/send를 끝낸 뒤, 둘 다 완료되면 Connection을 drop해서 close
+   (커널이 아직 쓰기 버퍼를 읽고 있을 수 있으므로 바로 drop하지 않음)
+ - 1초마다 타임아웃이 지난 연결 정리
+ - graceful 종료: accept를 취소하고 리스너를 닫은 뒤 연결을 모두 drain, 다 닫히거나 기한이 지나면 리턴
+*/
+pub fn run_event_loop(
+    id: usize,
+    mut ring: IoUring,
+    mut listeners: Vec<Listener>,
+    router: &Router,
+    notifications: &Notifications,
+) -> Result<()> {
+    let config = router.config();
+
+    for (index, tcp_listener) in listeners.iter().enumerate() {
+        //논블로킹 리스너여도 io_uring이 내부에서 poll로 기다렸다가 accept (accept 대기는 커널이 exclusive로 걸어서 한 워커만 깨어남)
+        ring.accept_multishot(tcp_listener.as_raw_fd(), Token(index as u64))?;
+    }
+    ring.recv_multishot(notifications.as_raw_fd(), NOTIFY_TOKEN)?;
+
+    let mut slots: HashMap<Token, Slot> = HashMap::new();
+    let mut next_token: u64 = listeners.len() as u64;
+
+    let mut completions: Vec<Completion> = Vec::with_capacity(256);
+    let mut last_sweep: Instant = Instant::now();
+    let mut shutdown_deadline: Option<Instant> = None;
+
+    loop {
+        match ring.wait(&mut completions, Some(Duration::from_secs(1))) {
+            Ok(_) => {}
+            //신호로 깨어남 (완료 없음), 아래에서 종료 요청 확인
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+
+        if quit_requested() && shutdown_deadline.is_none() {
+            println!(
+                "👷 Worker {} shutting down gracefully ({} connections)",
+                id + 1,
+                slots.len()
+            );
+            shutdown_deadline = Some(Instant::now() + config.worker_shutdown_timeout);
+
+            //accept를 취소하고 리스너를 닫아서 새 연결은 다른 워커(새 세대)로 가게 함
+            for index in 0..listeners.len() {
+                ring.cancel(Token(index as u64), Op::Accept)?;
+            }
+            listeners.clear();
+
+            let tokens: Vec<Token> = slots.keys().copied().collect();
+            for token in tokens {
+                if let Some(slot) = slots.get_mut(&token) {
+                    slot.connection.drain();
+                }
+                drive(&mut ring, &mut slots, token, router)?;
+            }
+        }
+
+        if let Some(deadline) = shutdown_deadline {
+            if slots.is_empty() {
+                println!("👷 Worker {} exited", id + 1);
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                eprintln!(
+                    "⚠️ Worker {} shutdown timed out, closing {} connections",
+                    id + 1,
+                    slots.len()
+                );
+                return Ok(());
+            }
+        }
+
+        let mut batch_count = 0;
+
+        for completion in completions.iter() {
+            let token = completion.token();
+
+            match completion.op() {
+                Op::Accept => {
+                    let listener = token.0 as usize;
+
+                    //종료 중: 취소 완료이거나 취소 직전에 받은 연결이면 바로 닫음
+                    if listeners.is_empty() {
+                        if let Ok(fd) = completion.result() {
+                            unsafe { libc::close(fd as RawFd) };
+                        }
+                        continue;
+                    }
+
+                    if !completion.has_more() {
+                        ring.accept_multishot(listeners[listener].as_raw_fd(), token)?;
+                    }
+
+                    match completion.result() {
+                        Ok(fd) => {
+                            //accept된 fd는 이제 이 워커 소유
+                            let stream =
+                                unsafe { listeners[listener].stream_from_raw_fd(fd as RawFd) };
+
+                            if slots.len() >= config.events.worker_connections {
+                                eprintln!(
+                                    "⚠️ Worker {}: {} worker_connections are not enough",
+                                    id + 1,
+                                    config.events.worker_connections
+                                );
+                                continue; //stream drop → 연결 종료
+                            }
+
+                            batch_count += 1;
+                            if config.listeners[listener].tcp_nodelay {
+                                let _ = stream.set_nodelay(true);
+                            }
+
+                            let token = Token(next_token);
+                            next_token += 1;
+
+                            slots.insert(
+                                token,
+                                Slot {
+                                    connection: Connection::new(
+                                        stream,
+                                        token,
+                                        listener,
+                                        &config.http,
+                                    ),
+                                    recv_armed: false,
+                                    sending: false,
+                                    closing: false,
+                                },
+                            );
+                            drive(&mut ring, &mut slots, token, router)?;
+                        }
+                        Err(e) => eprintln!("❌ Worker {} accept error: {}", id + 1, e),
+                    }
+                }
+                Op::Recv if token == NOTIFY_TOKEN => {
+                    //깨우기 바이트는 내용이 필요 없음
+                    if let Some(buffer) = completion.buffer() {
+                        ring.recycle(buffer)?;
+                    }
+                    if !completion.has_more() {
+                        ring.recv_multishot(notifications.as_raw_fd(), NOTIFY_TOKEN)?;
+                    }
+
+                    for token in notifications.take() {
+                        if let Some(slot) = slots.get_mut(&token)
+                            && !slot.closing
+                        {
+                            slot.connection.poll_upstream(router);
+                            drive(&mut ring, &mut slots, token, router)?;
+                        }
+                    }
+                }
+                Op::Recv => {
+                    let Some(slot) = slots.get_mut(&token) else {
+                        if let Some(buffer) = completion.buffer() {
+                            ring.recycle(buffer)?;
+                        }
+                        continue;
+                    };
+
+                    if !completion.has_more() {
+                        slot.recv_armed = false;
+                    }
+
+                    match completion.result() {
+                        Ok(n) => {
+                            let data = match completion.buffer() {
+                                Some(buffer) => ring.buffer(buffer, n),
+                                None => &[],
+                            };
+                            slot.connection.on_received(data, router);
+
+                            if let Some(buffer) = completion.buffer() {
+                                ring.recycle(buffer)?;
+                            }
+                        }
+                        //버퍼 풀이 바닥남: drive()에서 recv를 다시 제출
+                        Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {}
+                        Err(_) => slot.connection.close(),
+                    }
+
+                    drive(&mut ring, &mut slots, token, router)?;
+                }
+                Op::Send => {
+                    let Some(slot) = slots.get_mut(&token) else {
+                        continue;
+                    };
+                    slot.sending = false;
+
+                    match completion.result() {
+                        Ok(n) => slot.connection.on_sent(n),
+                        Err(_) => slot.connection.close(),
+                    }
+
+                    drive(&mut ring, &mut slots, token, router)?;
+                }
+            }
+        }
+
+        if batch_count > 0 {
+            println!("👷 Worker {} completed batch of {}", id + 1, batch_count);
+        }
+
+        //타임아웃 지난 연결 정리 (유휴 keep-alive, 느린 클라이언트)
+        let now = Instant::now();
+        if now.duration_since(last_sweep) >= Duration::from_secs(1) {
+            last_sweep = now;
+
+            let expired: Vec<Token> = slots
+                .iter()
+                .filter(|(_, slot)| !slot.closing && slot.connection.is_expired(now, &config.http))
+                .map(|(token, _)| *token)
+                .collect();
+
+            for token in expired {
+                if let Some(slot) = slots.get_mut(&token) {
+                    slot.connection.close();
+                }
+                drive(&mut ring, &mut slots, token, router)?;
+            }
+        }
+    }
+}
+
+/*
+ 완료 이벤트를 처리한 뒤 연결의 다음 작업 제출
+ - 보낼 응답이 있고 send가 진행 중이 아니면 send 제출
+ - recv가 끝났고 클라이언트가 아직 열려 있으면 다시 recv 제출
+ - 닫아야 하면 shutdown, 진행 중인 작업이 모두 끝났으면 drop
+*/
+fn drive(
+    ring: &mut IoUring,
+    slots: &mut HashMap<Token, Slot>,
+    token: Token,
+    router: &Router,
+) -> Result<()> {
+    let Some(slot) = slots.get_mut(&token) else {
+        return Ok(());
+    };
+    let fd = slot.connection.as_raw_fd();
+
+    if !slot.closing && !slot.sending && slot.connection.poll_output(router) {
+        //안전성: sending 동안에는 poll_output()/on_received()가 쓰기 버퍼를 바꾸지 않음
+        unsafe { ring.send(fd, token, slot.connection.output())? };
+        slot.sending = true;
+    }
+
+    if !slot.closing && slot.connection.is_closed() {
+        slot.closing = true;
+        //진행 중인 recv는 0으로, send는 에러로 끝남
+        unsafe { libc::shutdown(fd, libc::SHUT_RDWR) };
+    }
+
+    if !slot.closing && !slot.recv_armed && !slot.connection.is_peer_closed() {
+        ring.recv_multishot(fd, token)?;
+        slot.recv_armed = true;
+    }
+
+    if slot.closing && !slot.recv_armed && !slot.sending {
+        slots.remove(&token);
+    }
+
+    Ok(())
+}
@@ -1,185 +1,251 @@
 use std::{
-    io::{Error, ErrorKind, Result, Write},
-    mem::zeroed,
-    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
-    os::fd::{AsRawFd, FromRawFd, RawFd},
-    time::Duration,
-    u32,
+    collections::HashMap,
+    io::{ErrorKind, Result},
+    os::fd::AsRawFd,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
 };
 
-#[cfg(target_os = "macos")]
-use Rustify::Kqueue;
+use Rustify::{DefaultPoller, Events, Interest, Poller, Token};
+
+use crate::{
+    config::{Config, ListenConfig},
+    server::{
+        connection::Connection,
+        health::HealthTable,
+        notify::{self, NOTIFY_TOKEN, Notifications},
+        router::Router,
+        socket::{Listener, Stream},
+    },
+};
 
 #[cfg(target_os = "linux")]
-use Rustify::Epoll;
+use crate::{config::AcceptStrategy, utils::system};
 
-use Rustify::ThreadPool;
+#[cfg(all(target_os = "linux", feature = "io-uring"))]
+use crate::{config::EventMethod, server::uring};
 
-use libc::{
-    AF_INET, SO_REUSEADDR, SO_REUSEPORT, SOL_SOCKET, bind, c_void, close, sa_family_t, sockaddr_in,
-    socklen_t,
-};
+use libc::{SIGQUIT, c_int, sighandler_t};
 
-#[cfg(target_os = "macos")]
-use libc::kevent;
+//SIGQUIT(graceful 종료)을 받았는지 (핸들러 안에서는 원자적 저장만 함)
+static QUIT_REQUESTED: AtomicBool = AtomicBool::new(false);
 
-#[cfg(target_os = "linux")]
-use libc::epoll_event;
+extern "C" fn request_quit(_signal: c_int) {
+    QUIT_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+//graceful 종료 요청 여부 (이벤트 루프가 매 반복마다 확인, 대기 중이던 poll은 신호로 깸)
+pub fn quit_requested() -> bool {
+    QUIT_REQUESTED.load(Ordering::SeqCst)
+}
 
 /*
-  SO_REUSEPORT 소켓 생성
+ 워커 프로세스 진입점
+ - 마스터가 만든 리스너 중 이 워커 몫(config.listeners와 같은 순서)을 복제해 받아서 이벤트 루프 실행
+   (graceful 종료 시 복제본만 닫으므로 마스터와 다른 워커는 계속 accept)
+ - reuseport steer=cpu: 자기 소켓으로 연결이 분배되는 CPU에 프로세스를 고정
+ - 이벤트 루프: events { use ...; } 설정에 따라 Poller(Linux: epoll, macOS: kqueue) 또는 io_uring
+ - proxy_pass 업스트림 작업은 다른 스레드에서 끝나므로 알림 소켓으로 이벤트 루프를 깨움
+ - SIGTERM/SIGINT: 기본 동작으로 즉시 종료 (fast stop)
+ - SIGQUIT: 리스너를 닫고 처리 중인 연결을 worker_shutdown_timeout까지 마무리한 뒤 종료 (graceful)
 */
-pub fn create_reusable_listener(addr: &str) -> Result<TcpListener> {
-    let addr: SocketAddr = addr.parse().unwrap();
-
-    //소켓 생성
-    let sockfd: i32 = unsafe { libc::socket(libc::AF_INET, libc::SOCK_STREAM, 0) };
-
-    if sockfd < 0 {
-        return Err(Error::last_os_error());
-    }
+pub fn start_worker_process(
+    id: usize,
+    parent_pid: i32,
+    config: &Config,
+    health: &HealthTable,
+    listeners: Vec<Listener>,
+) -> Result<()> {
+    let pid: i32 = unsafe { libc::getpid() };
+    println!(
+        "👷 Worker {} started (PID: {},  Parent PID={})",
+        id + 1,
+        pid,
+        parent_pid
+    );
 
-    //SO_REUSEADDR 설정
-    let optval: libc::c_int = 1;
-    unsafe {
-        libc::setsockopt(
-            sockfd,
-            SOL_SOCKET,
-            SO_REUSEADDR,
-            &optval as *const _ as *const c_void,
-            size_of::<libc::c_int>() as socklen_t,
-        );
-    }
+    unsafe { libc::signal(SIGQUIT, request_quit as *const () as sighandler_t) };
 
-    //SO_REUSEPORT 설정(여러 프로세스가 같은 포트 사용)
-    unsafe {
-        libc::setsockopt(
-            sockfd,
-            SOL_SOCKET,
-            SO_REUSEPORT,
-            &optval as *const _ as *const c_void,
-            size_of::<libc::c_int>() as socklen_t,
-        );
+    #[cfg(target_os = "linux")]
+    if config.events.accept_strategy == (AcceptStrategy::Reuseport { steer_by_cpu: true }) {
+        //CBPF 프로그램이 CPU c의 연결을 c % 워커 수 번째 소켓으로 보냄 (CPU보다 워커가 많으면 남는 워커는 고정하지 않음)
+        let cpus: Vec<usize> = (0..system::get_cpu_count())
+            .filter(|cpu| cpu % config.worker_processes == id)
+            .collect();
+        if !cpus.is_empty()
+            && let Err(e) = system::set_cpu_affinity(&cpus)
+        {
+            eprintln!("⚠️ Worker {} failed to set CPU affinity: {}", id + 1, e);
+        }
     }
 
-    //bind
-    let sockaddr: sockaddr_in = {
-        let mut sa: sockaddr_in = unsafe { zeroed::<sockaddr_in>() };
-        sa.sin_family = AF_INET as sa_family_t;
-        sa.sin_port = addr.port().to_be();
+    let (notifier, notifications) = notify::channel()?;
+    let router = Router::new(config.clone(), notifier, health)?;
 
-        match addr.ip() {
-            IpAddr::V4(ip) => {
-                sa.sin_addr.s_addr = u32::from_ne_bytes(ip.octets());
+    //io_uring을 선택했으면 그쪽 이벤트 루프로 (커널이 지원하지 않으면 epoll로 대체)
+    #[cfg(all(target_os = "linux", feature = "io-uring"))]
+    if config.events.method == EventMethod::IoUring {
+        match uring::create_ring() {
+            Ok(ring) => {
+                return uring::run_event_loop(id, ring, listeners, &router, &notifications);
             }
-
-            _ => panic!("Ipv4만 지원됩니다!"),
+            Err(e) => eprintln!(
+                "⚠️ Worker {} io_uring unavailable ({}), falling back to epoll",
+                id + 1,
+                e
+            ),
         }
-
-        sa
-    };
-
-    let ret = unsafe {
-        bind(
-            sockfd,
-            &sockaddr as *const libc::sockaddr_in as *const libc::sockaddr,
-            std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
-        )
-    };
-
-    if ret < 0 {
-        unsafe { close(sockfd) };
-        return Err(Error::last_os_error());
-    }
-
-    //listen
-    let ret = unsafe { libc::listen(sockfd, 15000) };
-
-    if ret < 0 {
-        unsafe { close(sockfd) };
-        return Err(Error::last_os_error());
     }
 
-    Ok(unsafe { TcpListener::from_raw_fd(sockfd) })
+    let poller: DefaultPoller = DefaultPoller::new()?;
+    run_event_loop(id, &poller, listeners, &router, &notifications)
 }
 
-pub fn start_worker_process(id: usize, parent_pid: i32) -> Result<()> {
-    #[cfg(target_os = "macos")]
-    {
-        start_worker_process_kqueue(id, parent_pid)
-    }
-
-    #[cfg(target_os = "linux")]
-    {
-        start_worker_process_epoll(id, parent_pid)
+/*
+ 워커 이벤트 루프 (플랫폼 공통)
+ - 토큰 0..리스너 수는 리스너, 나머지는 연결마다 증가하는 id (fd는 close 후 재사용되므로 토큰으로 쓰지 않음)
+ - 리스너 이벤트: accept 가능한 연결을 모두 받아서 논블로킹으로 등록 (worker_connections를 넘으면 바로 닫음)
+ - 클라이언트 이벤트: Connection 상태 머신에 넘겨서 읽기/쓰기 진행
+ - 알림 이벤트(NOTIFY_TOKEN): 업스트림 응답이 진행된 연결들을 이어서 처리
+ - 감시 이벤트는 연결 상태에 따라 전환 (요청 수신 READABLE, 응답 전송 WRITABLE, 업스트림 대기 READ_CLOSED)
+ - 1초마다 타임아웃이 지난 연결 정리
+ - graceful 종료: 리스너를 닫고(더 이상 accept하지 않음) 연결을 모두 drain, 다 닫히거나 기한이 지나면 리턴
+*/
+pub fn run_event_loop<P: Poller>(
+    id: usize,
+    poller: &P,
+    mut listeners: Vec<Listener>,
+    router: &Router,
+    notifications: &Notifications,
+) -> Result<()> {
+    let config = router.config();
+
+    for (index, tcp_listener) in listeners.iter().enumerate() {
+        /*
+         공유 리스너에 연결이 오면 기다리는 워커 중 하나만 깨움 (thundering herd 방지)
+         - reuseport로 이 워커만 쓰는 소켓은 기다리는 곳이 하나뿐이므로 일반 등록
+        */
+        let shared = config
+            .events
+            .accept_strategy
+            .sockets_per_listener(&config.listeners[index].addr, config.worker_processes)
+            == 1;
+        let interest = if shared {
+            Interest::READABLE | Interest::EXCLUSIVE
+        } else {
+            Interest::READABLE
+        };
+        poller.register(tcp_listener.as_raw_fd(), Token(index as u64), interest)?; //소켓 fd를 커널에 등록
     }
-}
+    poller.register(notifications.as_raw_fd(), NOTIFY_TOKEN, Interest::READABLE)?;
 
-// ============= macOS (kqueue) 워커 구현 =============
-#[cfg(target_os = "macos")]
-pub fn start_worker_process_kqueue(id: usize, parent_pid: i32) -> Result<()> {
-    use crate::config::host::HOST_ADDR;
+    //토큰 → 연결 상태
+    let mut connections: HashMap<Token, Connection> = HashMap::new();
+    let mut next_token: u64 = listeners.len() as u64;
 
-    let pid: i32 = unsafe { libc::getpid() };
-    println!(
-        "👷 Worker {} started (PID: {},  Parent PID={})",
-        id + 1,
-        pid,
-        parent_pid
-    );
+    let mut events: Events = Events::with_capacity(128);
+    let mut last_sweep: Instant = Instant::now();
+    let mut shutdown_deadline: Option<Instant> = None;
 
-    //각 Worker가 자체 리스너 생성(SO_REUSEPORT 덕분)
-    let tcp_listener: TcpListener = create_reusable_listener(HOST_ADDR)?;
-    tcp_listener.set_nonblocking(true)?;
+    loop {
+        match poller.poll(&mut events, Some(Duration::from_secs(1))) {
+            Ok(_) => {}
+            //신호로 깨어남 (이벤트 없음), 아래에서 종료 요청 확인
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
 
-    //각 Worker가 자체 kqueue 생성
-    let kqueue: Kqueue = Kqueue::new()?;
-    let listener_fd: i32 = tcp_listener.as_raw_fd();
-    kqueue.add(listener_fd)?; //소켓 fd를 커널에 등록
+        if quit_requested() && shutdown_deadline.is_none() {
+            println!(
+                "👷 Worker {} shutting down gracefully ({} connections)",
+                id + 1,
+                connections.len()
+            );
+            shutdown_deadline = Some(Instant::now() + config.worker_shutdown_timeout);
+
+            //리스너를 닫아서 새 연결은 다른 워커(새 세대)로 가게 함
+            for tcp_listener in listeners.drain(..) {
+                let _ = poller.deregister(tcp_listener.as_raw_fd());
+            }
 
-    //각 worker당 스레드풀 생성
-    let pool: ThreadPool = ThreadPool::build(100);
+            let idle: Vec<Token> = connections
+                .iter_mut()
+                .filter_map(|(token, connection)| {
+                    connection.drain();
+                    connection.is_closed().then_some(*token)
+                })
+                .collect();
+            for token in idle {
+                close_connection(poller, &mut connections, token);
+            }
+        }
 
-    let mut events: Vec<kevent> = vec![unsafe { std::mem::zeroed::<libc::kevent>() }; 128];
-    let mut total = 0;
+        if let Some(deadline) = shutdown_deadline {
+            if connections.is_empty() {
+                println!("👷 Worker {} exited", id + 1);
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                eprintln!(
+                    "⚠️ Worker {} shutdown timed out, closing {} connections",
+                    id + 1,
+                    connections.len()
+                );
+                return Ok(());
+            }
+        }
 
-    loop {
-        let event_count: usize = kqueue.wait(&mut events)?;
+        for event in events.iter() {
+            let token = event.token();
 
-        for i in 0..event_count {
-            let event: &kevent = &events[i];
-            let fd = event.udata as RawFd;
+            if token == NOTIFY_TOKEN {
+                for token in notifications.drain() {
+                    let Some(connection) = connections.get_mut(&token) else {
+                        continue; //이미 닫힌 연결
+                    };
+                    let interest = connection.interest();
 
-            println!("fd = {}, listener_fd = {}", fd, listener_fd);
+                    connection.on_upstream(router);
+                    update_interest(poller, &mut connections, token, interest);
+                }
+                continue;
+            }
 
-            if fd == listener_fd {
+            if let Some(tcp_listener) = listeners.get(token.0 as usize) {
+                let listener = token.0 as usize;
                 let mut batch_count = 0;
 
                 //하나의 워커 프로세스가 현재 이벤트 큐에 있는 연결을 모두 처리
                 loop {
-                    match tcp_listener.accept() {
+                    match accept(tcp_listener, &config.listeners[listener]) {
                         //해당 소켓의 accept queue에서 가져옴
-                        Ok((stream, _addr)) => {
+                        Ok(stream) => {
+                            if connections.len() >= config.events.worker_connections {
+                                eprintln!(
+                                    "⚠️ Worker {}: {} worker_connections are not enough",
+                                    id + 1,
+                                    config.events.worker_connections
+                                );
+                                continue; //stream drop → 연결 종료
+                            }
+
                             batch_count += 1;
-                            total += 1;
 
-                            if total % 100 == 0 {
-                                // println!(
-                                //     "👷 Worker {} processed {} total (batch: {})",
-                                //     id, total, batch_count
-                                // );
-                            }
+                            let token = Token(next_token);
+                            next_token += 1;
 
-                            pool.execute(move || {
-                                // println!("pid : {} 워커 프로세스에서 http 연결 처리", pid);
-                                handle_connection(&stream);
-                            });
+                            let connection = Connection::new(stream, token, listener, &config.http);
+                            if let Err(e) =
+                                register_connection(poller, &mut connections, token, connection)
+                            {
+                                eprintln!("❌ Worker {} register error: {}", id + 1, e);
+                            }
                         }
                         Err(e) if e.kind() == ErrorKind::WouldBlock => {
                             break;
                         }
                         Err(e) => {
-                            eprintln!("❌ Worker {} accept error: {}", id, e);
+                            eprintln!("❌ Worker {} accept error: {}", id + 1, e);
                             break;
                         }
                     }
@@ -189,96 +255,112 @@ pub fn start_worker_process_kqueue(id: usize, parent_pid: i32) -> Result<()> {
                 if batch_count > 0 {
                     println!("👷 Worker {} completed batch of {}", id + 1, batch_count);
                 }
+                continue;
             }
-        }
-    }
-}
 
-// ============= Linux (epoll) 워커 구현 =============
-#[cfg(target_os = "linux")]
-pub fn start_worker_process_epoll(id: usize, parent_pid: i32) -> Result<()> {
-    let pid: i32 = unsafe { libc::getpid() };
-    println!(
-        "👷 Worker {} started (PID: {},  Parent PID={})",
-        id + 1,
-        pid,
-        parent_pid
-    );
+            let Some(connection) = connections.get_mut(&token) else {
+                continue;
+            };
+            let interest = connection.interest();
 
-    //각 Worker가 자체 리스너 생성(SO_REUSEPORT 덕분)
-    let tcp_listener: TcpListener = create_reusable_listener("HOST_ADDR")?;
-    tcp_listener.set_nonblocking(true)?;
+            //HUP/ERR도 read/write에서 에러로 드러나므로 같은 경로로 처리
+            let failed = event.is_hangup() || event.is_error();
+            if event.is_readable() || failed {
+                connection.on_readable(router);
+            }
+            if event.is_writable() || failed {
+                connection.on_writable(router);
+            }
+            if event.is_read_closed() || failed {
+                connection.on_read_closed();
+            }
 
-    //각 Worker가 자체 epoll 생성
-    let epoll: Epoll = Epoll::new()?;
-    let listener_fd: i32 = tcp_listener.as_raw_fd();
-    epoll.add(listener_fd)?; //소켓 fd를 커널에 등록
+            update_interest(poller, &mut connections, token, interest);
+        }
 
-    //각 worker당 스레드풀 생성
-    let pool: ThreadPool = ThreadPool::build(100);
+        //타임아웃 지난 연결 정리 (유휴 keep-alive, 느린 클라이언트)
+        let now = Instant::now();
+        if now.duration_since(last_sweep) >= Duration::from_secs(1) {
+            last_sweep = now;
 
-    let mut events: Vec<epoll_event> =
-        vec![unsafe { std::mem::zeroed::<libc::epoll_event>() }; 128];
-    let mut total = 0;
+            let expired: Vec<Token> = connections
+                .iter()
+                .filter(|(_, connection)| connection.is_expired(now, &config.http))
+                .map(|(token, _)| *token)
+                .collect();
 
-    loop {
-        let event_count: usize = epoll.wait(&mut events)?;
+            for token in expired {
+                close_connection(poller, &mut connections, token);
+            }
+        }
+    }
+}
 
-        for i in 0..event_count {
-            let event: &epoll_event = &events[i];
-            let fd = event.u64 as RawFd;
+//연결 하나를 받아서 listen 옵션대로 논블로킹, TCP_NODELAY 설정
+fn accept(listener: &Listener, listen: &ListenConfig) -> Result<Stream> {
+    #[cfg(target_os = "linux")]
+    let stream = if listen.accept4 {
+        listener.accept4()?
+    } else {
+        let stream = listener.accept()?;
+        stream.set_nonblocking(true)?;
+        stream
+    };
 
-            println!("fd = {}, listenr_fd = {}", fd, listener_fd);
+    #[cfg(not(target_os = "linux"))]
+    let stream = {
+        let stream = listener.accept()?;
+        stream.set_nonblocking(true)?;
+        stream
+    };
 
-            if fd == listener_fd {
-                let mut batch_count = 0;
+    if listen.tcp_nodelay {
+        stream.set_nodelay(true)?;
+    }
 
-                //하나의 워커 프로세스가 현재 이벤트 큐에 있는 연결을 모두 처리
-                loop {
-                    match tcp_listener.accept() {
-                        //해당 소켓의 accept queue에서 가져옴
-                        Ok((stream, _addr)) => {
-                            batch_count += 1;
-                            total += 1;
+    Ok(stream)
+}
 
-                            if total % 100 == 0 {
-                                // println!(
-                                //     "👷 Worker {} processed {} total (batch: {})",
-                                //     id, total, batch_count
-                                // );
-                            }
+fn register_connection<P: Poller>(
+    poller: &P,
+    connections: &mut HashMap<Token, Connection>,
+    token: Token,
+    connection: Connection,
+) -> Result<()> {
+    poller.register(connection.as_raw_fd(), token, Interest::READABLE)?;
+    connections.insert(token, connection);
 
-                            pool.execute(move || {
-                                // println!("pid : {} 워커 프로세스에서 http 연결 처리", pid);
-                                handle_connection(&stream);
-                            });
-                        }
-                        Err(e) if e.kind() == ErrorKind::WouldBlock => {
-                            break;
-                        }
-                        Err(e) => {
-                            eprintln!("❌ Worker {} accept error: {}", id, e);
-                            break;
-                        }
-                    }
-                }
+    Ok(())
+}
 
-                // batch 처리가 끝났으면 로그 출력
-                if batch_count > 0 {
-                    println!("👷 Worker {} completed batch of {}", id + 1, batch_count);
-                }
-            }
-        }
+//처리 후 닫힌 연결은 정리하고, 감시 이벤트가 바뀌었으면 다시 등록 (before: 처리 전 감시 이벤트)
+fn update_interest<P: Poller>(
+    poller: &P,
+    connections: &mut HashMap<Token, Connection>,
+    token: Token,
+    before: Interest,
+) {
+    let Some(connection) = connections.get(&token) else {
+        return;
+    };
+
+    let closed = connection.is_closed()
+        || (connection.interest() != before
+            && poller
+                .reregister(connection.as_raw_fd(), token, connection.interest())
+                .is_err());
+    if closed {
+        close_connection(poller, connections, token);
     }
 }
 
-fn handle_connection(mut stream: &TcpStream) {
-    // println!("커넥션 핸들러 실행!");
-    stream
-        .set_write_timeout(Some(Duration::from_secs(5)))
-        .unwrap();
-
-    let response = "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHello";
-    stream.write_all(response.as_bytes()).unwrap();
-    stream.flush().unwrap();
+//Poller에서 해제한 뒤 Connection을 drop해서 소켓 close
+fn close_connection<P: Poller>(
+    poller: &P,
+    connections: &mut HashMap<Token, Connection>,
+    token: Token,
+) {
+    if let Some(connection) = connections.remove(&token) {
+        let _ = poller.deregister(connection.as_raw_fd());
+    }
 }
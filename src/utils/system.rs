@@ -5,3 +5,18 @@ pub fn get_cpu_count() -> usize {
     let count: i64 = unsafe { sysconf(_SC_NPROCESSORS_ONLN) };
     if count > 0 { count as usize } else { 4 }
 }
+
+//현재 프로세스를 cpus에 있는 CPU에서만 실행되게 고정
+#[cfg(target_os = "linux")]
+pub fn set_cpu_affinity(cpus: &[usize]) -> std::io::Result<()> {
+    let mut set: cpu_set_t = unsafe { std::mem::zeroed() };
+    for &cpu in cpus {
+        unsafe { CPU_SET(cpu, &mut set) };
+    }
+
+    if unsafe { sched_setaffinity(0, size_of::<cpu_set_t>(), &set) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    Ok(())
+}